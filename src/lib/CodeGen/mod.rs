@@ -0,0 +1,830 @@
+use std::collections::HashMap;
+
+pub mod phi_resolver;
+
+use lib::Graph::node::{Node, NodeType};
+use lib::Graph::{block_successors, branch_targets, control_flow_graph, fall_through_successor, is_phi_x_side};
+use lib::IR::array_manager::ArrayManager;
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::MAIN_NAME;
+use lib::RegisterAllocator::RegisterAllocation;
+use lib::Utility::dlx::*;
+
+use super::petgraph;
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::DfsPostOrder;
+use petgraph::{Directed, Graph, Incoming};
+
+// Registers the allocator never hands out (it colors R1 and up). Everything
+// it does hand out is callee-saved, so a call only clobbers the registers
+// from here on and values live across a call can stay where they are.
+const MAX_ALLOCATED_REG: i32 = 24;
+const SCRATCH_X: i32 = 25;
+const SCRATCH_Y: i32 = 26;
+// Matches the R_27 used by the IR for function results.
+const RET_REG: i32 = 27;
+const FRAME_REG: i32 = 28;
+const STACK_REG: i32 = 29;
+// The emulator points R30 at the top of memory before running.
+const GLOBAL_REG: i32 = 30;
+const RA_REG: i32 = 31;
+
+/// Source of a value while lowering, either a register or a constant
+/// that still has to be encoded (or materialized) by the instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Reg(i32),
+    Imm(i32),
+}
+
+/// Lowers the register allocated graph into a DLX program image. Main is
+/// laid out first (so execution starts at address 0), followed by each
+/// function, and every jump and call is patched once all blocks are placed.
+pub fn generate_program(
+    irgm: &mut IRGraphManager,
+    register_map: HashMap<usize, RegisterAllocation>,
+) -> Result<Vec<i32>, String> {
+    // Only control flow edges matter here, dominance edges may already be in the graph.
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+
+    let main_entry = irgm.graph_manager_ref().get_main_entrance_node();
+
+    let mut functions = Vec::new();
+    for (func_name, func_root) in irgm.function_manager().list_functions() {
+        let entry_id = graph
+            .neighbors_directed(func_root, Incoming)
+            .next()
+            .ok_or_else(|| format!("Function {} has no entrance node.", func_name))?;
+        functions.push((func_name, entry_id));
+    }
+    functions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut layout = MemoryLayout::new(irgm.array_manager().clone());
+    for (func_name, _) in &functions {
+        let params = irgm.function_manager().get_function(func_name).load_param_list();
+        layout.params.insert(func_name.clone(), params);
+    }
+
+    let mut generator = CodeGenerator::new(graph, register_map, layout);
+    generator.generate(main_entry, functions)
+}
+
+/// Placement of everything the IR addresses by name. Globals grow down
+/// from R30. Every call gets a frame on a stack that grows up from the end
+/// of the program, R29 pointing at its last used word and R28 at its start:
+///
+/// ```text
+///     FP -> param 0 .. param n-1    (written by the caller)
+///           return address
+///           caller's FP
+///           registers the function writes
+///           local arrays and spills
+///     SP -> last local
+/// ```
+///
+/// Main has no parameters or saved registers, its frame only holds locals.
+struct MemoryLayout {
+    arrays: ArrayManager,
+    globals: HashMap<String, i32>,
+    global_size: i32,
+    params: HashMap<String, Vec<String>>,
+    saved_regs: HashMap<String, Vec<i32>>,
+    frame: HashMap<String, i32>,
+    locals_size: HashMap<String, i32>,
+}
+
+impl MemoryLayout {
+    fn new(arrays: ArrayManager) -> Self {
+        MemoryLayout {
+            arrays,
+            globals: HashMap::new(),
+            global_size: 0,
+            params: HashMap::new(),
+            saved_regs: HashMap::new(),
+            frame: HashMap::new(),
+            locals_size: HashMap::new(),
+        }
+    }
+
+    fn param_count(&self, func_name: &str) -> i32 {
+        self.params.get(func_name).map_or(0, |params| params.len() as i32)
+    }
+
+    /// Offset of the return address, the caller's FP follows it.
+    fn return_address_offset(&self, func_name: &str) -> i32 {
+        self.param_count(func_name) * 4
+    }
+
+    fn saved_regs(&self, func_name: &str) -> Vec<i32> {
+        self.saved_regs.get(func_name).cloned().unwrap_or_default()
+    }
+
+    fn locals_offset(&self, func_name: &str) -> i32 {
+        if func_name == MAIN_NAME {
+            0
+        } else {
+            self.return_address_offset(func_name) + 8 + self.saved_regs(func_name).len() as i32 * 4
+        }
+    }
+
+    fn locals_size(&self, func_name: &str) -> i32 {
+        self.locals_size.get(func_name).cloned().unwrap_or(0)
+    }
+
+    fn size_of(&self, ident: &String) -> i32 {
+        if self.arrays.contains_array(ident) {
+            self.arrays.get_array_ref(ident.clone()).get_size() as i32 * 4
+        } else {
+            4
+        }
+    }
+
+    fn global_offset(&mut self, ident: &String) -> i32 {
+        if let Some(offset) = self.globals.get(ident) {
+            return *offset;
+        }
+
+        self.global_size += self.size_of(ident);
+        let offset = -self.global_size;
+        self.globals.insert(ident.clone(), offset);
+        offset
+    }
+
+    fn frame_offset(&mut self, func_name: &str, ident: &String) -> i32 {
+        let param_position = self
+            .params
+            .get(func_name)
+            .and_then(|params| params.iter().position(|param| param == ident));
+        if let Some(position) = param_position {
+            return position as i32 * 4;
+        }
+
+        let key = func_name.to_owned() + "::" + ident;
+        if let Some(offset) = self.frame.get(&key) {
+            return *offset;
+        }
+
+        let locals_size = self.locals_size(func_name);
+        let offset = self.locals_offset(func_name) + locals_size;
+        self.locals_size
+            .insert(func_name.to_owned(), locals_size + self.size_of(ident));
+        self.frame.insert(key, offset);
+        offset
+    }
+}
+
+struct CodeGenerator {
+    graph: Graph<Node, String, Directed, u32>,
+    register_map: HashMap<usize, RegisterAllocation>,
+    layout: MemoryLayout,
+
+    program: Vec<i32>,
+    block_addr: HashMap<NodeIndex, usize>,
+    func_addr: HashMap<String, usize>,
+
+    // Branches waiting on the address of a block, calls waiting on a function.
+    branch_fixups: Vec<(usize, NodeIndex)>,
+    call_fixups: Vec<(usize, String)>,
+    // Stack checks waiting on the lowest address taken by globals.
+    stack_checks: Vec<usize>,
+}
+
+/// Phi moves for a taken branch that could not be placed in line, they
+/// get their own stub after the function which then jumps to the target.
+struct EdgeStub {
+    branch_addr: usize,
+    moves: Vec<(i32, Operand)>,
+    target: NodeIndex,
+}
+
+impl CodeGenerator {
+    fn new(
+        graph: Graph<Node, String, Directed, u32>,
+        register_map: HashMap<usize, RegisterAllocation>,
+        layout: MemoryLayout,
+    ) -> Self {
+        CodeGenerator {
+            graph,
+            register_map,
+            layout,
+            program: Vec::new(),
+            block_addr: HashMap::new(),
+            func_addr: HashMap::new(),
+            branch_fixups: Vec::new(),
+            call_fixups: Vec::new(),
+            stack_checks: Vec::new(),
+        }
+    }
+
+    fn generate(
+        &mut self,
+        main_entry: NodeIndex,
+        functions: Vec<(String, NodeIndex)>,
+    ) -> Result<Vec<i32>, String> {
+        // Main's frame is set up once the size of the program is known.
+        self.emit(DLX::F1(ADDI, FRAME_REG, 0, 0))?;
+        self.emit(DLX::F1(ADDI, STACK_REG, FRAME_REG, 0))?;
+
+        self.emit_function(&String::from(MAIN_NAME), main_entry)?;
+        for (func_name, entry_id) in functions {
+            let func_start = self.program.len();
+            self.func_addr.insert(func_name.clone(), func_start);
+            self.emit_function(&func_name, entry_id)?;
+        }
+
+        self.resolve_fixups()?;
+
+        // One word is left for the loader to mark the end of the program.
+        let frame_base = (self.program.len() as i32 + 1) * 4;
+        let main_size = self.layout.locals_size(MAIN_NAME);
+        let global_floor = (MemSize - 1 - self.layout.global_size) / 4 * 4;
+        // Arguments, return address and FP are written above SP before the
+        // callee gets to check it, so that much is kept free past each check.
+        let call_reserve = self
+            .layout
+            .params
+            .keys()
+            .map(|func_name| self.layout.locals_offset(func_name))
+            .max()
+            .unwrap_or(0);
+        let stack_limit = global_floor - call_reserve;
+        if frame_base + main_size > stack_limit {
+            return Err(format!(
+                "Program needs {} bytes of code and frame and {} bytes of globals, only {} available.",
+                frame_base + main_size,
+                self.layout.global_size,
+                MemSize
+            ));
+        }
+        self.program[0] = Self::encode(DLX::F1(ADDI, FRAME_REG, 0, frame_base))?;
+        self.program[1] = Self::encode(DLX::F1(ADDI, STACK_REG, FRAME_REG, main_size - 4))?;
+
+        // Recursion that runs into the globals faults instead of overwriting them.
+        for check_addr in self.stack_checks.clone() {
+            self.program[check_addr] = Self::encode(DLX::F1(CHKI, STACK_REG, 0, stack_limit))?;
+        }
+
+        Ok(self.program.clone())
+    }
+
+    fn emit_function(&mut self, func_name: &String, entry_id: NodeIndex) -> Result<(), String> {
+        let dom_space = simple_fast(&self.graph, entry_id);
+
+        let mut visit_order = Vec::new();
+        let mut dfs_post_order = DfsPostOrder::new(&self.graph, entry_id);
+        while let Some(node_id) = dfs_post_order.next(&self.graph) {
+            visit_order.push(node_id);
+        }
+        visit_order.reverse();
+
+        // The size of the locals is only known once the body is lowered.
+        let mut locals_addr = None;
+        if func_name != MAIN_NAME {
+            let saved_regs = self.written_registers(&visit_order);
+            self.layout.saved_regs.insert(func_name.clone(), saved_regs.clone());
+
+            let params_size = self.layout.return_address_offset(func_name);
+            if params_size > 0 {
+                self.emit(DLX::F1(ADDI, STACK_REG, STACK_REG, params_size))?;
+            }
+            self.emit(DLX::F1(PSH, RA_REG, STACK_REG, 4))?;
+            self.emit(DLX::F1(PSH, FRAME_REG, STACK_REG, 4))?;
+            self.emit(DLX::F1(SUBI, FRAME_REG, STACK_REG, params_size + 4))?;
+            for reg in saved_regs {
+                self.emit(DLX::F1(PSH, reg, STACK_REG, 4))?;
+            }
+
+            locals_addr = Some(self.program.len());
+            self.emit(DLX::F1(ADDI, STACK_REG, STACK_REG, 0))?;
+            self.stack_checks.push(self.program.len());
+            self.emit(DLX::F1(CHKI, STACK_REG, 0, 0))?;
+        }
+
+        let mut stubs = Vec::new();
+        for (position, node_id) in visit_order.iter().enumerate() {
+            let block_start = self.program.len();
+            self.block_addr.insert(*node_id, block_start);
+
+            let next_node = visit_order.get(position + 1).cloned();
+            self.emit_block(func_name, *node_id, next_node, &dom_space, &mut stubs)?;
+        }
+
+        for stub in stubs {
+            let stub_start = self.program.len();
+            self.patch_branch(stub.branch_addr, stub_start)?;
+            self.emit_moves(stub.moves)?;
+            self.emit_jump(stub.target)?;
+        }
+
+        if let Some(locals_addr) = locals_addr {
+            let locals_size = self.layout.locals_size(func_name);
+            self.program[locals_addr] =
+                Self::encode(DLX::F1(ADDI, STACK_REG, STACK_REG, locals_size))?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_block(
+        &mut self,
+        func_name: &String,
+        node_id: NodeIndex,
+        next_node: Option<NodeIndex>,
+        dom_space: &Dominators<NodeIndex>,
+        stubs: &mut Vec<EdgeStub>,
+    ) -> Result<(), String> {
+        let node = self.graph.node_weight(node_id).unwrap().clone();
+
+        if node.get_node_type() == NodeType::exit {
+            return self.emit_return(func_name, None);
+        }
+
+        let mut branch = None;
+        for inst in node.get_data_ref().get_inst_list_ref() {
+            let op = inst.borrow().clone();
+            if !op.is_active() {
+                continue;
+            }
+
+            let is_terminated = self.emit_inst(func_name, &op, &mut branch)?;
+            if is_terminated {
+                return Ok(());
+            }
+        }
+
+        let successors = block_successors(&self.graph, node_id);
+
+        match branch {
+            Some((inst_ty, cmp_val, target_val)) => {
+                let (taken_id, fall_id) = branch_targets(&self.graph, node_id, &successors, &target_val)?;
+                let cmp_reg = self.value_reg(&cmp_val, SCRATCH_X)?;
+
+                let branch_addr = self.program.len();
+                self.emit(DLX::F1(Self::branch_opcode(&inst_ty), cmp_reg, 0, 0))?;
+
+                let taken_moves = self.phi_moves(node_id, taken_id, dom_space)?;
+                if taken_moves.is_empty() {
+                    self.branch_fixups.push((branch_addr, taken_id));
+                } else {
+                    stubs.push(EdgeStub {
+                        branch_addr,
+                        moves: taken_moves,
+                        target: taken_id,
+                    });
+                }
+
+                self.emit_edge(node_id, fall_id, next_node, dom_space)
+            }
+            None => {
+                match fall_through_successor(&self.graph, &successors) {
+                    Some(fall_id) => self.emit_edge(node_id, fall_id, next_node, dom_space),
+                    None => self.emit_return(func_name, None),
+                }
+            }
+        }
+    }
+
+    /// Lowers a single instruction, returns true when it ends the block.
+    fn emit_inst(
+        &mut self,
+        func_name: &String,
+        op: &Op,
+        branch: &mut Option<(InstTy, Value, Value)>,
+    ) -> Result<bool, String> {
+        let (x_val, y_val, special_val) = op.get_values();
+        let inst_ty = op.inst_type().clone();
+
+        match inst_ty {
+            InstTy::add | InstTy::sadd | InstTy::adda => {
+                let (x_val, y_val) = Self::expect_x_y(op, x_val, y_val)?;
+                let dest = self.dest_reg(op);
+
+                match self.address_displacement(func_name, &x_val, &y_val)? {
+                    Some((base_reg, offset)) => {
+                        self.emit_arith(ADD, ADDI, dest, Operand::Reg(base_reg), Operand::Imm(offset), true)?;
+                    }
+                    None => {
+                        let x_op = self.operand(&x_val)?;
+                        let y_op = self.operand(&y_val)?;
+                        self.emit_arith(ADD, ADDI, dest, x_op, y_op, true)?;
+                    }
+                }
+            }
+            InstTy::sub | InstTy::mul | InstTy::div | InstTy::cmp => {
+                let (x_val, y_val) = Self::expect_x_y(op, x_val, y_val)?;
+                let dest = self.dest_reg(op);
+                let x_op = self.operand(&x_val)?;
+                let y_op = self.operand(&y_val)?;
+
+                match inst_ty {
+                    InstTy::sub => self.emit_arith(SUB, SUBI, dest, x_op, y_op, false)?,
+                    InstTy::mul => self.emit_arith(MUL, MULI, dest, x_op, y_op, true)?,
+                    InstTy::div => self.emit_arith(DIV, DIVI, dest, x_op, y_op, false)?,
+                    _ => self.emit_arith(CMP, CMPI, dest, x_op, y_op, false)?,
+                }
+            }
+            InstTy::neg => {
+                let x_val = Self::expect_value(op, x_val)?;
+                let dest = self.dest_reg(op);
+                let x_reg = self.value_reg(&x_val, SCRATCH_X)?;
+                self.emit(DLX::F2(SUB, dest, 0, x_reg))?;
+            }
+            InstTy::load | InstTy::sload => {
+                let y_val = Self::expect_value(op, y_val)?;
+                let dest = self.dest_reg(op);
+                let (addr_reg, offset) = self.memory_operand(func_name, &y_val, SCRATCH_Y)?;
+                self.emit(DLX::F1(LDW, dest, addr_reg, offset))?;
+            }
+            InstTy::store => {
+                let (x_val, y_val) = Self::expect_x_y(op, x_val, y_val)?;
+                let (addr_reg, offset) = self.memory_operand(func_name, &x_val, SCRATCH_X)?;
+                let store_reg = self.value_reg(&y_val, SCRATCH_Y)?;
+                self.emit(DLX::F1(STW, store_reg, addr_reg, offset))?;
+            }
+            InstTy::read => {
+                let dest = self.dest_reg(op);
+                self.emit(DLX::F1(RDI, dest, 0, 0))?;
+            }
+            InstTy::write => {
+                let x_val = Self::expect_value(op, x_val)?;
+                let x_reg = self.value_reg(&x_val, SCRATCH_X)?;
+                self.emit(DLX::F1(WRD, 0, x_reg, 0))?;
+            }
+            InstTy::writeNL => {
+                self.emit(DLX::F1(WRL, 0, 0, 0))?;
+            }
+            InstTy::call => {
+                let func_name = special_val
+                    .ok_or_else(|| format!("Call ({}) has no function name.", op.get_inst_num()))?;
+                let call_addr = self.program.len();
+                self.emit(DLX::F3(JSR, 0))?;
+                self.call_fixups.push((call_addr, func_name));
+
+                // Only a call whose result is read has a register.
+                if let Ok(dest) = self.assigned_reg(op.get_inst_num()) {
+                    self.emit(DLX::F2(ADD, dest, 0, RET_REG))?;
+                }
+            }
+            InstTy::ret => {
+                self.emit_return(func_name, x_val)?;
+                return Ok(true);
+            }
+            InstTy::end => {
+                self.emit_return(func_name, None)?;
+                return Ok(true);
+            }
+            InstTy::bra => {
+                // Jumps to other blocks are rebuilt from the edges at the end
+                // of the block, only the branch on SP (return) matters here.
+                if let Some(ValTy::adr(_)) = y_val.map(|val| val.clone_value()) {
+                    self.emit_return(func_name, None)?;
+                    return Ok(true);
+                }
+            }
+            InstTy::bne
+            | InstTy::beq
+            | InstTy::blt
+            | InstTy::bge
+            | InstTy::ble
+            | InstTy::bgt => {
+                let (x_val, y_val) = Self::expect_x_y(op, x_val, y_val)?;
+                *branch = Some((inst_ty, x_val, y_val));
+            }
+            InstTy::phi | InstTy::kill => {
+                // Phis are resolved by moves in the predecessors.
+            }
+            InstTy::mov => {
+                return Err(format!(
+                    "Instruction ({}) {} is not supported by code generation.",
+                    op.get_inst_num(),
+                    op.to_string()
+                ));
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Registers given to any instruction in `blocks`, these are saved on
+    /// entry and restored on the way out.
+    fn written_registers(&self, blocks: &[NodeIndex]) -> Vec<i32> {
+        let mut registers = Vec::new();
+        for node_id in blocks {
+            for inst in self.graph.node_weight(*node_id).unwrap().get_data_ref().get_inst_list_ref() {
+                if let Some(register) = self.register_map.get(&inst.borrow().get_inst_num()) {
+                    let register = register.to_usize() as i32;
+                    if !registers.contains(&register) {
+                        registers.push(register);
+                    }
+                }
+            }
+        }
+        registers.sort();
+        registers
+    }
+
+    /// Pops the current frame and returns through the saved return address,
+    /// or halts the machine when leaving main.
+    fn emit_return(&mut self, func_name: &String, ret_val: Option<Value>) -> Result<(), String> {
+        if func_name == MAIN_NAME {
+            return self.emit(DLX::F2(RET, 0, 0, 0));
+        }
+
+        if let Some(ret_val) = ret_val {
+            let ret_op = self.operand(&ret_val)?;
+            self.emit_moves(vec![(RET_REG, ret_op)])?;
+        }
+
+        let params_size = self.layout.return_address_offset(func_name);
+        let saved_regs = self.layout.saved_regs(func_name);
+        let saved_size = saved_regs.len() as i32 * 4;
+        self.emit(DLX::F1(ADDI, STACK_REG, FRAME_REG, params_size + 4 + saved_size))?;
+        for reg in saved_regs.into_iter().rev() {
+            self.emit(DLX::F1(POP, reg, STACK_REG, -4))?;
+        }
+        self.emit(DLX::F1(POP, FRAME_REG, STACK_REG, -4))?;
+        self.emit(DLX::F1(POP, RA_REG, STACK_REG, -4))?;
+        if params_size > 0 {
+            self.emit(DLX::F1(SUBI, STACK_REG, STACK_REG, params_size))?;
+        }
+        self.emit(DLX::F2(RET, 0, 0, RA_REG))
+    }
+
+    /// Leaves `node_id` towards `child_id`, resolving the child's phis and
+    /// jumping only if the child is not laid out next.
+    fn emit_edge(
+        &mut self,
+        node_id: NodeIndex,
+        child_id: NodeIndex,
+        next_node: Option<NodeIndex>,
+        dom_space: &Dominators<NodeIndex>,
+    ) -> Result<(), String> {
+        let moves = self.phi_moves(node_id, child_id, dom_space)?;
+        self.emit_moves(moves)?;
+
+        if next_node != Some(child_id) {
+            self.emit_jump(child_id)?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_jump(&mut self, target_id: NodeIndex) -> Result<(), String> {
+        let jump_addr = self.program.len();
+        self.emit(DLX::F1(BEQ, 0, 0, 0))?;
+        self.branch_fixups.push((jump_addr, target_id));
+        Ok(())
+    }
+
+    fn emit_moves(&mut self, moves: Vec<(i32, Operand)>) -> Result<(), String> {
+        for (dest, src) in phi_resolver::sequence_moves(moves, SCRATCH_Y) {
+            match src {
+                Operand::Reg(src_reg) => self.emit(DLX::F2(ADD, dest, 0, src_reg))?,
+                Operand::Imm(con) => self.load_immediate(dest, con)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collects the moves needed for the phis of `child_id` when entering
+    /// it from `node_id`.
+    fn phi_moves(
+        &self,
+        node_id: NodeIndex,
+        child_id: NodeIndex,
+        dom_space: &Dominators<NodeIndex>,
+    ) -> Result<Vec<(i32, Operand)>, String> {
+        let mut moves = Vec::new();
+        let mut is_x_side = None;
+
+        for inst in self.graph.node_weight(child_id).unwrap().get_data_ref().get_inst_list_ref() {
+            let op = inst.borrow();
+            if !op.is_active() || op.inst_type().clone() != InstTy::phi {
+                continue;
+            }
+
+            // A phi nobody reads was never given a register.
+            let dest = match self.register_map.get(&op.get_inst_num()) {
+                Some(register) => register.to_usize() as i32,
+                None => continue,
+            };
+
+            if is_x_side.is_none() {
+                is_x_side = Some(is_phi_x_side(&self.graph, dom_space, node_id, child_id));
+            }
+
+            let src_val = if is_x_side.unwrap() { op.clone_x_val() } else { op.clone_y_val() };
+            let src_val = src_val.ok_or_else(|| format!("Phi ({}) is missing an operand.", op.get_inst_num()))?;
+            moves.push((dest, self.operand(&src_val)?));
+        }
+
+        Ok(moves)
+    }
+
+    /// Points the branches and calls emitted before their target had an
+    /// address at that target, once every block and function is placed.
+    fn resolve_fixups(&mut self) -> Result<(), String> {
+        for (branch_addr, target_id) in self.branch_fixups.clone() {
+            let target_addr = self
+                .block_addr
+                .get(&target_id)
+                .cloned()
+                .ok_or_else(|| format!("Branch to node {} which was never emitted.", target_id.index()))?;
+            self.patch_branch(branch_addr, target_addr)?;
+        }
+
+        for (call_addr, func_name) in self.call_fixups.clone() {
+            let func_addr = self
+                .func_addr
+                .get(&func_name)
+                .cloned()
+                .ok_or_else(|| format!("Call to unknown function {}.", func_name))?;
+            self.program[call_addr] = Self::encode(DLX::F3(JSR, func_addr as i32 * 4))?;
+        }
+
+        Ok(())
+    }
+
+    fn patch_branch(&mut self, branch_addr: usize, target_addr: usize) -> Result<(), String> {
+        let word = self.program[branch_addr];
+        let op = (word >> 26) & 0x3F;
+        let cond_reg = (word >> 21) & 0x1F;
+        let offset = target_addr as i32 - branch_addr as i32;
+
+        if !Self::fits_immediate(offset) {
+            return Err(format!("Branch at {} is too far from its target.", branch_addr * 4));
+        }
+
+        self.program[branch_addr] = Self::encode(DLX::F1(op, cond_reg, 0, offset))?;
+        Ok(())
+    }
+
+    fn emit_arith(
+        &mut self,
+        reg_opcode: i32,
+        imm_opcode: i32,
+        dest: i32,
+        x_op: Operand,
+        y_op: Operand,
+        is_commutative: bool,
+    ) -> Result<(), String> {
+        let (x_op, y_op) = match (x_op, y_op) {
+            (Operand::Imm(con), Operand::Reg(reg)) if is_commutative => (Operand::Reg(reg), Operand::Imm(con)),
+            operands => operands,
+        };
+
+        let x_reg = self.operand_reg(x_op, SCRATCH_X)?;
+        match y_op {
+            Operand::Imm(con) if Self::fits_immediate(con) => self.emit(DLX::F1(imm_opcode, dest, x_reg, con)),
+            y_op => {
+                let y_reg = self.operand_reg(y_op, SCRATCH_Y)?;
+                self.emit(DLX::F2(reg_opcode, dest, x_reg, y_reg))
+            }
+        }
+    }
+
+    fn load_immediate(&mut self, dest: i32, con: i32) -> Result<(), String> {
+        if Self::fits_immediate(con) {
+            return self.emit(DLX::F1(ADDI, dest, 0, con));
+        }
+
+        // Immediates are sign extended, so the upper half absorbs the borrow.
+        let low = (con as i16) as i32;
+        let high = con.wrapping_sub(low) >> 16;
+        self.emit(DLX::F1(ADDI, dest, 0, high))?;
+        self.emit(DLX::F1(LSHI, dest, dest, 16))?;
+        self.emit(DLX::F1(ADDI, dest, dest, low))
+    }
+
+    /// Resolves `&FP &name` style address pairs to the register and offset
+    /// of the named slot. Arguments of the next call sit just above SP.
+    fn address_displacement(
+        &mut self,
+        func_name: &str,
+        x_val: &Value,
+        y_val: &Value,
+    ) -> Result<Option<(i32, i32)>, String> {
+        if let (ValTy::adr(base_addr), ValTy::adr(uniq_addr)) = (x_val.get_value(), y_val.get_value()) {
+            let ident = uniq_addr.get_base_ident();
+            return match base_addr.get_base_ident().as_ref() {
+                "globalReg" => Ok(Some((GLOBAL_REG, self.layout.global_offset(&ident)))),
+                "FP" => Ok(Some((FRAME_REG, self.layout.frame_offset(func_name, &ident)))),
+                "SP" => Ok(Some((STACK_REG, (uniq_addr.get_register_value() as i32 + 1) * 4))),
+                base => Err(format!("Address {} can not be relative to {}.", ident, base)),
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// Base register and displacement for a load or store. Addresses of
+    /// named slots are taken straight from FP, SP or R30 rather than from
+    /// the register holding the sum, which a call in between may have
+    /// overwritten.
+    fn memory_operand(&mut self, func_name: &str, addr_val: &Value, scratch: i32) -> Result<(i32, i32), String> {
+        if let ValTy::op(addr_op) = addr_val.get_var_base() {
+            let addr_op = addr_op.borrow().clone();
+            if let (InstTy::add | InstTy::sadd, (Some(x_val), Some(y_val), _)) =
+                (addr_op.inst_type(), addr_op.get_values())
+            {
+                if let Some((base_reg, offset)) = self.address_displacement(func_name, &x_val, &y_val)? {
+                    if Self::fits_immediate(offset) {
+                        return Ok((base_reg, offset));
+                    }
+                }
+            }
+        }
+
+        Ok((self.value_reg(addr_val, scratch)?, 0))
+    }
+
+    fn operand(&self, value: &Value) -> Result<Operand, String> {
+        match value.get_var_base() {
+            ValTy::op(op) => Ok(Operand::Reg(self.assigned_reg(op.borrow().get_inst_num())?)),
+            ValTy::con(con) => Ok(Operand::Imm(con)),
+            ValTy::reg(register) => Ok(Operand::Reg(register.to_usize() as i32)),
+            ValTy::ret(_) => Ok(Operand::Reg(RET_REG)),
+            ValTy::adr(uniq_addr) => match uniq_addr.get_base_ident().as_ref() {
+                "globalReg" => Ok(Operand::Reg(GLOBAL_REG)),
+                "FP" => Ok(Operand::Reg(FRAME_REG)),
+                "SP" => Ok(Operand::Reg(STACK_REG)),
+                "baseReg" => Ok(Operand::Reg(0)),
+                ident => Err(format!("Address {} used without a base register.", ident)),
+            },
+            val_ty => Err(format!("Unexpected operand {} in code generation.", val_ty.to_string())),
+        }
+    }
+
+    fn value_reg(&mut self, value: &Value, scratch: i32) -> Result<i32, String> {
+        let operand = self.operand(value)?;
+        self.operand_reg(operand, scratch)
+    }
+
+    fn operand_reg(&mut self, operand: Operand, scratch: i32) -> Result<i32, String> {
+        match operand {
+            Operand::Reg(reg) => Ok(reg),
+            Operand::Imm(con) => {
+                self.load_immediate(scratch, con)?;
+                Ok(scratch)
+            }
+        }
+    }
+
+    fn assigned_reg(&self, inst_num: usize) -> Result<i32, String> {
+        let register = self
+            .register_map
+            .get(&inst_num)
+            .ok_or_else(|| format!("Instruction ({}) is used but was never given a register.", inst_num))?
+            .to_usize() as i32;
+
+        if !(1..=MAX_ALLOCATED_REG).contains(&register) {
+            return Err(format!(
+                "Instruction ({}) was given R{}, only R1 through R{} are available.",
+                inst_num, register, MAX_ALLOCATED_REG
+            ));
+        }
+
+        Ok(register)
+    }
+
+    /// Values nobody reads are never colored, they are written to scratch.
+    fn dest_reg(&self, op: &Op) -> i32 {
+        self.assigned_reg(op.get_inst_num()).unwrap_or(SCRATCH_X)
+    }
+
+    fn emit(&mut self, word: Result<i32, DlxError>) -> Result<(), String> {
+        let word = Self::encode(word)?;
+        self.program.push(word);
+        Ok(())
+    }
+
+    fn encode(word: Result<i32, DlxError>) -> Result<i32, String> {
+        word.map_err(|err| err.to_string())
+    }
+
+    fn fits_immediate(con: i32) -> bool {
+        (i16::MIN as i32..=i16::MAX as i32).contains(&con)
+    }
+
+    fn branch_opcode(inst_ty: &InstTy) -> i32 {
+        match inst_ty {
+            InstTy::bne => BNE,
+            InstTy::beq => BEQ,
+            InstTy::blt => BLT,
+            InstTy::bge => BGE,
+            InstTy::ble => BLE,
+            _ => BGT,
+        }
+    }
+
+    fn expect_value(op: &Op, value: Option<Value>) -> Result<Value, String> {
+        value.ok_or_else(|| format!("Instruction ({}) is missing an operand.", op.get_inst_num()))
+    }
+
+    fn expect_x_y(op: &Op, x_val: Option<Value>, y_val: Option<Value>) -> Result<(Value, Value), String> {
+        Ok((Self::expect_value(op, x_val)?, Self::expect_value(op, y_val)?))
+    }
+}
@@ -0,0 +1,52 @@
+use super::Operand;
+
+/// Orders the moves needed to resolve the phis along a single edge so that
+/// no register is overwritten before every move reading it has been done.
+/// The moves are parallel (all sources are read before any destination is
+/// written), so any cycle found is broken by routing one value through
+/// `temp_reg`. Immediate loads cannot be read by another move, so they are
+/// left until the end.
+pub fn sequence_moves(moves: Vec<(i32, Operand)>, temp_reg: i32) -> Vec<(i32, Operand)> {
+    let mut pending: Vec<(i32, i32)> = Vec::new();
+    let mut immediates = Vec::new();
+
+    for (dest, src) in moves {
+        match src {
+            Operand::Reg(src_reg) => {
+                // Coalesced phi operands already live in the phi register.
+                if src_reg != dest {
+                    pending.push((dest, src_reg));
+                }
+            }
+            Operand::Imm(con) => immediates.push((dest, Operand::Imm(con))),
+        }
+    }
+
+    let mut ordered = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(dest, _)| !pending.iter().any(|(_, src)| src == dest));
+
+        match ready {
+            Some(position) => {
+                let (dest, src) = pending.remove(position);
+                ordered.push((dest, Operand::Reg(src)));
+            }
+            None => {
+                // Every destination left is still needed as a source, so
+                // save one of them off and point its readers at the copy.
+                let (dest, _) = pending[0];
+                ordered.push((temp_reg, Operand::Reg(dest)));
+                for pending_move in pending.iter_mut() {
+                    if pending_move.1 == dest {
+                        pending_move.1 = temp_reg;
+                    }
+                }
+            }
+        }
+    }
+
+    ordered.extend(immediates);
+    ordered
+}
@@ -0,0 +1,481 @@
+pub mod pipeline;
+
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+
+use lib::Optimizer::pass_manager::{all_passes, pass_names, PassPipeline, DEFAULT_FIXPOINT_ROUNDS};
+use lib::Utility::dlx::DLX;
+
+use self::pipeline::{compile_ir_stages, compile_stages, stage_runs, StageOutput};
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_COMPILE_ERROR: i32 = 1;
+pub const EXIT_USAGE_ERROR: i32 = 2;
+pub const EXIT_RUNTIME_ERROR: i32 = 3;
+
+// The pass lists are filled in by `usage`.
+const USAGE: &str = "\
+usage: crust <input.pl241> [options]
+       crust <input.ir> [options]
+
+options:
+    -o <file>           write the DLX program to <file>
+    --out-dir <dir>     directory for generated files (default: .)
+    -O0, -O1, -O2       optimization level (default: -O2)
+    --enable <passes>   comma separated optimizer passes to run on top of
+                        the level: {optional}
+    --disable <passes>  comma separated optimizer passes to leave out
+    --fixpoint          repeat the optimizer passes until the IR stops
+                        changing
+    --emit <stages>     comma separated stages to write, each optionally
+                        followed by =<file> (or =- for stdout):
+                          tokens        lexer output
+                          ast           parsed program
+                          ir            IR straight from the parser
+                          after:<pass>  IR after the given optimizer pass,
+                                        one of {passes}
+                          verify        IR invariant violations after
+                                        ir and each pass
+                          stats         what each pass did to each
+                                        function, and how long it took
+                          stats-json    the same as JSON
+                          cfg           control flow graph (dot)
+                          interference  colored interference graphs (dot)
+                          asm           disassembled DLX program
+                          program       DLX program image (default)
+    --run               execute the program on the DLX emulator
+    -h, --help          print this message";
+
+/// The usage message, naming the passes the optimizer has.
+pub fn usage() -> String {
+    let optional = all_passes()
+        .iter()
+        .filter(|pass| !pass.required())
+        .map(|pass| pass.name())
+        .collect::<Vec<&str>>();
+    USAGE
+        .replace("{optional}", &name_list(&optional, 35, 24))
+        .replace("{passes}", &name_list(&pass_names(), 47, 40))
+}
+
+// Comma separated `names` starting at `column`, carried over to lines
+// indented by `indent` so none runs past 72 columns.
+fn name_list(names: &[&str], column: usize, indent: usize) -> String {
+    let mut text = String::new();
+    let mut width = column;
+    for (index, name) in names.iter().enumerate() {
+        let item = if index + 1 < names.len() { format!("{},", name) } else { name.to_string() };
+        if index > 0 {
+            if width + 1 + item.len() > 72 {
+                text.push('\n');
+                text.push_str(&" ".repeat(indent));
+                width = indent;
+            } else {
+                text.push(' ');
+                width += 1;
+            }
+        }
+        text.push_str(&item);
+        width += item.len();
+    }
+    text
+}
+
+/// Stages of the pipeline the driver can write out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+    /// IR after the optimizer pass of that name.
+    After(&'static str),
+    Verify,
+    Stats,
+    StatsJson,
+    Cfg,
+    Interference,
+    Asm,
+    Program,
+}
+
+impl Emit {
+    pub fn from_str(kind: &str) -> Result<Self, String> {
+        match kind {
+            "tokens" => Ok(Emit::Tokens),
+            "ast" => Ok(Emit::Ast),
+            "ir" => Ok(Emit::Ir),
+            "verify" => Ok(Emit::Verify),
+            "stats" => Ok(Emit::Stats),
+            "stats-json" => Ok(Emit::StatsJson),
+            "cfg" => Ok(Emit::Cfg),
+            "interference" => Ok(Emit::Interference),
+            "asm" => Ok(Emit::Asm),
+            "program" => Ok(Emit::Program),
+            _ => match kind.strip_prefix("after:") {
+                Some(pass) => match pass_names().into_iter().find(|name| *name == pass) {
+                    Some(name) => Ok(Emit::After(name)),
+                    None => Err(format!(
+                        "Unknown pass '{}' given to --emit, the passes are {}.",
+                        pass,
+                        pass_names().join(", ")
+                    )),
+                },
+                None => Err(format!("Unknown stage '{}' given to --emit.", kind)),
+            },
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Emit::Tokens => String::from("tokens"),
+            Emit::Ast => String::from("ast"),
+            Emit::Ir => String::from("ir"),
+            Emit::After(pass) => format!("after:{}", pass),
+            Emit::Verify => String::from("verify"),
+            Emit::Stats => String::from("stats"),
+            Emit::StatsJson => String::from("stats-json"),
+            Emit::Cfg => String::from("cfg"),
+            Emit::Interference => String::from("interference"),
+            Emit::Asm => String::from("asm"),
+            Emit::Program => String::from("program"),
+        }
+    }
+
+    /// File the stage is written to when no destination is given.
+    fn default_file_name(&self, stem: &str, func_name: Option<&String>) -> String {
+        match self {
+            Emit::Tokens => format!("{}.tokens", stem),
+            Emit::Ast => format!("{}.ast", stem),
+            Emit::Ir => format!("{}.ir", stem),
+            Emit::After(pass) => format!("{}.{}.ir", stem, pass),
+            Emit::Verify => format!("{}.verify", stem),
+            Emit::Stats => format!("{}.stats", stem),
+            Emit::StatsJson => format!("{}.stats.json", stem),
+            Emit::Cfg => format!("{}.dot", stem),
+            Emit::Interference => match func_name {
+                Some(func_name) if func_name != "main" => {
+                    format!("{}_{}_interference.dot", stem, func_name)
+                }
+                _ => format!("{}_interference.dot", stem),
+            },
+            Emit::Asm => format!("{}.asm", stem),
+            Emit::Program => format!("{}.dlx", stem),
+        }
+    }
+}
+
+/// Where a stage is written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    // Named after the input, inside of the output directory.
+    Default,
+    Stdout,
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: PathBuf,
+    pub out_dir: PathBuf,
+    pub passes: PassPipeline,
+    pub emit: Vec<(Emit, Destination)>,
+    pub run: bool,
+    pub help: bool,
+}
+
+impl Options {
+    fn new(input: PathBuf) -> Self {
+        Options {
+            input,
+            out_dir: PathBuf::from("."),
+            passes: PassPipeline::for_level(2).unwrap(),
+            emit: vec![(Emit::Program, Destination::Default)],
+            run: false,
+            help: false,
+        }
+    }
+
+    /// File name of the input without its extension, used to name
+    /// every generated file.
+    pub fn stem(&self) -> String {
+        self.input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("out"))
+    }
+
+    pub fn destination(&self, stage: &Emit) -> Option<&Destination> {
+        self.emit
+            .iter()
+            .find(|(emit_stage, _)| emit_stage == stage)
+            .map(|(_, destination)| destination)
+    }
+
+    /// Path a stage is written to, None when it goes to stdout (or is
+    /// not being emitted at all).
+    pub fn stage_path(&self, stage: &Emit, func_name: Option<&String>) -> Option<PathBuf> {
+        match self.destination(stage)? {
+            Destination::Default => Some(
+                self.out_dir
+                    .join(stage.default_file_name(&self.stem(), func_name)),
+            ),
+            Destination::Stdout => None,
+            Destination::File(path) => Some(path.clone()),
+        }
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut out_dir = None;
+    let mut opt_level = None;
+    // Applied in order on top of the level, true enables the pass.
+    let mut pass_switches: Vec<(bool, String)> = Vec::new();
+    let mut fixpoint = false;
+    let mut emit: Option<Vec<(Emit, Destination)>> = None;
+    let mut run = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                let mut options = Options::new(PathBuf::new());
+                options.help = true;
+                return Ok(options);
+            }
+            "-o" => output = Some(PathBuf::from(expect_value(&mut args, "-o")?)),
+            "--out-dir" => out_dir = Some(PathBuf::from(expect_value(&mut args, "--out-dir")?)),
+            "-O0" => opt_level = Some(0),
+            "-O1" => opt_level = Some(1),
+            "-O2" => opt_level = Some(2),
+            "--enable" | "--disable" => {
+                let passes = expect_value(&mut args, &arg)?;
+                for pass in passes.split(',').filter(|pass| !pass.is_empty()) {
+                    pass_switches.push((arg == "--enable", pass.to_string()));
+                }
+            }
+            "--fixpoint" => fixpoint = true,
+            "--emit" => {
+                let stages = expect_value(&mut args, "--emit")?;
+                let emit_list = emit.get_or_insert_with(Vec::new);
+                for stage in stages.split(',').filter(|stage| !stage.is_empty()) {
+                    let (stage, destination) = match stage.find('=') {
+                        Some(position) => {
+                            let path = &stage[position + 1..];
+                            let destination = match path {
+                                "" => return Err(format!("Missing file for --emit {}.", stage)),
+                                "-" => Destination::Stdout,
+                                _ => Destination::File(PathBuf::from(path)),
+                            };
+                            (Emit::from_str(&stage[..position])?, destination)
+                        }
+                        None => (Emit::from_str(stage)?, Destination::Default),
+                    };
+
+                    // Naming a stage again only changes where it goes.
+                    emit_list.retain(|(emit_stage, _)| *emit_stage != stage);
+                    emit_list.push((stage, destination));
+                }
+            }
+            "--run" => run = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'.", arg)),
+            _ => {
+                if input.is_some() {
+                    return Err(format!("Unexpected extra input '{}'.", arg));
+                }
+                input = Some(PathBuf::from(arg));
+            }
+        }
+    }
+
+    let input = match input {
+        Some(input) => input,
+        None => return Err(String::from("No input file given.")),
+    };
+
+    let mut options = Options::new(input);
+    if let Some(out_dir) = out_dir {
+        options.out_dir = out_dir;
+    }
+    if let Some(opt_level) = opt_level {
+        options.passes = PassPipeline::for_level(opt_level)?;
+    }
+    for (enable, pass) in pass_switches {
+        options.passes = if enable {
+            options.passes.enable(&pass)?
+        } else {
+            options.passes.disable(&pass)?
+        };
+    }
+    if fixpoint {
+        options.passes = options.passes.repeat_until_fixpoint(DEFAULT_FIXPOINT_ROUNDS);
+    }
+    if let Some(emit) = emit {
+        options.emit = emit;
+    }
+    if let Some(output) = output {
+        options.emit.retain(|(stage, _)| *stage != Emit::Program);
+        options.emit.push((Emit::Program, Destination::File(output)));
+    }
+    options.run = run;
+
+    Ok(options)
+}
+
+fn expect_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    match args.next() {
+        Some(value) => Ok(value),
+        None => Err(format!("Missing value for {}.", flag)),
+    }
+}
+
+/// Entry point of the command line tool, returns the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, usage());
+            return EXIT_USAGE_ERROR;
+        }
+    };
+
+    if options.help {
+        println!("{}", usage());
+        return EXIT_SUCCESS;
+    }
+
+    let source = match fs::read_to_string(&options.input) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: could not read {}: {}", options.input.display(), err);
+            return EXIT_COMPILE_ERROR;
+        }
+    };
+
+    let stages = options
+        .emit
+        .iter()
+        .map(|(stage, _)| stage.clone())
+        .collect::<Vec<Emit>>();
+    for stage in stages.iter() {
+        if !stage_runs(stage, &options.passes) {
+            eprintln!(
+                "warning: the pass behind {} is not enabled, nothing is written for it",
+                stage.name()
+            );
+        }
+    }
+
+    // Compile errors are reported below, keep the panic hook from
+    // printing a backtrace hint on top of them.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let mut outputs = Vec::new();
+    // Textual IR skips the front end and goes straight to the optimizer.
+    let result = if options.input.extension().is_some_and(|ext| ext == "ir") {
+        compile_ir_stages(&source, &options.passes, &stages, &mut outputs)
+    } else {
+        compile_stages(&source, &options.passes, &stages, &mut outputs)
+    };
+    panic::set_hook(hook);
+
+    // Whatever stages finished are written even when a later one failed.
+    if let Err(err) = write_stages(&options, &outputs) {
+        eprintln!("error: {}", err);
+        return EXIT_COMPILE_ERROR;
+    }
+
+    let program = match result {
+        Ok(program) => program,
+        Err(err) => {
+            // Syntax errors come back one per line.
+            for line in err.lines() {
+                eprintln!("error: {}: {}", options.input.display(), line);
+            }
+            return EXIT_COMPILE_ERROR;
+        }
+    };
+
+    if let Err(err) = write_program(&options, &program) {
+        eprintln!("error: {}", err);
+        return EXIT_COMPILE_ERROR;
+    }
+
+    if options.run {
+        let mut dlx = DLX::new();
+        dlx.set_output_writer(io::stdout());
+        let result = dlx.load(&program).and_then(|_| dlx.execute());
+        io::stdout().flush();
+        if let Err(err) = result {
+            eprintln!("\nerror: {}", err);
+            return EXIT_RUNTIME_ERROR;
+        }
+        println!();
+    }
+
+    EXIT_SUCCESS
+}
+
+fn write_stages(options: &Options, outputs: &[StageOutput]) -> Result<(), String> {
+    // Stages sharing a file (every function's interference graph when a
+    // file is named) are written together.
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+
+    for output in outputs {
+        match options.stage_path(&output.stage, output.func_name.as_ref()) {
+            Some(path) => match files.iter_mut().find(|(file_path, _)| *file_path == path) {
+                Some((_, text)) => text.push_str(&output.text),
+                None => files.push((path, output.text.clone())),
+            },
+            None => {
+                // A closed pipe (say into head) is not worth failing over.
+                let mut stdout = io::stdout();
+                stdout.write_all(output.text.as_bytes());
+                if !output.text.ends_with('\n') {
+                    stdout.write_all(b"\n");
+                }
+            }
+        }
+    }
+
+    for (path, text) in files {
+        // `--emit ir` on an .ir input would otherwise replace it.
+        if let (Ok(target), Ok(input)) = (fs::canonicalize(&path), fs::canonicalize(&options.input)) {
+            if target == input {
+                return Err(format!("not overwriting the input {}", path.display()));
+            }
+        }
+        write_file(&path, text.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn write_program(options: &Options, program: &[i32]) -> Result<(), String> {
+    let mut bytes = Vec::with_capacity(program.len() * 4);
+    for word in program {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+
+    match options.destination(&Emit::Program) {
+        Some(Destination::Stdout) => io::stdout()
+            .write_all(&bytes)
+            .map_err(|err| format!("could not write program to stdout: {}", err)),
+        Some(_) => write_file(&options.stage_path(&Emit::Program, None).unwrap(), &bytes),
+        None => Ok(()),
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("could not create {}: {}", parent.display(), err))?;
+        }
+    }
+    fs::write(path, contents).map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
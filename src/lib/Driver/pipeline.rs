@@ -0,0 +1,530 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::panic;
+use std::time::Instant;
+
+use lib::CodeGen;
+use lib::Graph::control_flow_graph;
+use lib::IR::interpreter::{interpret, BufferedIo, StopKind};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::ir_text;
+use lib::IR::verify::verify;
+use lib::Lexer::source::SourceIter;
+use lib::Lexer::token::TokenCollection;
+use lib::Optimizer::pass_manager::{pass_names, PassPipeline};
+use lib::Optimizer::stats::{stats_json, stats_table, PassStats};
+use lib::Optimizer::Optimizer;
+use lib::Parser;
+use lib::Parser::AST::computation::Comp;
+use lib::Parser::interpreter::interpret as ast_interpret;
+use lib::RegisterAllocator::analyze_live_range;
+use lib::Semantic;
+use lib::Utility::display;
+use lib::Utility::dlx::{DlxErrorKind, DLX};
+use lib::Utility::generator::{self, GeneratedProgram};
+
+use super::Emit;
+
+use lib::petgraph;
+use petgraph::algo::dominators::simple_fast;
+
+/// Text dumped for one of the requested stages.
+#[derive(Debug, Clone)]
+pub struct StageOutput {
+    pub stage: Emit,
+    // Set for the stages that produce one output per function.
+    pub func_name: Option<String>,
+    pub text: String,
+}
+
+/// What a program wrote on one of the engines and, if it stopped early,
+/// why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub output: String,
+    pub stop: Option<(StopKind, String)>,
+}
+
+impl Run {
+    fn finished(output: &str) -> Self {
+        Run {
+            output: output.to_owned(),
+            stop: None,
+        }
+    }
+
+    fn stopped(output: &str, kind: StopKind, message: String) -> Self {
+        Run {
+            output: output.to_owned(),
+            stop: Some((kind, message)),
+        }
+    }
+
+    // The program never ran, the compiler failed or panicked.
+    fn failed(message: String) -> Self {
+        Run::stopped("", StopKind::CompileError, message)
+    }
+
+    /// Whether `found` agrees with this run of the reference: both write
+    /// the same output and stop for the same kind of reason, or not at
+    /// all. Engines count their budgets in different units, so when both
+    /// ran out of it one only has to have written the start of what the
+    /// other did. A reference that runs past the end of an array, which the
+    /// generated code does not check, only has to be followed up to there,
+    /// by a run that did not fail for some other reason.
+    pub fn matches(&self, found: &Run) -> bool {
+        let kind = |run: &Run| run.stop.as_ref().map(|(kind, _)| *kind);
+        match (kind(self), kind(found)) {
+            (Some(StopKind::BudgetExhausted), Some(StopKind::BudgetExhausted)) => {
+                found.output.starts_with(&self.output) || self.output.starts_with(&found.output)
+            }
+            (Some(StopKind::IndexOutOfBounds), Some(StopKind::CompileError))
+            | (Some(StopKind::IndexOutOfBounds), Some(StopKind::Other)) => false,
+            (Some(StopKind::IndexOutOfBounds), _) => found.output.starts_with(&self.output),
+            (expected, found_kind) => expected == found_kind && self.output == found.output,
+        }
+    }
+}
+
+impl fmt::Display for Run {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.stop {
+            None => write!(f, "output {:?}", self.output),
+            Some((kind, message)) => write!(f, "output {:?} then {:?} ({})", self.output, kind, message),
+        }
+    }
+}
+
+/// How the IR interpreter ran a program after a stage.
+pub type StageRun = (Emit, Run);
+
+/// Runs the whole pipeline over `source` and returns the DLX program.
+pub fn compile_source(source: &str, opt_level: u8) -> Result<Vec<i32>, String> {
+    let passes = PassPipeline::for_level(opt_level)?;
+    compile_stages(source, &passes, &[], &mut Vec::new())
+}
+
+/// Runs the whole pipeline over `source`, pushing the text of each stage in
+/// `stages` onto `outputs` as soon as it is produced (so the earlier stages
+/// are still there when a later one fails). `passes` picks which optimizer
+/// passes run.
+pub fn compile_stages(
+    source: &str,
+    passes: &PassPipeline,
+    stages: &[Emit],
+    outputs: &mut Vec<StageOutput>,
+) -> Result<Vec<i32>, String> {
+    // IR construction and code generation still report problems by panicking.
+    catch_panic(|| compile(source, passes, stages, outputs))
+}
+
+/// Same as `compile_stages`, starting from IR in the text form of
+/// `ir_text` instead of source. Only the stages from `ir` on produce output.
+pub fn compile_ir_stages(
+    text: &str,
+    passes: &PassPipeline,
+    stages: &[Emit],
+    outputs: &mut Vec<StageOutput>,
+) -> Result<Vec<i32>, String> {
+    catch_panic(|| {
+        let irgmanager = ir_text::from_text(text).map_err(|err| err.to_string())?;
+        compile_graph(irgmanager, passes, stages, outputs)
+    })
+}
+
+fn catch_panic<T, F>(compile: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String>,
+{
+    match panic::catch_unwind(panic::AssertUnwindSafe(compile)) {
+        Ok(result) => result,
+        Err(payload) => Err(panic_message(payload)),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("Compiler panicked.")
+    }
+}
+
+// One diagnostic per line.
+fn join_errors<T: ToString>(errors: &[T]) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Whether `stage` can produce any output with the given passes.
+pub fn stage_runs(stage: &Emit, passes: &PassPipeline) -> bool {
+    match stage {
+        Emit::After(pass) => passes.is_enabled(pass),
+        _ => true,
+    }
+}
+
+fn compile(
+    source: &str,
+    passes: &PassPipeline,
+    stages: &[Emit],
+    outputs: &mut Vec<StageOutput>,
+) -> Result<Vec<i32>, String> {
+    let irgmanager = front_end(source, stages, outputs)?;
+    compile_graph(irgmanager, passes, stages, outputs)
+}
+
+/// Lexing, parsing and the semantic check, up to the freshly built IR.
+fn front_end(source: &str, stages: &[Emit], outputs: &mut Vec<StageOutput>) -> Result<IRGraphManager, String> {
+    Ok(checked_ast(source, stages, outputs)?.to_ir())
+}
+
+/// Lexing, parsing and the semantic check.
+fn checked_ast(source: &str, stages: &[Emit], outputs: &mut Vec<StageOutput>) -> Result<Comp, String> {
+    let mut push = |stage: Emit, func_name: Option<String>, text: String| {
+        outputs.push(StageOutput {
+            stage,
+            func_name,
+            text,
+        });
+    };
+
+    if stages.contains(&Emit::Tokens) {
+        let tc = TokenCollection::collect(&mut SourceIter::new(source));
+        let mut text = String::new();
+        for token in tc.get_vector() {
+            writeln!(
+                text,
+                "{}\t{:?}\t{}",
+                token.get_span(),
+                token.get_type(),
+                token.get_contents()
+            );
+        }
+        push(Emit::Tokens, None, text);
+    }
+
+    let mut char_iter = SourceIter::new(source);
+    let mut tc = TokenCollection::collect(&mut char_iter);
+
+    let lex_errors = tc.take_lex_errors();
+    if !lex_errors.is_empty() {
+        return Err(join_errors(&lex_errors));
+    }
+
+    let comp = match Parser::parse(&mut tc) {
+        Ok(comp) => comp,
+        Err(errors) => return Err(join_errors(&errors)),
+    };
+
+    if stages.contains(&Emit::Ast) {
+        push(Emit::Ast, None, format!("{:#?}\n", comp));
+    }
+
+    if let Err(errors) = Semantic::check(&comp) {
+        return Err(join_errors(&errors));
+    }
+
+    Ok(comp)
+}
+
+/// Runs `source` on the AST interpreter, reading `input`. Returns what the
+/// program wrote, or why it stopped.
+pub fn interpret_source(source: &str, input: &[i32]) -> Result<String, String> {
+    let run = run_source(source, input);
+    match run.stop {
+        None => Ok(run.output),
+        Some((_, message)) => Err(message),
+    }
+}
+
+/// Same as `interpret_source`, keeping what was written before the program
+/// stopped.
+pub fn run_source(source: &str, input: &[i32]) -> Run {
+    let mut io = BufferedIo::new(input);
+    let result = catch_panic(|| {
+        let comp = checked_ast(source, &[], &mut Vec::new())?;
+        Ok(ast_interpret(&comp, &mut io))
+    });
+    match result {
+        Ok(Ok(())) => Run::finished(io.output()),
+        Ok(Err(error)) => Run::stopped(io.output(), error.get_kind(), error.to_string()),
+        Err(message) => Run::failed(message),
+    }
+}
+
+/// Runs `source` on the IR interpreter straight from the parser and again
+/// after every pass in `passes`, each time reading `input`. Returns every
+/// run as it went. When the compiler fails or panics, the last run is the
+/// failure of the stage that was being built.
+pub fn interpret_stages(source: &str, passes: &PassPipeline, input: &[i32]) -> Vec<StageRun> {
+    let mut runs = Vec::new();
+    let mut building = Emit::Ir;
+    let optimized = catch_panic(|| {
+        let irgmanager = front_end(source, &[], &mut Vec::new())?;
+        optimize(
+            Optimizer::new(irgmanager),
+            passes,
+            |stage| building = stage,
+            |stage, irgm| runs.push((stage, run_graph(irgm, input))),
+        );
+        Ok(())
+    });
+    if let Err(message) = optimized {
+        runs.push((building, Run::failed(message)));
+    }
+    runs
+}
+
+fn run_graph(irgm: &mut IRGraphManager, input: &[i32]) -> Run {
+    let mut io = BufferedIo::new(input);
+    match catch_panic(|| Ok(interpret(irgm, &mut io))) {
+        Ok(Ok(())) => Run::finished(io.output()),
+        Ok(Err(error)) => Run::stopped(io.output(), error.get_kind(), error.to_string()),
+        Err(message) => Run::stopped(io.output(), StopKind::Other, message),
+    }
+}
+
+/// Compiles `source` with `passes` and runs the program on the DLX
+/// emulator, reading `input`.
+pub fn run_program(source: &str, passes: &PassPipeline, input: &[i32]) -> Run {
+    let program = match compile_stages(source, passes, &[], &mut Vec::new()) {
+        Ok(program) => program,
+        Err(message) => return Run::failed(message),
+    };
+    let mut dlx = DLX::new();
+    if let Err(error) = dlx.load(&program) {
+        return Run::failed(error.to_string());
+    }
+    dlx.set_input(input);
+    match dlx.execute() {
+        Ok(exit_state) => Run::finished(&exit_state.output),
+        Err(error) => {
+            let kind = match error.kind {
+                DlxErrorKind::DivisionByZero => StopKind::DivisionByZero,
+                DlxErrorKind::InputExhausted => StopKind::InputExhausted,
+                DlxErrorKind::BudgetExhausted(_) => StopKind::BudgetExhausted,
+                _ => StopKind::Other,
+            };
+            Run::stopped(dlx.output(), kind, error.to_string())
+        }
+    }
+}
+
+/// First stage whose run of a program disagrees with the AST interpreter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    // `Program` stands for the generated code on the DLX emulator.
+    pub stage: Emit,
+    pub expected: Run,
+    pub found: Run,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} diverges: expected {} but found {}",
+            self.stage.name(),
+            self.expected,
+            self.found
+        )
+    }
+}
+
+/// Runs `source` on `input` through the AST interpreter, the IR
+/// interpreter after every pass in `passes` and the DLX emulator, in
+/// that order, and returns the first run that does not match the AST one
+/// (see `Run::matches`).
+pub fn check_stages(source: &str, passes: &PassPipeline, input: &[i32]) -> Result<(), Divergence> {
+    let expected = run_source(source, input);
+
+    let mut runs = interpret_stages(source, passes, input);
+    runs.push((Emit::Program, run_program(source, passes, input)));
+
+    for (stage, found) in runs {
+        if !expected.matches(&found) {
+            return Err(Divergence {
+                stage,
+                expected,
+                found,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the program `generator` makes from every seed in `seeds` with
+/// `check_stages`, returning the seeds whose program diverged.
+pub fn fuzz(seeds: Range<u64>, passes: &PassPipeline) -> Vec<(GeneratedProgram, Divergence)> {
+    let mut divergences = Vec::new();
+    for seed in seeds {
+        let program = generator::generate(seed);
+        if let Err(divergence) = check_stages(&program.source, passes, &program.input) {
+            divergences.push((program, divergence));
+        }
+    }
+    divergences
+}
+
+/// Runs `passes`, handing the graph to `after_stage` as it comes from the
+/// parser and after each pass, and naming each pass to `before_stage` as it
+/// starts. A pass that is repeated until a fixpoint hands it over after
+/// every round.
+fn optimize<B, F>(mut optimizer: Optimizer, passes: &PassPipeline, mut before_stage: B, mut after_stage: F) -> Optimizer
+where
+    B: FnMut(Emit),
+    F: FnMut(Emit, &mut IRGraphManager),
+{
+    let names = pass_names();
+    after_stage(Emit::Ir, optimizer.get_irgm_mut_ref());
+    passes.run_observed(
+        &mut optimizer,
+        |pass| before_stage(Emit::After(names[pass])),
+        |pass, irgm| after_stage(Emit::After(names[pass]), irgm),
+    );
+    optimizer
+}
+
+/// Everything from the freshly built IR on: the optimizer passes, register
+/// allocation and code generation.
+fn compile_graph(
+    irgmanager: IRGraphManager,
+    passes: &PassPipeline,
+    stages: &[Emit],
+    outputs: &mut Vec<StageOutput>,
+) -> Result<Vec<i32>, String> {
+    let mut push = |stage: Emit, func_name: Option<String>, text: String| {
+        // Passes repeated until a fixpoint come back every round, only the
+        // last round is kept.
+        outputs.retain(|output| output.stage != stage || output.func_name != func_name);
+        outputs.push(StageOutput {
+            stage,
+            func_name,
+            text,
+        });
+    };
+
+    let check = stages.contains(&Emit::Verify);
+    let mut violations = String::new();
+    let mut optimizer = Optimizer::new(irgmanager);
+    if stages.contains(&Emit::Stats) || stages.contains(&Emit::StatsJson) {
+        optimizer = optimizer.with_change_counts();
+    }
+    let optimizer = optimize(optimizer, passes, |_| {}, |stage, irgm| {
+        if check {
+            verify_listing(&mut violations, &stage, irgm);
+        }
+        if stages.contains(&stage) {
+            push(stage, None, ir_text::to_text(irgm));
+        }
+    });
+    if check {
+        push(Emit::Verify, None, violations);
+    }
+
+    let mut stats = optimizer.get_stats();
+    let mut main_temp_manager = optimizer.get_main_temp();
+    let mut func_temp_manager = optimizer.get_func_temp();
+    let mut irgmanager = optimizer.get_irgm();
+
+    let mut register_map = HashMap::new();
+    for (func_name, entry_id) in irgmanager.function_entries() {
+        let temp_manager = match func_temp_manager.get_mut(&func_name) {
+            Some(temp_manager) if func_name != "main" => temp_manager,
+            _ => &mut main_temp_manager,
+        };
+
+        let mut func_stats = PassStats::new("regalloc", &func_name);
+        let start = Instant::now();
+        let mut interference_dot = String::new();
+        let (func_register_map, spill_rounds) = if stages.contains(&Emit::Interference) {
+            analyze_live_range(&mut irgmanager, temp_manager, entry_id, Some(&mut interference_dot))
+        } else {
+            analyze_live_range(&mut irgmanager, temp_manager, entry_id, None)
+        };
+        func_stats.time = start.elapsed();
+        func_stats.spill_rounds = spill_rounds;
+        stats.push(func_stats);
+
+        if stages.contains(&Emit::Interference) {
+            push(Emit::Interference, Some(func_name), interference_dot);
+        }
+
+        register_map.extend(func_register_map);
+    }
+
+    if stages.contains(&Emit::Stats) {
+        push(Emit::Stats, None, stats_table(&stats));
+    }
+    if stages.contains(&Emit::StatsJson) {
+        push(Emit::StatsJson, None, stats_json(&stats));
+    }
+
+    let program = CodeGen::generate_program(&mut irgmanager, register_map)?;
+
+    if stages.contains(&Emit::Cfg) {
+        push(Emit::Cfg, None, cfg_dot(&mut irgmanager));
+    }
+
+    if stages.contains(&Emit::Asm) {
+        push(Emit::Asm, None, assembly_listing(&program));
+    }
+
+    Ok(program)
+}
+
+// One line per violation, prefixed by the stage that left it behind.
+fn verify_listing(text: &mut String, stage: &Emit, irgm: &mut IRGraphManager) {
+    match verify(irgm) {
+        Ok(()) => {
+            writeln!(text, "{}: ok", stage.name());
+        }
+        Err(errors) => {
+            for error in errors {
+                writeln!(text, "{}: {}", stage.name(), error);
+            }
+        }
+    }
+}
+
+fn cfg_dot(irgm: &mut IRGraphManager) -> String {
+    // Dominance edges are only added for the picture, code generation
+    // has already walked the control flow edges.
+    let root = irgm.graph_manager().get_main_node();
+    let graph = irgm.graph_manager().get_mut_ref_graph().clone();
+    let dom_space = simple_fast(&graph, root);
+    for node in graph.node_indices() {
+        if let Some(parent_node) = dom_space.immediate_dominator(node) {
+            irgm.graph_manager().add_dominance_edge(node, parent_node);
+        }
+    }
+
+    let mut text = String::new();
+    write!(
+        text,
+        "{:?}",
+        display::Dot::with_config(
+            &irgm.graph_manager().get_mut_ref_graph().clone(),
+            &[display::Config::EdgeColor]
+        )
+    );
+    text
+}
+
+fn assembly_listing(program: &[i32]) -> String {
+    let mut dlx = DLX::new();
+    let mut text = String::new();
+    for (word_index, word) in program.iter().enumerate() {
+        write!(text, "{:5}: {}", word_index * 4, dlx.disassemble(*word));
+    }
+    text
+}
@@ -41,6 +41,22 @@ impl GraphManager {
         }
     }
 
+    /// Manager around a graph that was built elsewhere, main starting at
+    /// `main_node_index` behind `entrance_index`.
+    pub fn from_graph(
+        graph: Graph<Node, String, Directed, u32>,
+        entrance_index: NodeIndex,
+        main_node_index: NodeIndex,
+    ) -> Self {
+        GraphManager {
+            graph,
+            block_to_id_map: HashMap::new(),
+            current_node_index: main_node_index,
+            main_node_index,
+            entrance_index,
+        }
+    }
+
     // -- Node Related Functions -- //
 
     pub fn new_node(
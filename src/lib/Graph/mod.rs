@@ -4,3 +4,138 @@ pub mod node;
 
 use std::cell::RefCell;
 use std::rc::Rc;
+
+use self::node::{Node, NodeType};
+use super::petgraph;
+use lib::IR::ir::{ValTy, Value};
+use petgraph::algo::dominators::Dominators;
+use petgraph::prelude::NodeIndex;
+use petgraph::{Directed, Graph, Outgoing};
+
+/// Copy of the graph with only the control flow ("black") edges left.
+pub fn control_flow_graph(graph: &Graph<Node, String, Directed, u32>) -> Graph<Node, String, Directed, u32> {
+    graph.filter_map(
+        |_, node| Some(node.clone()),
+        |_, edge| {
+            if edge == "black" {
+                Some(edge.clone())
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Whether the phis of `phi_node_id` take their x operand when entered
+/// from `parent_id`. While headers get x from the back edge, if joins get
+/// x from the if side (the else side or the header itself provide y).
+pub fn is_phi_x_side(
+    graph: &Graph<Node, String, Directed, u32>,
+    dom_space: &Dominators<NodeIndex>,
+    parent_id: NodeIndex,
+    phi_node_id: NodeIndex,
+) -> bool {
+    let header_id = match dom_space.immediate_dominator(phi_node_id) {
+        Some(header_id) => header_id,
+        None => return false,
+    };
+
+    if graph.node_weight(phi_node_id).unwrap().get_node_type() == NodeType::while_loop_header {
+        return parent_id != header_id;
+    }
+
+    if parent_id == header_id {
+        return false;
+    }
+
+    let mut branch_top = parent_id;
+    while let Some(dom_id) = dom_space.immediate_dominator(branch_top) {
+        if dom_id == header_id {
+            break;
+        }
+        branch_top = dom_id;
+    }
+
+    graph.node_weight(branch_top).unwrap().get_node_type() == NodeType::if_node
+}
+
+/// Successors of `node_id` without repeats, in the order code generation
+/// and the interpreter pick from.
+pub fn block_successors(graph: &Graph<Node, String, Directed, u32>, node_id: NodeIndex) -> Vec<NodeIndex> {
+    let mut successors = Vec::new();
+    for child_id in graph.neighbors_directed(node_id, Outgoing) {
+        if !successors.contains(&child_id) {
+            successors.push(child_id);
+        }
+    }
+    successors.reverse();
+    successors
+}
+
+/// Where a block without a conditional branch goes. Constant evaluation
+/// can leave an extra edge to the exit behind a pruned branch, the real
+/// path is preferred.
+pub fn fall_through_successor(
+    graph: &Graph<Node, String, Directed, u32>,
+    successors: &[NodeIndex],
+) -> Option<NodeIndex> {
+    successors
+        .iter()
+        .find(|child_id| !is_exit(graph, **child_id))
+        .or(successors.first())
+        .cloned()
+}
+
+/// Finds which successor a conditional branch jumps to and which one it
+/// falls through to, in that order. The body of an if or while always
+/// follows the header, anywhere else the stored branch target tells them
+/// apart (the cleaner points it at the target again when nodes move).
+pub fn branch_targets(
+    graph: &Graph<Node, String, Directed, u32>,
+    node_id: NodeIndex,
+    successors: &[NodeIndex],
+    target_val: &Value,
+) -> Result<(NodeIndex, NodeIndex), String> {
+    let mut successors = successors.to_vec();
+    if successors.len() > 2 {
+        successors.retain(|child_id| !is_exit(graph, *child_id));
+    }
+
+    match successors.len() {
+        1 => Ok((successors[0], successors[0])),
+        2 => {
+            let body_position = successors.iter().position(|child_id| {
+                matches!(
+                    graph.node_weight(*child_id).unwrap().get_node_type(),
+                    NodeType::if_node | NodeType::while_node
+                )
+            });
+
+            let fall_position = match body_position {
+                Some(position) => position,
+                None => match target_val.get_value() {
+                    ValTy::node_id(target_id) if successors.contains(target_id) => {
+                        successors.iter().position(|child_id| child_id != target_id).unwrap()
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Unable to determine branch target out of node {}.",
+                            node_id.index()
+                        ))
+                    }
+                },
+            };
+
+            Ok((successors[1 - fall_position], successors[fall_position]))
+        }
+        _ => Err(format!(
+            "Branch in node {} has {} successors.",
+            node_id.index(),
+            successors.len()
+        )),
+    }
+}
+
+fn is_exit(graph: &Graph<Node, String, Directed, u32>, node_id: NodeIndex) -> bool {
+    graph.node_weight(node_id).unwrap().get_node_type() == NodeType::exit
+}
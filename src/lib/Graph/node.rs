@@ -76,6 +76,7 @@ pub enum NodeType {
     function_head,
     main_node,
     while_loop_header,
+    loop_preheader,
     if_header,
     if_node,
     else_node,
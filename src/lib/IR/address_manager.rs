@@ -38,6 +38,13 @@ impl AddressManager {
         UniqueAddress::new(String::from("baseReg"), self.base_addr.clone())
     }
 
+    /// Slot `index` of the outgoing argument area just above the stack
+    /// pointer. Slots are named by position so that calls to different
+    /// functions agree on where each argument goes.
+    pub fn get_arg_slot(&self, index: usize) -> UniqueAddress {
+        UniqueAddress::new(format!("arg{}", index), index)
+    }
+
     pub fn get_addr_assignment(&mut self, addr_name: &String, size: usize) -> UniqueAddress {
         let current_clone = self.current_available_addr.clone();
         self.current_available_addr += size;
@@ -60,6 +67,14 @@ impl UniqueAddress {
         }
     }
 
+    pub fn get_base_ident(&self) -> String {
+        self.base_ident.clone()
+    }
+
+    pub fn get_register_value(&self) -> usize {
+        self.register_value
+    }
+
     pub fn to_string(&self) -> String {
         let t_str = String::from("&") + &self.base_ident.clone();
         t_str
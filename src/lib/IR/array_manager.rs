@@ -31,6 +31,10 @@ impl ArrayManager {
         self.array_global.contains(array_ident)
     }
 
+    pub fn contains_array(&self, array_ident: &String) -> bool {
+        self.array_manager.contains_key(array_ident)
+    }
+
     pub fn add_global(&mut self, array_ident: &String, array_depth: Vec<Number>) {
         self.array_global.push(array_ident.clone());
         self.add_array(array_ident, array_depth);
@@ -43,6 +47,29 @@ impl ArrayManager {
         );
     }
 
+    /// Declares an array by its dimensions, for arrays that do not come
+    /// from the AST.
+    pub fn add_array_dims(&mut self, array_ident: &str, array_depth: Vec<i32>, is_global: bool) {
+        if is_global {
+            self.array_global.push(array_ident.to_owned());
+        }
+        self.array_manager.insert(
+            array_ident.to_owned(),
+            UniqueArray {
+                base_ident: array_ident.to_owned(),
+                array_depth,
+                uniq_addr: None,
+            },
+        );
+    }
+
+    /// Every declared array, ordered by name.
+    pub fn list_arrays(&self) -> Vec<&UniqueArray> {
+        let mut arrays = self.array_manager.values().collect::<Vec<_>>();
+        arrays.sort_by(|a, b| a.base_ident.cmp(&b.base_ident));
+        arrays
+    }
+
     pub fn assign_addr(&mut self, array_ident: String, uniq_addr: UniqueAddress) {
         self.array_manager
             .get_mut(&array_ident)
@@ -202,6 +229,14 @@ impl UniqueArray {
         iter.product()
     }
 
+    pub fn get_ident(&self) -> String {
+        self.base_ident.clone()
+    }
+
+    pub fn get_dims(&self) -> &Vec<i32> {
+        &self.array_depth
+    }
+
     pub fn to_string(&self) -> String {
         let t_str = String::from("%") + &self.base_ident;
         t_str
@@ -31,6 +31,19 @@ impl FunctionManager {
         self.func_manager.insert(func.get_name(), func);
     }
 
+    pub fn has_function(&self, func_name: &String) -> bool {
+        self.func_manager.contains_key(func_name)
+    }
+
+    /// Number of globals read or written across every function, which
+    /// only grows as calls pull in the globals of their callees.
+    pub fn side_effect_count(&self) -> usize {
+        self.func_manager
+            .values()
+            .map(|func| func.affected_globals.len() + func.assigned_globals.len())
+            .sum()
+    }
+
     pub fn get_function(&self, func_name: &String) -> UniqueFunction {
         self.func_manager
             .get(func_name)
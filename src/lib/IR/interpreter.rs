@@ -0,0 +1,581 @@
+//! Runs an `IRGraphManager` directly, before register allocation and code
+//! generation, so the output of a program can be checked against itself
+//! after every optimizer pass.
+//!
+//! Memory is a map from byte address to word. The stack starts at 0 and
+//! grows up, one frame per call laid out the way the `AddressManager`
+//! names it: `&FP &x` is parameter `x` (by position) or a local slot after
+//! the parameters, `&SP &argN` is the Nth argument of the next call, which
+//! is where the callee finds its parameters. Globals sit below 0, each one
+//! placed the first time `&globalReg &x` is taken.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use lib::Graph::{block_successors, branch_targets, control_flow_graph, fall_through_successor, is_phi_x_side};
+use lib::Graph::node::{Node, NodeType};
+use lib::IR::address_manager::UniqueAddress;
+use lib::IR::array_manager::ArrayManager;
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::MAIN_NAME;
+use lib::Utility::DEFAULT_INSTRUCTION_BUDGET;
+
+use super::Graph;
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::Dfs;
+use petgraph::Directed;
+
+/// Where `read`, `write` and `writeNL` go.
+pub trait Io {
+    fn read(&mut self) -> Result<i32, String>;
+    fn write(&mut self, value: i32);
+    fn write_nl(&mut self);
+}
+
+/// Reads from a fixed list of values and keeps the output as the DLX
+/// emulator prints it, so the two can be compared directly.
+#[derive(Debug, Clone, Default)]
+pub struct BufferedIo {
+    input: VecDeque<i32>,
+    output: String,
+}
+
+impl BufferedIo {
+    pub fn new(input: &[i32]) -> Self {
+        BufferedIo {
+            input: input.iter().cloned().collect(),
+            output: String::new(),
+        }
+    }
+
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+impl Io for BufferedIo {
+    fn read(&mut self) -> Result<i32, String> {
+        self.input
+            .pop_front()
+            .ok_or_else(|| String::from("read found no more input"))
+    }
+
+    fn write(&mut self, value: i32) {
+        self.output.push_str(&format!("{}  ", value));
+    }
+
+    fn write_nl(&mut self) {
+        self.output.push('\n');
+    }
+}
+
+/// What made a program stop early, named the same by every engine that
+/// runs it so their runs can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopKind {
+    DivisionByZero,
+    InputExhausted,
+    IndexOutOfBounds,
+    BudgetExhausted,
+    /// The compiler rejected the program or failed on it, so it never ran.
+    CompileError,
+    /// Anything only a broken program or compiler runs into.
+    Other,
+}
+
+/// Why a program stopped early, located by block and instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpretError {
+    kind: StopKind,
+    block: usize,
+    inst: Option<usize>,
+    message: String,
+}
+
+impl InterpretError {
+    pub fn new(block: usize, inst: Option<usize>, message: String) -> Self {
+        InterpretError {
+            kind: StopKind::Other,
+            block,
+            inst,
+            message,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: StopKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn get_kind(&self) -> StopKind {
+        self.kind
+    }
+
+    pub fn get_block(&self) -> usize {
+        self.block
+    }
+
+    pub fn get_inst(&self) -> Option<usize> {
+        self.inst
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inst {
+            Some(inst) => write!(f, "b{} ({}): {}", self.block, inst, self.message),
+            None => write!(f, "b{}: {}", self.block, self.message),
+        }
+    }
+}
+
+/// Runs main of `irgm` to completion.
+pub fn interpret(irgm: &mut IRGraphManager, io: &mut dyn Io) -> Result<(), InterpretError> {
+    Interpreter::new(irgm).run(io)
+}
+
+/// One call in progress.
+struct Frame {
+    func_name: String,
+    values: HashMap<usize, i32>,
+    fp: i32,
+    sp: i32,
+    node_id: NodeIndex,
+    position: usize,
+    // Outcome of the conditional branch of the current block, if it has one.
+    branch: Option<(bool, Value)>,
+    // Call in the caller that receives the result.
+    call_inst: Option<usize>,
+}
+
+/// Why an instruction could not run, before it is located.
+struct Fault {
+    kind: StopKind,
+    message: String,
+}
+
+impl From<String> for Fault {
+    fn from(message: String) -> Self {
+        Fault {
+            kind: StopKind::Other,
+            message,
+        }
+    }
+}
+
+/// What happens after an instruction or at the end of a block.
+enum Flow {
+    Next,
+    Jump(NodeIndex),
+    Call(String, usize),
+    Return(Option<i32>),
+}
+
+pub struct Interpreter {
+    graph: Graph<Node, String, Directed, u32>,
+    arrays: ArrayManager,
+    entries: HashMap<String, NodeIndex>,
+    dominators: HashMap<String, Dominators<NodeIndex>>,
+    frame_layouts: HashMap<String, HashMap<String, i32>>,
+    frame_sizes: HashMap<String, i32>,
+
+    globals: HashMap<String, i32>,
+    global_size: i32,
+    memory: HashMap<i32, i32>,
+    last_return: i32,
+    instruction_budget: Option<u64>,
+}
+
+impl Interpreter {
+    pub fn new(irgm: &mut IRGraphManager) -> Self {
+        let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+        let arrays = irgm.array_manager().clone();
+
+        let mut interpreter = Interpreter {
+            graph,
+            arrays,
+            entries: HashMap::new(),
+            dominators: HashMap::new(),
+            frame_layouts: HashMap::new(),
+            frame_sizes: HashMap::new(),
+            globals: HashMap::new(),
+            global_size: 0,
+            memory: HashMap::new(),
+            last_return: 0,
+            // Same limit the DLX emulator puts on runaway programs.
+            instruction_budget: Some(DEFAULT_INSTRUCTION_BUDGET),
+        };
+
+        for (func_name, entry_id) in irgm.function_entries() {
+            let params = if func_name == MAIN_NAME {
+                Vec::new()
+            } else {
+                irgm.function_manager().get_function(&func_name).load_param_list()
+            };
+            interpreter.lay_out_frame(&func_name, entry_id, &params);
+
+            let dom_space = simple_fast(&interpreter.graph, entry_id);
+            interpreter.dominators.insert(func_name.clone(), dom_space);
+            interpreter.entries.insert(func_name, entry_id);
+        }
+
+        interpreter
+    }
+
+    /// `None` lets the program run for as long as it takes.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+    /// Parameters come first by position, then every other `&FP` slot the
+    /// function takes, in the order they first show up.
+    fn lay_out_frame(&mut self, func_name: &str, entry_id: NodeIndex, params: &[String]) {
+        let mut layout = HashMap::new();
+        for (position, param) in params.iter().enumerate() {
+            layout.insert(param.clone(), position as i32 * 4);
+        }
+        let mut frame_size = params.len() as i32 * 4;
+
+        let mut dfs = Dfs::new(&self.graph, entry_id);
+        while let Some(node_id) = dfs.next(&self.graph) {
+            for inst in self.graph[node_id].get_data_ref().get_inst_list_ref() {
+                if let (Some(x_val), Some(y_val)) = inst.borrow().get_val_ty() {
+                    if let (ValTy::adr(base_addr), ValTy::adr(uniq_addr)) = (x_val, y_val) {
+                        let ident = uniq_addr.get_base_ident();
+                        if base_addr.get_base_ident() == "FP" && !layout.contains_key(&ident) {
+                            layout.insert(ident.clone(), frame_size);
+                            frame_size += self.size_of(&ident);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.frame_layouts.insert(func_name.to_owned(), layout);
+        self.frame_sizes.insert(func_name.to_owned(), frame_size);
+    }
+
+    fn size_of(&self, ident: &String) -> i32 {
+        if self.arrays.contains_array(ident) {
+            self.arrays.get_array_ref(ident.clone()).get_size() as i32 * 4
+        } else {
+            4
+        }
+    }
+
+    /// Runs main until it returns, ends or falls into its exit block.
+    pub fn run(&mut self, io: &mut dyn Io) -> Result<(), InterpretError> {
+        let main_frame = self
+            .new_frame(MAIN_NAME, 0, None)
+            .map_err(|message| InterpretError::new(0, None, message))?;
+        let mut stack = vec![main_frame];
+        let mut executed: u64 = 0;
+
+        loop {
+            let flow = {
+                let frame = stack.last_mut().unwrap();
+                let block = frame.node_id.index();
+                let inst = self.graph[frame.node_id]
+                    .get_data_ref()
+                    .get_inst_list_ref()
+                    .get(frame.position)
+                    .map(|inst| inst.borrow().clone());
+
+                match inst {
+                    Some(op) => {
+                        frame.position += 1;
+                        if !op.is_active() {
+                            continue;
+                        }
+
+                        if let Some(budget) = self.instruction_budget {
+                            if executed >= budget {
+                                let message = format!("stopped after {} instructions", executed);
+                                return Err(InterpretError::new(block, Some(op.get_inst_num()), message)
+                                    .with_kind(StopKind::BudgetExhausted));
+                            }
+                        }
+                        executed += 1;
+
+                        self.step(frame, &op, io).map_err(|fault| {
+                            InterpretError::new(block, Some(op.get_inst_num()), fault.message).with_kind(fault.kind)
+                        })?
+                    }
+                    None => self
+                        .leave_block(frame)
+                        .map_err(|message| InterpretError::new(block, None, message))?,
+                }
+            };
+
+            match flow {
+                Flow::Next => {}
+                Flow::Jump(child_id) => {
+                    let frame = stack.last_mut().unwrap();
+                    let block = frame.node_id.index();
+                    self.enter_block(frame, child_id)
+                        .map_err(|message| InterpretError::new(block, None, message))?;
+                }
+                Flow::Call(func_name, inst_num) => {
+                    let (block, sp) = {
+                        let frame = stack.last().unwrap();
+                        (frame.node_id.index(), frame.sp)
+                    };
+                    // The callee's parameters are the caller's argument slots.
+                    let frame = self
+                        .new_frame(&func_name, sp + 4, Some(inst_num))
+                        .map_err(|message| InterpretError::new(block, Some(inst_num), message))?;
+                    stack.push(frame);
+                }
+                Flow::Return(value) => {
+                    let callee = stack.pop().unwrap();
+                    let caller = match stack.last_mut() {
+                        Some(caller) => caller,
+                        None => return Ok(()),
+                    };
+
+                    if let (Some(value), Some(call_inst)) = (value, callee.call_inst) {
+                        self.last_return = value;
+                        caller.values.insert(call_inst, value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn new_frame(&self, func_name: &str, fp: i32, call_inst: Option<usize>) -> Result<Frame, String> {
+        let entry_id = match self.entries.get(func_name) {
+            Some(entry_id) => *entry_id,
+            None => return Err(format!("call to unknown function {}", func_name)),
+        };
+        let frame_size = self.frame_sizes[func_name];
+
+        Ok(Frame {
+            func_name: func_name.to_owned(),
+            values: HashMap::new(),
+            fp,
+            sp: fp + frame_size - 4,
+            node_id: entry_id,
+            position: 0,
+            branch: None,
+            call_inst,
+        })
+    }
+
+    fn step(&mut self, frame: &mut Frame, op: &Op, io: &mut dyn Io) -> Result<Flow, Fault> {
+        let (x_val, y_val, special_val) = op.get_values();
+        let inst_num = op.get_inst_num();
+        let inst_type = op.inst_type().clone();
+
+        let result = match inst_type {
+            InstTy::add | InstTy::sadd | InstTy::adda => {
+                let (x_val, y_val) = expect_x_y(x_val, y_val)?;
+                match (x_val.get_value(), y_val.get_value()) {
+                    (ValTy::adr(base_addr), ValTy::adr(uniq_addr)) => {
+                        self.slot_address(frame, &base_addr.get_base_ident(), uniq_addr)?
+                    }
+                    _ => self.value(frame, &x_val)?.wrapping_add(self.value(frame, &y_val)?),
+                }
+            }
+            InstTy::sub | InstTy::mul | InstTy::div | InstTy::cmp => {
+                let (x_val, y_val) = expect_x_y(x_val, y_val)?;
+                let x = self.value(frame, &x_val)?;
+                let y = self.value(frame, &y_val)?;
+                match inst_type {
+                    InstTy::sub => x.wrapping_sub(y),
+                    InstTy::mul => x.wrapping_mul(y),
+                    InstTy::div if y == 0 => {
+                        return Err(Fault {
+                            kind: StopKind::DivisionByZero,
+                            message: String::from("division by zero"),
+                        })
+                    }
+                    InstTy::div => x.wrapping_div(y),
+                    _ => match x.cmp(&y) {
+                        Ordering::Less => -1,
+                        Ordering::Equal => 0,
+                        Ordering::Greater => 1,
+                    },
+                }
+            }
+            InstTy::neg => self.value(frame, &expect_value(x_val)?)?.wrapping_neg(),
+            InstTy::load | InstTy::sload => {
+                let address = self.value(frame, &expect_value(y_val)?)?;
+                self.memory.get(&address).cloned().unwrap_or(0)
+            }
+            InstTy::store => {
+                let (x_val, y_val) = expect_x_y(x_val, y_val)?;
+                let address = self.value(frame, &x_val)?;
+                let value = self.value(frame, &y_val)?;
+                self.memory.insert(address, value);
+                return Ok(Flow::Next);
+            }
+            InstTy::read => io.read().map_err(|message| Fault {
+                kind: StopKind::InputExhausted,
+                message,
+            })?,
+            InstTy::write => {
+                io.write(self.value(frame, &expect_value(x_val)?)?);
+                return Ok(Flow::Next);
+            }
+            InstTy::writeNL => {
+                io.write_nl();
+                return Ok(Flow::Next);
+            }
+            InstTy::call => {
+                let func_name = special_val.ok_or_else(|| String::from("call has no function name"))?;
+                return Ok(Flow::Call(func_name, inst_num));
+            }
+            InstTy::ret => {
+                if frame.func_name == MAIN_NAME {
+                    return Ok(Flow::Return(None));
+                }
+                let value = match x_val {
+                    Some(x_val) => Some(self.value(frame, &x_val)?),
+                    None => None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            InstTy::end => return Ok(Flow::Return(None)),
+            InstTy::bra => {
+                // Jumps between blocks follow the edges, only the branch on
+                // SP (return from a procedure) does anything.
+                if let Some(ValTy::adr(_)) = y_val.map(|val| val.clone_value()) {
+                    return Ok(Flow::Return(None));
+                }
+                return Ok(Flow::Next);
+            }
+            InstTy::bne | InstTy::beq | InstTy::blt | InstTy::bge | InstTy::ble | InstTy::bgt => {
+                let (x_val, y_val) = expect_x_y(x_val, y_val)?;
+                let cond = self.value(frame, &x_val)?;
+                let taken = match inst_type {
+                    InstTy::bne => cond != 0,
+                    InstTy::beq => cond == 0,
+                    InstTy::blt => cond < 0,
+                    InstTy::bge => cond >= 0,
+                    InstTy::ble => cond <= 0,
+                    _ => cond > 0,
+                };
+                frame.branch = Some((taken, y_val));
+                return Ok(Flow::Next);
+            }
+            // Phis are evaluated on the way into their block.
+            InstTy::phi | InstTy::kill => return Ok(Flow::Next),
+            InstTy::mov => return Err(String::from("move is not supported").into()),
+        };
+
+        frame.values.insert(inst_num, result);
+        Ok(Flow::Next)
+    }
+
+    /// Picks the successor the same way code generation lays out the
+    /// jumps: the body of an if or while is the fall through of a
+    /// conditional branch, and an extra edge to the exit left behind a
+    /// pruned branch is only taken when there is nothing else.
+    fn leave_block(&self, frame: &mut Frame) -> Result<Flow, String> {
+        let node_id = frame.node_id;
+        if self.graph[node_id].get_node_type() == NodeType::exit {
+            return Ok(Flow::Return(None));
+        }
+
+        let successors = block_successors(&self.graph, node_id);
+        let (taken, target_val) = match frame.branch.take() {
+            Some(branch) => branch,
+            None => {
+                return Ok(match fall_through_successor(&self.graph, &successors) {
+                    Some(child_id) => Flow::Jump(child_id),
+                    None => Flow::Return(None),
+                });
+            }
+        };
+
+        let (taken_id, fall_id) = branch_targets(&self.graph, node_id, &successors, &target_val)?;
+        Ok(Flow::Jump(if taken { taken_id } else { fall_id }))
+    }
+
+    /// Moves into `child_id`, giving its phis the operand of the edge taken.
+    fn enter_block(&self, frame: &mut Frame, child_id: NodeIndex) -> Result<(), String> {
+        let dom_space = &self.dominators[&frame.func_name];
+        let is_x_side = is_phi_x_side(&self.graph, dom_space, frame.node_id, child_id);
+
+        // All phis read the values from before the edge.
+        let mut phi_values = Vec::new();
+        for inst in self.graph[child_id].get_data_ref().get_inst_list_ref() {
+            let op = inst.borrow();
+            if !op.is_active() || op.inst_type().clone() != InstTy::phi {
+                continue;
+            }
+
+            let operand = if is_x_side { op.clone_x_val() } else { op.clone_y_val() };
+            let operand = operand.ok_or_else(|| format!("phi ({}) is missing an operand", op.get_inst_num()))?;
+            phi_values.push((op.get_inst_num(), self.value(frame, &operand)?));
+        }
+
+        frame.values.extend(phi_values);
+        frame.node_id = child_id;
+        frame.position = 0;
+        frame.branch = None;
+        Ok(())
+    }
+
+    fn value(&self, frame: &Frame, value: &Value) -> Result<i32, String> {
+        match value.get_var_base() {
+            ValTy::op(op) => {
+                let inst_num = op.borrow().get_inst_num();
+                frame
+                    .values
+                    .get(&inst_num)
+                    .cloned()
+                    .ok_or_else(|| format!("uses ({}) before it is defined", inst_num))
+            }
+            ValTy::con(con) => Ok(con),
+            ValTy::reg(register) if register.to_usize() == 0 => Ok(0),
+            ValTy::ret(_) => Ok(self.last_return),
+            ValTy::adr(uniq_addr) => match uniq_addr.get_base_ident().as_ref() {
+                "globalReg" | "baseReg" => Ok(0),
+                "FP" => Ok(frame.fp),
+                "SP" => Ok(frame.sp),
+                ident => Err(format!("address &{} used without a base", ident)),
+            },
+            val_ty => Err(format!("{} has no value before register allocation", val_ty.to_string())),
+        }
+    }
+
+    fn slot_address(&mut self, frame: &Frame, base: &str, uniq_addr: &UniqueAddress) -> Result<i32, String> {
+        let ident = uniq_addr.get_base_ident();
+        match base {
+            "globalReg" => Ok(self.global_address(&ident)),
+            "FP" => match self.frame_layouts[&frame.func_name].get(&ident) {
+                Some(offset) => Ok(frame.fp + offset),
+                None => Err(format!("no frame slot for &{}", ident)),
+            },
+            "SP" => Ok(frame.sp + (uniq_addr.get_register_value() as i32 + 1) * 4),
+            base => Err(format!("address &{} can not be relative to &{}", ident, base)),
+        }
+    }
+
+    fn global_address(&mut self, ident: &String) -> i32 {
+        if let Some(address) = self.globals.get(ident) {
+            return *address;
+        }
+
+        self.global_size += self.size_of(ident);
+        let address = -self.global_size;
+        self.globals.insert(ident.clone(), address);
+        address
+    }
+}
+
+fn expect_value(value: Option<Value>) -> Result<Value, String> {
+    value.ok_or_else(|| String::from("missing an operand"))
+}
+
+fn expect_x_y(x_val: Option<Value>, y_val: Option<Value>) -> Result<(Value, Value), String> {
+    Ok((expect_value(x_val)?, expect_value(y_val)?))
+}
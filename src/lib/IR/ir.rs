@@ -360,6 +360,10 @@ impl Op {
         self.block_number.clone()
     }
 
+    pub fn update_inst_block(&mut self, new_block_num: usize) {
+        self.block_number = new_block_num;
+    }
+
     pub fn get_inst_num(&self) -> usize {
         self.inst_number.clone()
     }
@@ -373,11 +377,13 @@ impl Op {
     }
 
     pub fn op_cleanup(&mut self, var_to_clean: usize, replacement_op: Value) {
+        // An operand that is already borrowed is this instruction itself, a
+        // loop phi can be its own operand.
         match self.x_val.clone() {
             Some(val) => {
                 if let ValTy::op(op) = val.clone_value() {
-                    let op_id = op.borrow().get_inst_num();
-                    if op_id == var_to_clean {
+                    let op_id = op.try_borrow().ok().map(|op| op.get_inst_num());
+                    if op_id == Some(var_to_clean) {
                         self.x_val = Some(replacement_op.clone());
                     }
                 }
@@ -388,8 +394,8 @@ impl Op {
         match self.y_val.clone() {
             Some(val) => {
                 if let ValTy::op(op) = val.clone_value() {
-                    let op_id = op.borrow().get_inst_num();
-                    if op_id == var_to_clean {
+                    let op_id = op.try_borrow().ok().map(|op| op.get_inst_num());
+                    if op_id == Some(var_to_clean) {
                         self.y_val = Some(replacement_op.clone());
                     }
                 }
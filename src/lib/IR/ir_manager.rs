@@ -10,10 +10,12 @@ use super::address_manager::{AddressManager, UniqueAddress};
 use super::array_manager::{ArrayManager, UniqueArray};
 use super::function_manager::{FunctionManager, UniqueFunction};
 use super::variable_manager::{UniqueVariable, VariableManager};
-use super::Graph;
+use super::{Graph, MAIN_NAME};
 use petgraph::algo::dominators;
 use petgraph::algo::dominators::Dominators;
 use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Incoming;
 
 /// Rough Draft of IR_Manager Rewrite
 #[derive(Clone)]
@@ -63,6 +65,28 @@ impl IRGraphManager {
         }
     }
 
+    /// Manager around an already built graph, as read back from text.
+    /// Blocks are numbered from `block_count` on and instructions after
+    /// `last_inst`.
+    pub fn from_parts(
+        graph_manager: GraphManager,
+        array_manager: ArrayManager,
+        func_manager: FunctionManager,
+        block_count: usize,
+        last_inst: usize,
+    ) -> Self {
+        IRGraphManager {
+            bt: BlockTracker::starting_at(block_count),
+            it: InstTracker::starting_at(last_inst),
+            var_manager: VariableManager::new(),
+            array_manager,
+            addr_manager: AddressManager::new(),
+            func_manager,
+            is_func: false,
+            graph_manager,
+        }
+    }
+
     pub fn is_func(&self) -> bool {
         self.is_func
     }
@@ -244,7 +268,7 @@ impl IRGraphManager {
                 &self.var_manager.get_latest_unique(uniq_base),
             )));
 
-            self.var_manager.loop_correction(old_val, new_val);
+            self.var_manager.loop_correction(old_val, new_val, node_starting_point);
         }
 
         remove_use_vec
@@ -384,7 +408,16 @@ impl IRGraphManager {
 
     pub fn new_function(&mut self, func_name: String, func_index: &NodeIndex) {
         self.is_func = true;
-        let func = self.func_manager.new_function(&func_name, func_index);
+
+        // Functions declared up front already carry their parameters and
+        // globals, keep those.
+        let func = if self.func_manager.has_function(&func_name) {
+            let mut func = self.func_manager.get_function(&func_name);
+            func.update_index(*func_index);
+            func
+        } else {
+            self.func_manager.new_function(&func_name, func_index)
+        };
         self.array_manager.add_active_function(func.clone());
         self.var_manager.add_active_function(func);
     }
@@ -394,6 +427,30 @@ impl IRGraphManager {
         self.var_manager.get_active_function()
     }
 
+    /// Entry node of main followed by the entry of every function, sorted
+    /// by name so dumps come out in the same order every run.
+    pub fn function_entries(&mut self) -> Vec<(String, NodeIndex)> {
+        let mut entries = vec![(
+            String::from(MAIN_NAME),
+            self.graph_manager.get_main_entrance_node(),
+        )];
+
+        let mut functions = self.func_manager.list_functions();
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (func_name, func_root) in functions {
+            let entry_id = self
+                .graph_manager
+                .get_ref_graph()
+                .edges_directed(func_root, Incoming)
+                .find(|edge| edge.weight() == "black")
+                .map_or(func_root, |edge| edge.source());
+            entries.push((func_name, entry_id));
+        }
+
+        entries
+    }
+
     pub fn get_func_call(&mut self, func_name: &String) -> UniqueFunction {
         if self.is_func {
             if func_name.clone() == self.var_manager.active_function().get_name() {
@@ -415,6 +472,10 @@ impl InstTracker {
         InstTracker { inst_number: 0 }
     }
 
+    pub fn starting_at(inst_number: usize) -> InstTracker {
+        InstTracker { inst_number }
+    }
+
     pub fn increment(&mut self) {
         self.inst_number += 1;
     }
@@ -434,6 +495,10 @@ impl BlockTracker {
         BlockTracker { block_number: 0 }
     }
 
+    pub fn starting_at(block_number: usize) -> BlockTracker {
+        BlockTracker { block_number }
+    }
+
     pub fn increment(&mut self) {
         self.block_number += 1;
     }
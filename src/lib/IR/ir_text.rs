@@ -0,0 +1,776 @@
+//! Stable text form of an `IRGraphManager`, so IR can be checked into
+//! tests, written by hand and fed straight to the optimizer.
+//!
+//! ```text
+//! global array[4][4] grid
+//!
+//! main:
+//!   b0 entrance -> b1
+//!   b1 main_node -> b2
+//!     (1): read
+//!     (2): add &SP &arg0
+//!     (3): store (1) (2)
+//!     (4): call square
+//!     (5): write (4)
+//!     (6): ret #0
+//!   b2 exit
+//!
+//! function square(x):
+//!   b3 entrance -> b4
+//!   b4 function_head -> b5
+//!     (7): add &FP &x
+//!     (8): load (7)
+//!     (9): mul (8) (8)
+//!     (10): ret (9)
+//!   b5 exit
+//! ```
+//!
+//! Blocks are named by graph index and list their successors in the order
+//! the edges were added, which is the order codegen relies on. Join points
+//! also list their predecessors (`b5 phi_node <- b3 b4 -> b6`), phis take
+//! their operands by predecessor position. Every block a branch or a
+//! predecessor list names has to be declared, gaps between the declared
+//! indices come back as empty placeholders. The first block of a section is
+//! its entrance, the block
+//! it leads to is the root. Graphs without the shape `verify_shape` checks
+//! for are turned down.
+//!
+//! Instructions keep the operand order of `Op::to_string` and only active
+//! instructions are written. Operands are `(12)` for the value of an
+//! instruction, `#5` constants, `&a` addresses, `%a` arrays, `[b4]` blocks,
+//! `R3` registers and `R_27` for the return register. Variables are written
+//! as their current value. Anything after `;` is a comment.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fmt::Write;
+
+use lib::Graph::control_flow_graph;
+use lib::Graph::graph_manager::GraphManager;
+use lib::Graph::node::{Node, NodeType};
+use lib::IR::address_manager::UniqueAddress;
+use lib::IR::array_manager::ArrayManager;
+use lib::IR::function_manager::{FunctionManager, UniqueFunction};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::{BlockTracker, IRGraphManager};
+use lib::IR::ret_register::RetRegister;
+use lib::IR::verify::verify_shape;
+use lib::RegisterAllocator::RegisterAllocation;
+
+use super::{Graph, Rc, RefCell};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::DfsPostOrder;
+use petgraph::{Incoming, Outgoing};
+
+const INST_TYPES: [InstTy; 27] = [
+    InstTy::read,
+    InstTy::end,
+    InstTy::writeNL,
+    InstTy::kill,
+    InstTy::neg,
+    InstTy::write,
+    InstTy::ret,
+    InstTy::add,
+    InstTy::sadd,
+    InstTy::sub,
+    InstTy::mul,
+    InstTy::div,
+    InstTy::cmp,
+    InstTy::adda,
+    InstTy::bne,
+    InstTy::beq,
+    InstTy::ble,
+    InstTy::blt,
+    InstTy::bge,
+    InstTy::bgt,
+    InstTy::phi,
+    InstTy::load,
+    InstTy::sload,
+    InstTy::bra,
+    InstTy::store,
+    InstTy::mov,
+    InstTy::call,
+];
+
+const NODE_TYPES: [NodeType; 13] = [
+    NodeType::entrance,
+    NodeType::function_head,
+    NodeType::main_node,
+    NodeType::while_loop_header,
+    NodeType::loop_preheader,
+    NodeType::if_header,
+    NodeType::if_node,
+    NodeType::else_node,
+    NodeType::while_node,
+    NodeType::phi_node,
+    NodeType::bra_node,
+    NodeType::ignored,
+    NodeType::exit,
+];
+
+/// A line of IR text that could not be read.
+#[derive(Debug, Clone)]
+pub struct IrTextError {
+    message: String,
+    line: usize,
+}
+
+impl IrTextError {
+    pub fn new(message: String, line: usize) -> Self {
+        IrTextError { message, line }
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for IrTextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Which operands an instruction carries and the order they are written in.
+enum Operands {
+    Bare,
+    X,
+    Y,
+    XY,
+    YX,
+    Name,
+}
+
+fn operands_of(inst_type: &InstTy) -> Operands {
+    match inst_type {
+        InstTy::read | InstTy::end | InstTy::writeNL | InstTy::kill => Operands::Bare,
+        InstTy::neg | InstTy::write | InstTy::ret => Operands::X,
+        InstTy::load | InstTy::sload | InstTy::bra => Operands::Y,
+        InstTy::store | InstTy::mov => Operands::YX,
+        InstTy::call => Operands::Name,
+        _ => Operands::XY,
+    }
+}
+
+// -- Printing -- //
+
+/// Writes main and then every function, ordered by name.
+pub fn to_text(irgm: &mut IRGraphManager) -> String {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let mut text = String::new();
+
+    let arrays = irgm.array_manager().clone();
+    for array in arrays.list_arrays() {
+        let dims = array
+            .get_dims()
+            .iter()
+            .map(|dim| format!("[{}]", dim))
+            .collect::<String>();
+        let ident = array.get_ident();
+        if arrays.is_global(&ident) {
+            text += "global ";
+        }
+        let _ = writeln!(text, "array{} {}", dims, ident);
+    }
+
+    for (func_name, entry_id) in irgm.function_entries() {
+        if !text.is_empty() {
+            text += "\n";
+        }
+        if func_name == "main" {
+            text += "main:\n";
+        } else {
+            let func = irgm.function_manager().get_function(&func_name);
+            let _ = writeln!(
+                text,
+                "{} {}({}):",
+                if func.has_return() { "function" } else { "procedure" },
+                func_name,
+                func.load_param_list().join(", ")
+            );
+        }
+
+        let mut order = Vec::new();
+        let mut dfs = DfsPostOrder::new(&graph, entry_id);
+        while let Some(node_id) = dfs.next(&graph) {
+            order.push(node_id);
+        }
+        order.reverse();
+
+        // Blocks nothing reaches, such as the code after a return, can still
+        // lead into the ones that are reached. They follow at the end, so
+        // every predecessor named is declared.
+        let mut index = 0;
+        while index < order.len() {
+            for parent_id in graph.neighbors_directed(order[index], Incoming) {
+                if !order.contains(&parent_id) {
+                    order.push(parent_id);
+                }
+            }
+            index += 1;
+        }
+
+        for node_id in order {
+            let node = graph.node_weight(node_id).unwrap();
+            let mut successors = graph
+                .neighbors_directed(node_id, Outgoing)
+                .map(|child_id| format!("b{}", child_id.index()))
+                .collect::<Vec<String>>();
+            successors.reverse();
+            let mut predecessors = graph
+                .neighbors_directed(node_id, Incoming)
+                .map(|parent_id| format!("b{}", parent_id.index()))
+                .collect::<Vec<String>>();
+            predecessors.reverse();
+
+            let _ = write!(text, "  b{} {:?}", node_id.index(), node.get_node_type());
+            if predecessors.len() > 1 {
+                let _ = write!(text, " <- {}", predecessors.join(" "));
+            }
+            if !successors.is_empty() {
+                let _ = write!(text, " -> {}", successors.join(" "));
+            }
+            text += "\n";
+
+            for inst in node.get_data_ref().get_inst_list_ref() {
+                let inst = inst.borrow();
+                if inst.is_active() {
+                    let _ = writeln!(text, "    ({}): {}", inst.get_inst_num(), inst_text(&inst));
+                }
+            }
+        }
+    }
+
+    text
+}
+
+/// One instruction the way it is written, without its number.
+pub fn inst_text(inst: &Op) -> String {
+    let (x_val, y_val, special_val) = inst.get_values();
+    let operand = |val: Option<Value>| val.map_or(String::from("?"), |val| value_text(&val));
+
+    let mut line = inst.inst_type().to_string();
+    let operands = match operands_of(inst.inst_type()) {
+        Operands::Bare => Vec::new(),
+        Operands::X => vec![operand(x_val)],
+        Operands::Y => vec![operand(y_val)],
+        Operands::XY => vec![operand(x_val), operand(y_val)],
+        Operands::YX => vec![operand(y_val), operand(x_val)],
+        Operands::Name => vec![special_val.unwrap_or_default()],
+    };
+    for operand in operands {
+        line += " ";
+        line += &operand;
+    }
+    line
+}
+
+fn value_text(val: &Value) -> String {
+    match val.get_var_base() {
+        ValTy::op(op) => op.borrow().get_return_value(),
+        ValTy::node_id(node_id) => format!("[b{}]", node_id.index()),
+        other => other.to_string(),
+    }
+}
+
+// -- Parsing -- //
+
+struct InstLine {
+    line: usize,
+    inst_num: usize,
+    inst_type: InstTy,
+    operands: Vec<String>,
+}
+
+struct BlockLine {
+    line: usize,
+    index: usize,
+    node_type: NodeType,
+    successors: Vec<usize>,
+    predecessors: Vec<usize>,
+    insts: Vec<InstLine>,
+}
+
+struct Section {
+    line: usize,
+    // None for main.
+    func: Option<UniqueFunction>,
+    blocks: Vec<BlockLine>,
+}
+
+/// Reads text written by `to_text`, or by hand in the same form, back into
+/// a manager ready for the optimizer.
+pub fn from_text(text: &str) -> Result<IRGraphManager, IrTextError> {
+    let mut array_manager = ArrayManager::new();
+    let mut sections: Vec<Section> = Vec::new();
+    let mut line_count = 0;
+
+    for (line_index, raw_line) in text.lines().enumerate() {
+        let line = line_index + 1;
+        line_count = line;
+        let content = raw_line.split(';').next().unwrap().trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if content.starts_with("array") || content.starts_with("global ") {
+            parse_array(content, line, &mut array_manager)?;
+        } else if content.ends_with(':') && !content.starts_with('(') {
+            sections.push(Section {
+                line,
+                func: parse_header(content, line)?,
+                blocks: Vec::new(),
+            });
+        } else if content.starts_with('b') {
+            let block = parse_block(content, line)?;
+            match sections.last_mut() {
+                Some(section) => section.blocks.push(block),
+                None => return Err(IrTextError::new(String::from("block outside of main or a function"), line)),
+            }
+        } else if content.starts_with('(') {
+            let inst = parse_inst(content, line)?;
+            match sections.last_mut().and_then(|section| section.blocks.last_mut()) {
+                Some(block) => block.insts.push(inst),
+                None => return Err(IrTextError::new(String::from("instruction outside of a block"), line)),
+            }
+        } else {
+            return Err(IrTextError::new(format!("unexpected '{}'", content), line));
+        }
+    }
+
+    let main_count = sections.iter().filter(|section| section.func.is_none()).count();
+    if main_count != 1 {
+        return Err(IrTextError::new(
+            format!("expected one main section, found {}", main_count),
+            line_count,
+        ));
+    }
+
+    build(sections, array_manager)
+}
+
+fn build(sections: Vec<Section>, array_manager: ArrayManager) -> Result<IRGraphManager, IrTextError> {
+    // Blocks keep their printed index, unused indices are filled with
+    // unreachable placeholders.
+    let mut declared: HashMap<usize, (NodeType, usize)> = HashMap::new();
+    for block in sections.iter().flat_map(|section| section.blocks.iter()) {
+        if declared.insert(block.index, (block.node_type.clone(), block.line)).is_some() {
+            return Err(IrTextError::new(format!("block b{} declared twice", block.index), block.line));
+        }
+    }
+    let blocks = sections
+        .iter()
+        .flat_map(|section| section.blocks.iter())
+        .collect::<Vec<&BlockLine>>();
+    let block_count = declared.keys().max().map_or(0, |max| max + 1);
+
+    let mut graph = Graph::new();
+    let mut bt = BlockTracker::new();
+    for index in 0..block_count {
+        let node_type = declared
+            .get(&index)
+            .map_or(NodeType::ignored, |(node_type, _)| node_type.clone());
+        let mut node = Node::new(format!("{:?}", node_type), &mut bt, node_type);
+        node.update_node_id(index);
+        graph.add_node(node);
+    }
+
+    // Every instruction exists before any operand is read, phis refer to
+    // values defined further down.
+    let mut insts: HashMap<usize, Rc<RefCell<Op>>> = HashMap::new();
+    for block in blocks.iter() {
+        for inst in block.insts.iter() {
+            let op = Rc::new(RefCell::new(Op::new(
+                None,
+                None,
+                None,
+                inst.inst_num,
+                block.index,
+                inst.inst_type.clone(),
+            )));
+            if insts.insert(inst.inst_num, Rc::clone(&op)).is_some() {
+                return Err(IrTextError::new(format!("instruction ({}) declared twice", inst.inst_num), inst.line));
+            }
+            graph
+                .node_weight_mut(NodeIndex::new(block.index))
+                .unwrap()
+                .get_mut_data_ref()
+                .add_instruction(op);
+        }
+    }
+    let last_inst = insts.keys().max().cloned().unwrap_or(0);
+
+    for (parent, child) in edge_order(&blocks, &declared)? {
+        graph.add_edge(NodeIndex::new(parent), NodeIndex::new(child), String::from("black"));
+    }
+
+    for block in blocks {
+        for inst in block.insts.iter() {
+            let mut op = insts.get(&inst.inst_num).unwrap().borrow_mut();
+            let operand = |index: usize| {
+                parse_operand(&inst.operands[index], inst.line, &insts, &declared, &array_manager)
+            };
+
+            match operands_of(&inst.inst_type) {
+                Operands::Bare => {}
+                Operands::X => op.update_x_val(operand(0)?),
+                Operands::Y => op.update_y_val(operand(0)?),
+                Operands::XY => {
+                    op.update_x_val(operand(0)?);
+                    op.update_y_val(operand(1)?);
+                }
+                Operands::YX => {
+                    op.update_y_val(operand(0)?);
+                    op.update_x_val(operand(1)?);
+                }
+                Operands::Name => op.update_special_val(inst.operands[0].clone()),
+            }
+        }
+    }
+
+    // The root of a section is the block its entrance leads to.
+    let mut main_ids = None;
+    let mut func_manager = FunctionManager::new();
+    for section in sections {
+        let entry = match section.blocks.first() {
+            Some(block) => NodeIndex::new(block.index),
+            None => return Err(IrTextError::new(String::from("section has no blocks"), section.line)),
+        };
+        let root = match graph[entry].get_node_type() {
+            NodeType::entrance => graph.neighbors_directed(entry, Outgoing).last().unwrap_or(entry),
+            _ => entry,
+        };
+
+        match section.func {
+            Some(mut func) => {
+                func.update_index(root);
+                func_manager.add_func_to_manager(func);
+            }
+            None => main_ids = Some((entry, root)),
+        }
+    }
+
+    let (entrance, main_root) = main_ids.unwrap();
+    let graph_manager = GraphManager::from_graph(graph, entrance, main_root);
+    let mut irgm = IRGraphManager::from_parts(
+        graph_manager,
+        array_manager,
+        func_manager,
+        block_count,
+        last_inst,
+    );
+
+    // The passes would loop or overflow their stack on a graph no program
+    // builds, so it is turned down here instead.
+    if let Err(errors) = verify_shape(&mut irgm) {
+        let error = &errors[0];
+        let line = declared.get(&error.get_block()).map_or(0, |(_, line)| *line);
+        return Err(IrTextError::new(format!("b{}: {}", error.get_block(), error.get_message()), line));
+    }
+
+    Ok(irgm)
+}
+
+/// Orders the edges so every block sees its successors, and its
+/// predecessors where those are listed, in the written order. Phis pick
+/// their operand by the position of the predecessor they were entered from.
+fn edge_order(
+    blocks: &[&BlockLine],
+    declared: &HashMap<usize, (NodeType, usize)>,
+) -> Result<Vec<(usize, usize)>, IrTextError> {
+    let mut edges = Vec::new();
+    let mut edge_ids = HashMap::new();
+    for block in blocks {
+        for successor in block.successors.iter() {
+            if !declared.contains_key(successor) {
+                return Err(IrTextError::new(format!("unknown block b{}", successor), block.line));
+            }
+            if edge_ids.insert((block.index, *successor), edges.len()).is_some() {
+                return Err(IrTextError::new(format!("b{} leads to b{} twice", block.index, successor), block.line));
+            }
+            edges.push((block.index, *successor));
+        }
+    }
+
+    for block in blocks {
+        for parent in block.predecessors.iter() {
+            if !declared.contains_key(parent) {
+                return Err(IrTextError::new(format!("unknown block b{}", parent), block.line));
+            }
+        }
+    }
+
+    // Edges that have to be added before each edge, and the reverse.
+    let mut waiting_on = vec![0; edges.len()];
+    let mut followers = vec![Vec::new(); edges.len()];
+    let mut constrain = |first: usize, second: usize| {
+        followers[first].push(second);
+        waiting_on[second] += 1;
+    };
+
+    for block in blocks {
+        for pair in block.successors.windows(2) {
+            constrain(edge_ids[&(block.index, pair[0])], edge_ids[&(block.index, pair[1])]);
+        }
+
+        if block.predecessors.is_empty() {
+            continue;
+        }
+        let mut listed = block.predecessors.clone();
+        let mut incoming = edges
+            .iter()
+            .filter(|(_, child)| *child == block.index)
+            .map(|(parent, _)| *parent)
+            .collect::<Vec<usize>>();
+        listed.sort();
+        incoming.sort();
+        if listed != incoming {
+            return Err(IrTextError::new(
+                format!("predecessors of b{} do not match the blocks leading to it", block.index),
+                block.line,
+            ));
+        }
+        for pair in block.predecessors.windows(2) {
+            constrain(edge_ids[&(pair[0], block.index)], edge_ids[&(pair[1], block.index)]);
+        }
+    }
+
+    // Earliest written edge first among those that are free to go.
+    let mut ready = (0..edges.len())
+        .filter(|edge_id| waiting_on[*edge_id] == 0)
+        .collect::<BTreeSet<usize>>();
+    let mut order = Vec::new();
+    while let Some(edge_id) = ready.iter().next().cloned() {
+        ready.remove(&edge_id);
+        order.push(edges[edge_id]);
+        for follower in followers[edge_id].iter() {
+            waiting_on[*follower] -= 1;
+            if waiting_on[*follower] == 0 {
+                ready.insert(*follower);
+            }
+        }
+    }
+
+    if order.len() < edges.len() {
+        let (_, child) = edges[(0..edges.len()).find(|edge_id| waiting_on[*edge_id] > 0).unwrap()];
+        let line = declared[&child].1;
+        return Err(IrTextError::new(
+            format!("predecessors of b{} contradict the order of the successor lists", child),
+            line,
+        ));
+    }
+
+    Ok(order)
+}
+
+fn parse_array(content: &str, line: usize, array_manager: &mut ArrayManager) -> Result<(), IrTextError> {
+    let error = || IrTextError::new(format!("expected array declaration, found '{}'", content), line);
+
+    let is_global = content.starts_with("global ");
+    let mut rest = content.trim_start_matches("global ").trim_start();
+    if !rest.starts_with("array") {
+        return Err(error());
+    }
+    rest = &rest["array".len()..];
+
+    let mut dims = Vec::new();
+    while rest.starts_with('[') {
+        let close = rest.find(']').ok_or_else(error)?;
+        dims.push(rest[1..close].trim().parse::<i32>().map_err(|_| error())?);
+        rest = &rest[close + 1..];
+    }
+
+    let ident = rest.trim();
+    if dims.is_empty() || !is_ident(ident) {
+        return Err(error());
+    }
+    array_manager.add_array_dims(ident, dims, is_global);
+    Ok(())
+}
+
+fn parse_header(content: &str, line: usize) -> Result<Option<UniqueFunction>, IrTextError> {
+    let content = content.trim_end_matches(':').trim();
+    if content == "main" {
+        return Ok(None);
+    }
+
+    let error = || IrTextError::new(format!("expected main, function or procedure header, found '{}:'", content), line);
+    let (has_return, rest) = if let Some(rest) = content.strip_prefix("function ") {
+        (true, rest)
+    } else if let Some(rest) = content.strip_prefix("procedure ") {
+        (false, rest)
+    } else {
+        return Err(error());
+    };
+
+    let open = rest.find('(').ok_or_else(error)?;
+    if !rest.ends_with(')') {
+        return Err(error());
+    }
+    let name = rest[..open].trim();
+    if !is_ident(name) {
+        return Err(error());
+    }
+
+    let mut func = UniqueFunction::new(String::from(name), &NodeIndex::new(0));
+    func.set_return(has_return);
+    for param in rest[open + 1..rest.len() - 1].split(',') {
+        let param = param.trim();
+        if param.is_empty() {
+            continue;
+        }
+        if !is_ident(param) {
+            return Err(IrTextError::new(format!("bad parameter name '{}'", param), line));
+        }
+        func.add_parameter(&String::from(param));
+    }
+
+    Ok(Some(func))
+}
+
+fn parse_block(content: &str, line: usize) -> Result<BlockLine, IrTextError> {
+    let (head, tail) = match content.find("->") {
+        Some(arrow) => (&content[..arrow], Some(&content[arrow + 2..])),
+        None => (content, None),
+    };
+    let (head, parents) = match head.find("<-") {
+        Some(arrow) => (&head[..arrow], Some(&head[arrow + 2..])),
+        None => (head, None),
+    };
+
+    let mut words = head.split_whitespace();
+    let index = parse_block_name(words.next().unwrap(), line)?;
+    let type_name = words
+        .next()
+        .ok_or_else(|| IrTextError::new(format!("block b{} has no type", index), line))?;
+    let node_type = NODE_TYPES
+        .iter()
+        .find(|node_type| format!("{:?}", node_type) == type_name)
+        .cloned()
+        .ok_or_else(|| IrTextError::new(format!("unknown block type '{}'", type_name), line))?;
+    if let Some(extra) = words.next() {
+        return Err(IrTextError::new(format!("unexpected '{}' after block type", extra), line));
+    }
+
+    let mut successors = Vec::new();
+    for name in tail.unwrap_or("").split_whitespace() {
+        successors.push(parse_block_name(name, line)?);
+    }
+    let mut predecessors = Vec::new();
+    for name in parents.unwrap_or("").split_whitespace() {
+        predecessors.push(parse_block_name(name, line)?);
+    }
+
+    Ok(BlockLine {
+        line,
+        index,
+        node_type,
+        successors,
+        predecessors,
+        insts: Vec::new(),
+    })
+}
+
+fn parse_block_name(name: &str, line: usize) -> Result<usize, IrTextError> {
+    if let Some(Ok(index)) = name.strip_prefix('b').map(str::parse::<usize>) {
+        return Ok(index);
+    }
+    Err(IrTextError::new(format!("expected block name, found '{}'", name), line))
+}
+
+fn parse_inst(content: &str, line: usize) -> Result<InstLine, IrTextError> {
+    let error = || IrTextError::new(format!("expected '(n): inst', found '{}'", content), line);
+
+    let close = content.find("):").ok_or_else(error)?;
+    let inst_num = content[1..close].trim().parse::<usize>().map_err(|_| error())?;
+
+    let mut words = content[close + 2..].split_whitespace();
+    let name = words.next().ok_or_else(error)?;
+    let inst_type = INST_TYPES
+        .iter()
+        .find(|inst_type| inst_type.to_string() == name)
+        .cloned()
+        .ok_or_else(|| IrTextError::new(format!("unknown instruction '{}'", name), line))?;
+    let operands = words.map(String::from).collect::<Vec<String>>();
+
+    let expected = match operands_of(&inst_type) {
+        Operands::Bare => 0,
+        Operands::X | Operands::Y | Operands::Name => 1,
+        Operands::XY | Operands::YX => 2,
+    };
+    if operands.len() != expected {
+        return Err(IrTextError::new(
+            format!("{} takes {} operand(s), found {}", name, expected, operands.len()),
+            line,
+        ));
+    }
+
+    Ok(InstLine {
+        line,
+        inst_num,
+        inst_type,
+        operands,
+    })
+}
+
+fn parse_operand(
+    operand: &str,
+    line: usize,
+    insts: &HashMap<usize, Rc<RefCell<Op>>>,
+    declared: &HashMap<usize, (NodeType, usize)>,
+    array_manager: &ArrayManager,
+) -> Result<Value, IrTextError> {
+    let error = || IrTextError::new(format!("bad operand '{}'", operand), line);
+    let rest = operand.get(1..).unwrap_or("");
+
+    let val = if operand.starts_with('(') && operand.ends_with(')') {
+        let inst_num = operand[1..operand.len() - 1].parse::<usize>().map_err(|_| error())?;
+        match insts.get(&inst_num) {
+            Some(op) => ValTy::op(Rc::clone(op)),
+            None => return Err(IrTextError::new(format!("unknown instruction ({})", inst_num), line)),
+        }
+    } else if operand.starts_with("[b") && operand.ends_with(']') {
+        let index = operand[2..operand.len() - 1].parse::<usize>().map_err(|_| error())?;
+        if !declared.contains_key(&index) {
+            return Err(IrTextError::new(format!("unknown block b{}", index), line));
+        }
+        ValTy::node_id(NodeIndex::new(index))
+    } else if operand.starts_with('#') {
+        ValTy::con(rest.parse::<i32>().map_err(|_| error())?)
+    } else if operand.starts_with('&') && is_ident(rest) {
+        // Argument slots carry their position, everything else is placed
+        // by name.
+        let position = rest
+            .strip_prefix("arg")
+            .and_then(|index| index.parse::<usize>().ok())
+            .unwrap_or(0);
+        ValTy::adr(UniqueAddress::new(String::from(rest), position))
+    } else if operand.starts_with('%') {
+        if !array_manager.contains_array(&String::from(rest)) {
+            return Err(IrTextError::new(format!("unknown array '{}'", rest), line));
+        }
+        ValTy::arr(array_manager.get_array_ref(String::from(rest)).clone())
+    } else if operand == RetRegister::new().to_string() {
+        ValTy::ret(RetRegister::new())
+    } else if operand.starts_with('R') {
+        ValTy::reg(RegisterAllocation::allocate_register(
+            rest.parse::<usize>().map_err(|_| error())?,
+        ))
+    } else {
+        return Err(error());
+    };
+
+    Ok(Value::new(val))
+}
+
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.is_ascii_alphabetic() && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        None => false,
+    }
+}
@@ -1,13 +1,19 @@
 pub mod address_manager;
 pub mod array_manager;
 pub mod function_manager;
+pub mod interpreter;
 pub mod ir;
 pub mod ir_manager;
+pub mod ir_text;
 pub mod ret_register;
 pub mod variable_manager;
+pub mod verify;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Name main goes by wherever it sits next to the functions.
+pub const MAIN_NAME: &str = "main";
+
 extern crate petgraph;
 use petgraph::Graph;
@@ -134,9 +134,14 @@ impl VariableManager {
         set
     }
 
-    pub fn loop_correction(&mut self, old_val: Value, new_val: Value) {
+    /// Points the copies of `old_val` made from `first_block` on at
+    /// `new_val`, the copies made before the loop keep the value they had.
+    pub fn loop_correction(&mut self, old_val: Value, new_val: Value, first_block: usize) {
         for (ident, uniq_vec) in &mut self.var_manager {
             for uniq in uniq_vec {
+                if uniq.borrow().get_block() < first_block {
+                    continue;
+                }
                 match old_val.clone_value() {
                     ValTy::var(old_var) => {
                         let val_comp = uniq.borrow().get_value().clone_value();
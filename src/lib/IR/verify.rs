@@ -0,0 +1,304 @@
+//! Invariants the optimizer passes and code generation rely on, checked
+//! over the whole graph so a broken pass shows up as a list of violations
+//! instead of a wrong program further down.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use lib::Graph::control_flow_graph;
+use lib::Graph::is_phi_x_side;
+use lib::Graph::node::{Node, NodeType};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+
+use super::{Graph, Rc, RefCell};
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::DfsPostOrder;
+use petgraph::{Directed, Incoming, Outgoing};
+
+/// One broken invariant, located by block and, where there is one,
+/// instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyError {
+    block: usize,
+    inst: Option<usize>,
+    message: String,
+}
+
+impl VerifyError {
+    pub fn new(block: usize, inst: Option<usize>, message: String) -> Self {
+        VerifyError { block, inst, message }
+    }
+
+    pub fn get_block(&self) -> usize {
+        self.block
+    }
+
+    pub fn get_inst(&self) -> Option<usize> {
+        self.inst
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inst {
+            Some(inst) => write!(f, "b{} ({}): {}", self.block, inst, self.message),
+            None => write!(f, "b{}: {}", self.block, self.message),
+        }
+    }
+}
+
+/// Checks every block reachable from main and each function:
+///
+/// - each `phi` has one operand per predecessor
+/// - every instruction used is defined in a block dominating the use
+///   (the matching predecessor for phi operands)
+/// - no active instruction uses a deactivated one
+/// - a block ends in at most one branch, and it branches to a successor
+/// - `block_node_map` agrees with the node ids
+///
+/// along with everything `verify_shape` checks.
+pub fn verify(irgm: &mut IRGraphManager) -> Result<(), Vec<VerifyError>> {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let mut errors = match verify_shape(irgm) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors,
+    };
+
+    // Where every instruction still in a block sits.
+    let mut defs: HashMap<usize, (NodeIndex, usize)> = HashMap::new();
+    for node_id in graph.node_indices() {
+        for (position, inst) in graph[node_id].get_data_ref().get_inst_list_ref().iter().enumerate() {
+            defs.insert(inst.borrow().get_inst_num(), (node_id, position));
+        }
+    }
+
+    for (block_num, node_id) in irgm.graph_manager_ref().block_node_map() {
+        match graph.node_weight(*node_id) {
+            Some(node) if node.get_node_id() == *block_num => {}
+            Some(node) => errors.push(VerifyError::new(
+                node_id.index(),
+                None,
+                format!("block map sends block {} here, but this is block {}", block_num, node.get_node_id()),
+            )),
+            None => errors.push(VerifyError::new(
+                node_id.index(),
+                None,
+                format!("block map sends block {} to a node that is not in the graph", block_num),
+            )),
+        }
+    }
+
+    for (_, entry_id) in irgm.function_entries() {
+        let dom_space = simple_fast(&graph, entry_id);
+
+        let mut order = Vec::new();
+        let mut dfs = DfsPostOrder::new(&graph, entry_id);
+        while let Some(node_id) = dfs.next(&graph) {
+            order.push(node_id);
+        }
+        order.reverse();
+
+        let checker = BlockChecker {
+            graph: &graph,
+            dom_space: &dom_space,
+            defs: &defs,
+        };
+        for node_id in order {
+            checker.check(node_id, &mut errors);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks the control flow of main and each function, which every pass
+/// walks assuming it was built from a program:
+///
+/// - each kind of block has as many successors as it is built with
+/// - an exit can be reached from the entrance
+pub fn verify_shape(irgm: &mut IRGraphManager) -> Result<(), Vec<VerifyError>> {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let mut errors = Vec::new();
+
+    for (_, entry_id) in irgm.function_entries() {
+        let mut reaches_exit = false;
+        let mut dfs = DfsPostOrder::new(&graph, entry_id);
+        while let Some(node_id) = dfs.next(&graph) {
+            let node_type = graph[node_id].get_node_type();
+            reaches_exit |= node_type == NodeType::exit;
+
+            let successors = graph.neighbors_directed(node_id, Outgoing).count();
+            let expected = expected_successors(&node_type);
+            if !expected.contains(&successors) {
+                let expected = if expected.start() == expected.end() {
+                    expected.start().to_string()
+                } else {
+                    format!("{} or {}", expected.start(), expected.end())
+                };
+                errors.push(VerifyError::new(
+                    node_id.index(),
+                    None,
+                    format!("{:?} block has {} successor(s) instead of {}", node_type, successors, expected),
+                ));
+            }
+        }
+
+        if !reaches_exit {
+            errors.push(VerifyError::new(entry_id.index(), None, String::from("no exit can be reached from here")));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn expected_successors(node_type: &NodeType) -> RangeInclusive<usize> {
+    match node_type {
+        // Folding the condition leaves only the side that is taken.
+        NodeType::if_header | NodeType::while_loop_header => 1..=2,
+        // Code after a return hangs off the exit and never runs.
+        NodeType::exit | NodeType::ignored => 0..=1,
+        _ => 1..=1,
+    }
+}
+
+struct BlockChecker<'a> {
+    graph: &'a Graph<Node, String, Directed, u32>,
+    dom_space: &'a Dominators<NodeIndex>,
+    defs: &'a HashMap<usize, (NodeIndex, usize)>,
+}
+
+impl<'a> BlockChecker<'a> {
+    fn check(&self, node_id: NodeIndex, errors: &mut Vec<VerifyError>) {
+        let block = node_id.index();
+        let inst_list = self.graph[node_id].get_data_ref().get_inst_list_ref();
+        let active = inst_list
+            .iter()
+            .enumerate()
+            .filter(|(_, inst)| inst.borrow().is_active())
+            .collect::<Vec<(usize, &Rc<RefCell<Op>>)>>();
+
+        let branch_count = active
+            .iter()
+            .filter(|(_, inst)| is_branch(inst.borrow().inst_type()))
+            .count();
+        if branch_count > 1 {
+            errors.push(VerifyError::new(block, None, format!("block has {} branches", branch_count)));
+        }
+
+        for (index, (position, inst)) in active.iter().enumerate() {
+            let inst = inst.borrow();
+            let inst_num = Some(inst.get_inst_num());
+            let inst_type = inst.inst_type().clone();
+
+            if is_branch(&inst_type) {
+                if index + 1 != active.len() {
+                    errors.push(VerifyError::new(block, inst_num, String::from("branch is not the last instruction")));
+                }
+                match inst.clone_y_val().map(|val| val.clone_value()) {
+                    Some(ValTy::node_id(target)) => {
+                        if !self.graph.neighbors_directed(node_id, Outgoing).any(|child| child == target) {
+                            errors.push(VerifyError::new(
+                                block,
+                                inst_num,
+                                format!("branches to b{} which does not follow this block", target.index()),
+                            ));
+                        }
+                    }
+                    // Procedures return by branching on SP.
+                    Some(ValTy::adr(_)) if inst_type == InstTy::bra => {}
+                    _ => errors.push(VerifyError::new(block, inst_num, String::from("branch has no target block"))),
+                }
+            }
+
+            let (x_val, y_val, _) = inst.get_values();
+            if inst_type == InstTy::phi {
+                let operands = x_val.iter().count() + y_val.iter().count();
+                let parents = self.graph.neighbors_directed(node_id, Incoming).count();
+                if operands != parents {
+                    errors.push(VerifyError::new(
+                        block,
+                        inst_num,
+                        format!("phi has {} operand(s) but the block has {} predecessor(s)", operands, parents),
+                    ));
+                }
+            }
+
+            for (is_x, val) in [(true, x_val), (false, y_val)] {
+                let used = match val.as_ref().map(Value::get_var_base) {
+                    Some(ValTy::op(op)) => op,
+                    _ => continue,
+                };
+                let used_num = used.borrow().get_inst_num();
+                if !used.borrow().is_active() {
+                    errors.push(VerifyError::new(block, inst_num, format!("uses deactivated ({})", used_num)));
+                    continue;
+                }
+
+                let dominated = match self.defs.get(&used_num) {
+                    Some((def_id, _)) if inst_type == InstTy::phi => {
+                        self.phi_operand_dominated(node_id, is_x, *def_id)
+                    }
+                    Some((def_id, def_position)) => {
+                        self.dominates(*def_id, node_id) && (*def_id != node_id || def_position < position)
+                    }
+                    None => {
+                        errors.push(VerifyError::new(block, inst_num, format!("uses ({}) which is in no block", used_num)));
+                        continue;
+                    }
+                };
+                if !dominated {
+                    errors.push(VerifyError::new(
+                        block,
+                        inst_num,
+                        format!("uses ({}) which does not dominate it", used_num),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// A phi operand has to be available at the end of every reachable
+    /// predecessor on its side.
+    fn phi_operand_dominated(&self, node_id: NodeIndex, is_x: bool, def_id: NodeIndex) -> bool {
+        self.graph
+            .neighbors_directed(node_id, Incoming)
+            .filter(|parent_id| self.dom_space.dominators(*parent_id).is_some())
+            .filter(|parent_id| is_phi_x_side(self.graph, self.dom_space, *parent_id, node_id) == is_x)
+            .all(|parent_id| self.dominates(def_id, parent_id))
+    }
+
+    fn dominates(&self, dom_id: NodeIndex, node_id: NodeIndex) -> bool {
+        match self.dom_space.dominators(node_id) {
+            Some(mut dominators) => dominators.any(|id| id == dom_id),
+            None => false,
+        }
+    }
+}
+
+/// Whether `inst_type` ends a block.
+pub fn is_branch(inst_type: &InstTy) -> bool {
+    *inst_type == InstTy::bra || is_conditional_branch(inst_type)
+}
+
+/// Whether `inst_type` is a branch on a comparison.
+pub fn is_conditional_branch(inst_type: &InstTy) -> bool {
+    matches!(
+        inst_type,
+        InstTy::bne | InstTy::beq | InstTy::ble | InstTy::blt | InstTy::bge | InstTy::bgt
+    )
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+use super::token::Span;
+
+/// Input the lexer could not turn into a token, such as a character outside
+/// the language or a number running into letters.
+#[derive(Debug, Clone)]
+pub struct LexError {
+    message: String,
+    span: Span,
+}
+
+impl LexError {
+    pub fn new(message: String, span: Span) -> Self {
+        LexError { message, span }
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
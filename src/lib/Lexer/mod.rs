@@ -1,5 +1,10 @@
+pub mod lex_error;
+pub mod source;
 pub mod token;
 
+use self::lex_error::LexError;
+use self::source::SourceIter;
+use self::token::Span;
 use self::token::Token;
 use self::token::TokenType;
 
@@ -8,179 +13,181 @@ use std;
 //  As per Fabian's suggestion: use this function to request a token,
 //then pass the token back. This function will take a string version of
 //the code files, grab tokens, then throw back a single token when found.
+//Returns None once only whitespace and comments are left, and an error for
+//anything that can't start a token (the offending characters are consumed,
+//so the caller can carry on after it).
+pub fn get_token(iter: &mut SourceIter) -> Option<Result<Token, LexError>> {
+    let mut start = iter.location();
+    let token = next_token(iter, &mut start)?;
+    let span = start.to(&iter.location());
+
+    match token {
+        Ok(token) => Some(Ok(token.with_span(span))),
+        Err(message) => Some(Err(LexError::new(message, span))),
+    }
+}
 
-// TODO : Potential optimization or reworks?
-
-//This seems to work in separating tokens, but may need revisiting for better
-//clarity of tokens or perhaps consolidation, we shall see.
-pub fn get_token(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<Token> {
+// Finds the next token, leaving `start` on its first character.
+fn next_token(iter: &mut SourceIter, start: &mut Span) -> Option<Result<Token, String>> {
     let mut buffer = String::new();
 
     let mut is_comment: bool = false;
-    let mut is_number: bool = true;
 
-    while let Some(c) = iter.next() {
-        if is_comment == true {
+    loop {
+        // Whitespace and comments leave the buffer empty, so the token
+        // starts wherever the buffer was last empty.
+        if buffer.is_empty() {
+            *start = iter.location();
+        }
+        let c = iter.next()?;
+
+        if is_comment {
             if c == '\n' || c == '\r' {
-                // TODO :
                 //we are removing the comments completely so that the parser does not become more complicated
                 buffer.clear();
                 is_comment = false;
-            //return Some(Token::new(TokenType::Comment, buffer));
             } else {
                 buffer.push(c);
             }
-        } else {
-            match c {
-                // TODO : Add '_' case? might be nice for naming variables but isn't in assignment.
-                //Alpha characters
-                'a'...'z' | 'A'...'Z' => {
-                    buffer.push(c);
-                    is_number = false;
-                    match iter.peek() {
-                        Some(' ') | Some('=') | Some('!') | Some('>') | Some('<') | Some('(')
-                        | Some(')') | Some('{') | Some('}') | Some('[') | Some(']') | Some(';')
-                        | Some('+') | Some('-') | Some('.') | Some('*') | Some('/') | Some(',')
-                        | Some('#') | Some('\r') | Some('\n') => match check_keyword(&mut buffer) {
-                            Some(x) => return Some(x),
-                            None => return Some(Token::new(TokenType::Ident, buffer)),
-                        },
-                        None => return Some(Token::new(TokenType::Ident, buffer)),
-                        Some(_) => {}
-                    }
-                }
+            continue;
+        }
 
-                //Numerics
-                '0'...'9' => {
-                    buffer.push(c);
-                    match iter.peek() {
-                        Some(' ') | Some('+') | Some('-') | Some('/') | Some('*') | Some('=')
-                        | Some('!') | Some('>') | Some('<') | Some('{') | Some('[') | Some('(')
-                        | Some('}') | Some(']') | Some(')') | Some(';') | Some(',')
-                        | Some('\r') | Some('\n') | Some('\t') => {
-                            if is_number == true {
-                                return Some(Token::new(TokenType::Number, buffer));
-                            } else if is_number == false {
-                                return Some(Token::new(TokenType::Ident, buffer));
-                            }
-                        }
-                        _ => {}
-                    }
+        match c {
+            //Alpha characters, digits may follow after the first one.
+            'a'..='z' | 'A'..='Z' => {
+                buffer.push(c);
+                take_alphanumeric(iter, &mut buffer);
+                match check_keyword(&mut buffer) {
+                    Some(x) => return Some(Ok(x)),
+                    None => return Some(Ok(Token::new(TokenType::Ident, buffer))),
                 }
+            }
 
-                //Braces and Brackets
-                '{' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::LeftBrace, buffer));
-                }
-                '[' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::LeftBracket, buffer));
-                }
-                '(' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::LeftPara, buffer));
-                }
-                '}' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::RightBrace, buffer));
-                }
-                ']' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::RightBracket, buffer));
-                }
-                ')' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::RightPara, buffer));
+            //Numerics
+            '0'..='9' => {
+                buffer.push(c);
+                take_alphanumeric(iter, &mut buffer);
+                if buffer.chars().all(|c| c.is_ascii_digit()) {
+                    return Some(Ok(Token::new(TokenType::Number, buffer)));
+                } else {
+                    return Some(Err(format!("malformed number '{}'", buffer)));
                 }
+            }
+
+            //Braces and Brackets
+            '{' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::LeftBrace, buffer)));
+            }
+            '[' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::LeftBracket, buffer)));
+            }
+            '(' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::LeftPara, buffer)));
+            }
+            '}' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::RightBrace, buffer)));
+            }
+            ']' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::RightBracket, buffer)));
+            }
+            ')' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::RightPara, buffer)));
+            }
 
-                //relOp characters will need an explicit peeknext
-                '=' | '!' | '>' | '<' => {
-                    buffer.push(c);
-                    match iter.peek() {
-                        Some('=') | Some('!') | Some('>') | Some('<') => {}
-                        Some('-') => {
-                            if c == '<' {
-                            } else {
-                                return Some(Token::new(TokenType::RelOp, buffer));
-                            }
-                        }
-                        _ => return Some(Token::new(TokenType::RelOp, buffer)),
+            //relOp characters need a peek at the next one, '<' might also start '<-'
+            '=' | '!' | '>' | '<' => {
+                buffer.push(c);
+                match iter.peek() {
+                    Some('=') => {
+                        buffer.push('=');
+                        iter.next();
+                    }
+                    Some('-') if c == '<' => {
+                        buffer.push('-');
+                        iter.next();
+                        return Some(Ok(Token::new(TokenType::AssignmentOp, buffer)));
                     }
+                    _ => {}
                 }
 
-                //Math Operators
-                '+' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::AddOp, buffer));
-                }
-                '-' => {
-                    buffer.push(c);
-                    if buffer.as_str() == "<-" {
-                        return Some(Token::new(TokenType::AssignmentOp, buffer));
-                    } else {
-                        return Some(Token::new(TokenType::SubOp, buffer));
+                match buffer.as_str() {
+                    "==" | "!=" | "<" | "<=" | ">" | ">=" => {
+                        return Some(Ok(Token::new(TokenType::RelOp, buffer)));
                     }
+                    _ => return Some(Err(format!("unknown operator '{}'", buffer))),
                 }
+            }
 
-                //Comment handlers
-                '#' => {
-                    //Single comment token, take the rest of the line.
-                    buffer.push(c);
-                    is_comment = true;
-                }
-                '/' => {
-                    buffer.push(c);
+            //Math Operators
+            '+' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::AddOp, buffer)));
+            }
+            '-' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::SubOp, buffer)));
+            }
 
-                    if *iter.peek().unwrap() == '/' {
-                        is_comment = true;
-                    } else {
-                        return Some(Token::new(TokenType::DivOp, buffer));
-                    }
-                }
-                '*' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::MulOp, buffer));
-                }
+            //Comment handlers
+            '#' => {
+                //Single comment token, take the rest of the line.
+                buffer.push(c);
+                is_comment = true;
+            }
+            '/' => {
+                buffer.push(c);
 
-                //Comma Splitter
-                ',' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::Comma, buffer));
+                if iter.peek() == Some(&'/') {
+                    is_comment = true;
+                } else {
+                    return Some(Ok(Token::new(TokenType::DivOp, buffer)));
                 }
+            }
+            '*' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::MulOp, buffer)));
+            }
 
-                //characters to ignore or remove (such as whitespace)
-                ' ' => {}
-                ';' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::SemiTermination, buffer));
-                }
-                '\'' => {}
-                '\t' => {}
-                '\r' => {}
-                '\n' => {}
-
-                //EOF and End of Main Function
-                '.' => {
-                    buffer.push(c);
-                    return Some(Token::new(TokenType::ComputationEnd, buffer));
-                }
+            //Comma Splitter
+            ',' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::Comma, buffer)));
+            }
+
+            ';' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::SemiTermination, buffer)));
+            }
 
-                _ => panic!(
-                    "Should never reach this case! Character that gave error: {}",
-                    c
-                ), //buffer.push(c),//for now we just build:
+            //EOF and End of Main Function
+            '.' => {
+                buffer.push(c);
+                return Some(Ok(Token::new(TokenType::ComputationEnd, buffer)));
             }
+
+            //characters to ignore (whitespace)
+            c if c.is_whitespace() => {}
+
+            _ => return Some(Err(format!("unexpected character {:?}", c))),
         }
     }
+}
 
-    //leave in until dev done.
-    //very rudimentary method of breaking for loop of main function,
-    //there definitely has to be a better way!
-
-    // TODO : Find better method for finding EOF and remove this case.
-    //Some(Token::new(TokenType::Test, buffer))
-    None //If all above cases fall through, then we are at the end of our computation and thus we return None. This ends the Parsing.
+// Moves the rest of an identifier or number over into `buffer`.
+fn take_alphanumeric(iter: &mut SourceIter, buffer: &mut String) {
+    while let Some(&c) = iter.peek() {
+        if !c.is_ascii_alphanumeric() {
+            break;
+        }
+        buffer.push(c);
+        iter.next();
+    }
 }
 
 fn check_keyword(key: &mut String) -> Option<Token> {
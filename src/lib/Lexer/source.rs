@@ -0,0 +1,48 @@
+use std;
+
+use super::token::Span;
+
+/// Character iterator over the source text that keeps track of where it
+/// is, so the lexer can tag every token with its position.
+pub struct SourceIter<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> SourceIter<'a> {
+    pub fn new(source: &'a str) -> Self {
+        SourceIter {
+            chars: source.chars().peekable(),
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    /// Empty span sitting on the next character to be read.
+    pub fn location(&self) -> Span {
+        Span::new(self.line, self.column, self.offset, self.offset)
+    }
+}
+
+impl<'a> Iterator for SourceIter<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
@@ -1,10 +1,51 @@
 use std;
+use std::fmt;
 use Lexer;
+use Lexer::lex_error::LexError;
+use Lexer::source::SourceIter;
+use Parser::parse_error::ParseError;
+
+/// Location of a token (or a whole AST node) in the source text. Lines and
+/// columns start at 1 and point at the first character, `start..end` is the
+/// byte range covered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, start: usize, end: usize) -> Self {
+        Span {
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+
+    /// Span starting where this one does and ending where `other` ends.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            end: std::cmp::max(self.end, other.end),
+            ..*self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Token {
     token_type: TokenType,
     token_contents: String,
+    span: Span,
 }
 
 impl Token {
@@ -12,9 +53,15 @@ impl Token {
         Token {
             token_type: TkTy,
             token_contents: TkCn,
+            span: Span::default(),
         }
     }
 
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
     pub fn get_contents(&self) -> String {
         let copy = self.token_contents.clone();
         copy
@@ -25,6 +72,10 @@ impl Token {
         copy
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn peek_type(&self) -> TokenType {
         let peek_token_copy = self.token_type.clone();
         peek_token_copy
@@ -39,23 +90,37 @@ impl Token {
 #[derive(Debug)]
 pub struct TokenCollection {
     token_vector: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    last_span: Span,
+    end_span: Span,
+    // Input the lexer skipped over because it couldn't make a token of it.
+    lex_errors: Vec<LexError>,
+    // Syntax errors the parser recovered from, in the order they were found.
+    errors: Vec<ParseError>,
 }
 
 impl TokenCollection {
-    pub fn collect(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> TokenCollection {
+    pub fn collect(iter: &mut SourceIter) -> TokenCollection {
         let mut token_builder: Vec<Token> = Vec::new();
+        let mut lex_errors = Vec::new();
 
         loop {
             if iter.peek() == None {
+                let end_span = iter.location();
                 return TokenCollection {
                     token_vector: token_builder.into_iter().peekable(),
+                    last_span: Span::default(),
+                    end_span,
+                    lex_errors,
+                    errors: Vec::new(),
                 };
             }
 
-            if let Some(token) = Lexer::get_token(iter) {
-                token_builder.push(token);
-            } else {
-                //Lexer::get_token(iter) returned None.
+            match Lexer::get_token(iter) {
+                Some(Ok(token)) => token_builder.push(token),
+                Some(Err(error)) => lex_errors.push(error),
+                None => {
+                    //Lexer::get_token(iter) returned None.
+                }
             }
         }
     }
@@ -66,7 +131,15 @@ impl TokenCollection {
     }
 
     pub fn get_next_token(&mut self) -> Option<Token> {
-        self.token_vector.next()
+        let token = self.token_vector.next();
+        if let Some(token) = &token {
+            self.last_span = token.get_span();
+        }
+        token
+    }
+
+    pub fn peek_next_token(&mut self) -> Option<Token> {
+        self.token_vector.peek().cloned()
     }
 
     pub fn peek_next_token_type(&mut self) -> Option<TokenType> {
@@ -78,9 +151,35 @@ impl TokenCollection {
             None => None,
         }
     }
+
+    /// Span of the next token, or the end of the source once they run out.
+    pub fn next_span(&mut self) -> Span {
+        match self.token_vector.peek() {
+            Some(token) => token.get_span(),
+            None => self.end_span,
+        }
+    }
+
+    /// Span from `start` through the last token handed out.
+    pub fn span_from(&self, start: &Span) -> Span {
+        start.to(&self.last_span)
+    }
+
+    /// Keeps an error the parser has recovered from.
+    pub fn report_error(&mut self, error: ParseError) {
+        self.errors.push(error);
+    }
+
+    pub fn take_lex_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.lex_errors)
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
 }
 //std::iter::Peekable<std::slice::Iter<Token<'_>>>
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TokenType {
     // Debugging Type
     Test,
@@ -1,4 +1,6 @@
-use super::{IRGraphManager, TempValManager};
+use std::collections::HashMap;
+
+use super::{IRGraphManager, Op, Rc, RefCell, TempValManager, ValTy, Value};
 use petgraph::algo::has_path_connecting;
 use petgraph::algo::toposort;
 use petgraph::prelude::NodeIndex;
@@ -11,7 +13,17 @@ pub fn clean_graph(
     graph_visitor: &Vec<NodeIndex>,
 ) -> NodeIndex {
     let mut return_node_id = root_node.clone();
-    let mut new_root_id = root_node.index();
+    let mut new_root_id = irgm
+        .graph_manager()
+        .get_ref_graph()
+        .node_weight(root_node)
+        .unwrap()
+        .get_node_id();
+
+    // Removing a node moves the last one into its place, so the branches
+    // are pointed at their targets again by block number once the nodes are
+    // gone.
+    let branch_targets = branch_target_blocks(irgm);
 
     // First remove nodes that cannot be reached from the root node.
     let mut walkable_graph = irgm.graph_manager().get_ref_graph().clone();
@@ -39,6 +51,10 @@ pub fn clean_graph(
         }
     }
 
+    // Removing a node moves the last one into its place, which can be the
+    // root itself.
+    let root_node = find_node(irgm, new_root_id).unwrap_or(root_node);
+
     // Removes inactive instructions from each remaining node
     let visit_order = irgm.graph_manager().graph_visitor(root_node);
     for node_index in visit_order.clone() {
@@ -118,9 +134,6 @@ pub fn clean_graph(
         //println!("Removed Node {:?}", node_index);
     }*/
 
-    // Update branch commands
-    // TODO : Update branch commands.
-
     // Remove inactive nodes in reverse order
     let mut node_vec = irgm
         .graph_manager()
@@ -152,22 +165,55 @@ pub fn clean_graph(
             .update_node_id(node_id.index());*/
     }
 
-    // Using new_root_id to look up actual location (NodeIndex)
-    for node_id in irgm.graph_manager().get_ref_graph().node_indices() {
-        let current_node_id = irgm
-            .graph_manager()
-            .get_ref_graph()
-            .node_weight(node_id)
-            .unwrap()
-            .get_node_id();
+    retarget_branches(irgm, branch_targets);
 
-        if new_root_id == current_node_id {
-            // Found new node_id for function. Update root node and break loop.
-            return_node_id = node_id;
-            break;
-        }
+    // Using new_root_id to look up actual location (NodeIndex)
+    if let Some(node_id) = find_node(irgm, new_root_id) {
+        return_node_id = node_id;
     }
 
     //println!("Sending back new main node: {:?}", return_node_id);
     return_node_id
 }
+
+fn find_node(irgm: &mut IRGraphManager, block_id: usize) -> Option<NodeIndex> {
+    let graph = irgm.graph_manager().get_ref_graph();
+    graph
+        .node_indices()
+        .find(|node_id| graph.node_weight(*node_id).unwrap().get_node_id() == block_id)
+}
+
+/// Every branch in the graph, along with the block number of its target.
+fn branch_target_blocks(irgm: &mut IRGraphManager) -> Vec<(Rc<RefCell<Op>>, usize)> {
+    let graph = irgm.graph_manager().get_ref_graph();
+    let mut branch_targets = Vec::new();
+    for node_id in graph.node_indices() {
+        for inst in graph[node_id].get_data_ref().get_inst_list_ref() {
+            let target = match inst.borrow().clone_y_val().map(|val| val.clone_value()) {
+                Some(ValTy::node_id(target)) => target,
+                _ => continue,
+            };
+            if let Some(node) = graph.node_weight(target) {
+                branch_targets.push((Rc::clone(inst), node.get_node_id()));
+            }
+        }
+    }
+    branch_targets
+}
+
+fn retarget_branches(irgm: &mut IRGraphManager, branch_targets: Vec<(Rc<RefCell<Op>>, usize)>) {
+    let graph = irgm.graph_manager().get_ref_graph();
+    let blocks = graph
+        .node_indices()
+        .map(|node_id| (graph[node_id].get_node_id(), node_id))
+        .collect::<HashMap<usize, NodeIndex>>();
+
+    for (inst, block_id) in branch_targets {
+        match blocks.get(&block_id) {
+            Some(node_id) => inst.borrow_mut().update_y_val(Value::new(ValTy::node_id(*node_id))),
+            // Only a branch a pass has already decided can lead to a block
+            // that went, such as the side sccp found is never taken.
+            None => inst.borrow_mut().deactivate(),
+        }
+    }
+}
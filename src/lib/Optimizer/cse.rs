@@ -242,40 +242,37 @@ impl CLE {
                 let mut else_op_dom = op_dom_handler.clone();
 
                 // First check if_branch is still valid
+                let mut phi_node = None;
                 if if_bra != local_current {
                     // If branch is a valid and unique branch
                     self.current_node = if_bra;
                     self.recurse_remove_loads(irgm, &mut if_op_dom, temp_manager);
+                    phi_node = self.reached_phi(irgm).or(phi_node);
                 }
 
                 if else_bra != local_current {
                     self.current_node = else_bra;
                     self.recurse_remove_loads(irgm, &mut else_op_dom, temp_manager);
+                    phi_node = self.reached_phi(irgm).or(phi_node);
                 }
 
-                // Quick sanity check to make sure we are on the phi node before continuing.
-                let node_type = irgm.graph_manager()
-                    .get_ref_graph()
-                    .node_weight(self.current_node)
-                    .unwrap()
-                    .get_node_type();
-
-                if node_type == NodeType::phi_node {
-                    let removed_item = self.if_bp.pop();
-                    if Some(local_current) == removed_item {
-                        // All is well
-                    } else {
-                        // Some error occured, perhaps wrong item popped.
-                        panic!("Popped wrong item for if-else.");
-                    }
+                let removed_item = self.if_bp.pop();
+                if Some(local_current) == removed_item {
+                    // All is well
+                } else {
+                    // Some error occured, perhaps wrong item popped.
+                    panic!("Popped wrong item for if-else.");
+                }
+
+                // A branch that returns ends at the exit instead of the phi,
+                // when both do nothing follows the if.
+                if let Some(phi_node) = phi_node {
+                    self.current_node = phi_node;
 
                     // Merge the two op_doms
                     if_op_dom.merge_op_doms(&else_op_dom);
 
                     self.recurse_remove_loads(irgm, &mut if_op_dom, temp_manager);
-                } else {
-                    // I dont think this case should ever be reached
-                    panic!("Reached end of if statement without a phi being reached.");
                 }
 
             },
@@ -491,38 +488,35 @@ impl CLE {
                 let local_current = self.current_node.clone();
 
                 // First check if_branch is still valid
+                let mut phi_node = None;
                 if if_bra != local_current {
                     // If branch is a valid and unique branch
                     self.current_node = if_bra;
                     self.recurse_insert_kills(irgm);
+                    phi_node = self.reached_phi(irgm).or(phi_node);
                 }
 
                 if else_bra != local_current {
                     self.current_node = else_bra;
                     self.recurse_insert_kills(irgm);
+                    phi_node = self.reached_phi(irgm).or(phi_node);
                 }
 
-                // Quick sanity check to make sure we are on the phi node before continuing.
-                let node_type = irgm.graph_manager()
-                    .get_ref_graph()
-                    .node_weight(self.current_node)
-                    .unwrap()
-                    .get_node_type();
-
-                if node_type == NodeType::phi_node {
-                    let removed_item = self.if_bp.pop();
-                    if Some(local_current) == removed_item {
-                        // All is well
-                    } else {
-                        // Some error occured, perhaps wrong item popped.
-                        panic!("Popped wrong item for if-else.");
-                    }
+                let removed_item = self.if_bp.pop();
+                if Some(local_current) == removed_item {
+                    // All is well
+                } else {
+                    // Some error occured, perhaps wrong item popped.
+                    panic!("Popped wrong item for if-else.");
+                }
+
+                // A branch that returns ends at the exit instead of the phi,
+                // when both do nothing follows the if.
+                if let Some(phi_node) = phi_node {
+                    self.current_node = phi_node;
                     self.if_to_phi_map.insert(local_current, self.current_node.clone());
 
                     self.recurse_insert_kills(irgm);
-                } else {
-                    // I dont think this case should ever be reached
-                    panic!("Reached end of if statement without a phi being reached.");
                 }
 
             },
@@ -571,6 +565,21 @@ impl CLE {
         // Do something for phi node case inside of loop.
     }
 
+    // The phi a branch of an if stopped at, if it did not leave through the exit.
+    fn reached_phi(&self, irgm: &mut IRGraphManager) -> Option<NodeIndex> {
+        let node_type = irgm.graph_manager()
+            .get_ref_graph()
+            .node_weight(self.current_node)
+            .unwrap()
+            .get_node_type();
+
+        if node_type == NodeType::phi_node {
+            Some(self.current_node)
+        } else {
+            None
+        }
+    }
+
     fn search_kill_inst(&mut self, irgm: &mut IRGraphManager) {
         let inst_list = irgm.graph_manager().get_ref_graph()
             .node_weight(self.current_node)
@@ -611,7 +620,11 @@ impl CLE {
     pub fn add_kills(&mut self, irgm: &mut IRGraphManager) {
         // Insert kill instruction to phis
         for node_id in self.if_kill_prop.iter() {
-            let phi_id = self.if_to_phi_map.get(&node_id).unwrap().clone();
+            // Ifs whose branches all return have no phi to kill in.
+            let phi_id = match self.if_to_phi_map.get(node_id) {
+                Some(phi_id) => *phi_id,
+                None => continue,
+            };
 
             let kill_op = irgm.build_op_in_block(InstTy::kill, phi_id.index());
             irgm.graph_manager().insert_instruction_in_node(0, kill_op, &phi_id);
@@ -37,6 +37,13 @@ pub fn dead_code_elimination(
                     continue
                 }
 
+                // Reads and divisions stay even when nothing uses them, a
+                // read takes up input and a division may stop the program.
+                let inst_ty = inst.borrow().inst_type().clone();
+                if inst_ty == InstTy::read || inst_ty == InstTy::div {
+                    continue
+                }
+
                 let active_uses = temp_manager
                     .borrow_inst(&inst.borrow().get_inst_num())
                     .borrow()
@@ -0,0 +1,589 @@
+//! Induction variables and strength reduction. A basic induction variable
+//! is a while header phi the back edge brings back stepped by a constant,
+//! `add (i) #s` or `sub (i) #s`. A multiplication in the loop that works
+//! out to `i*c + b` gets a phi of its own for `i*c`, started in the
+//! preheader and stepped by `c*s` at the end of every iteration, and adds
+//! `b` to it instead of multiplying. Arithmetic wraps, so the new phi holds
+//! exactly what the multiplication would have.
+//!
+//! An induction variable left with nothing but its own step and the exit
+//! test is removed, the test moving onto one of its multiples. Comparing
+//! `i*c` where `i` was compared only gives the same answer while neither
+//! side overflows, so this is limited to constant starts and bounds, and to
+//! tests that stop the variable before it wraps.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use lib::Graph::node::NodeType;
+use lib::Graph::{block_successors, branch_targets, control_flow_graph};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::Optimizer::licm::{find_loops, insert_preheader, Loop};
+use lib::RegisterAllocator::RegisterAllocation;
+
+use super::temp_value_manager::TempValManager;
+use petgraph::algo::dominators::simple_fast;
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Incoming;
+
+struct InductionVar {
+    phi: Rc<RefCell<Op>>,
+    // What the back edge brings, the phi plus `step`.
+    next: Rc<RefCell<Op>>,
+    step: i32,
+    // What the loop is entered with.
+    init: Value,
+}
+
+/// A multiplication that is `scale * iv + offset`.
+struct Derived {
+    inst: Rc<RefCell<Op>>,
+    scale: i32,
+    offset: i32,
+}
+
+/// The comparison the header leaves the loop on, when it is between the
+/// induction variable and a constant.
+struct ExitTest {
+    cmp: Rc<RefCell<Op>>,
+    iv_is_x: bool,
+    bound: i32,
+}
+
+/// Reduces the multiplications of every while loop of the function, inner
+/// loops first so their new start values can be reduced by an outer loop.
+pub fn reduce_strength(irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let dom_space = simple_fast(&graph, root);
+    let mut loops = find_loops(&graph, &dom_space, root);
+    loops.sort_by_key(|natural_loop| natural_loop.body.len());
+
+    for position in 0..loops.len() {
+        let header = loops[position].header;
+        if graph[header].get_node_type() != NodeType::while_loop_header {
+            continue;
+        }
+        let latch = match single_latch(irgm, &loops[position]) {
+            Some(latch) => latch,
+            None => continue,
+        };
+
+        let blocks = irgm
+            .graph_manager_ref()
+            .graph_visitor(root)
+            .into_iter()
+            .filter(|node_id| loops[position].body.contains(node_id))
+            .collect::<Vec<NodeIndex>>();
+        let defined = defined_insts(irgm, &blocks);
+
+        let mut reductions = Vec::new();
+        for iv in induction_vars(irgm, header, &defined) {
+            let derived = derived_insts(irgm, &blocks, &iv, &defined);
+            if !derived.is_empty() {
+                reductions.push((iv, derived));
+            }
+        }
+        if reductions.is_empty() {
+            continue;
+        }
+
+        let preheader = match insert_preheader(irgm, &loops[position]) {
+            Some(preheader) => preheader,
+            None => continue,
+        };
+        for outer in loops[position + 1..].iter_mut() {
+            if outer.body.contains(&header) {
+                outer.body.insert(preheader);
+            }
+        }
+
+        for (iv, derived) in reductions {
+            let scaled = reduce(irgm, temp_manager, &iv, derived, header, preheader, latch);
+            let smallest = scaled
+                .into_iter()
+                .filter(|(scale, _)| *scale > 1)
+                .min_by_key(|(scale, _)| *scale);
+            if let Some((scale, phi)) = smallest {
+                if let Some(test) = exit_test(irgm, temp_manager, &loops[position], &iv, scale) {
+                    move_exit_test(irgm, temp_manager, &iv, test, scale, &phi, preheader);
+                }
+            }
+        }
+    }
+}
+
+/// The one block that jumps back to the header, if it is not the header.
+fn single_latch(irgm: &IRGraphManager, natural_loop: &Loop) -> Option<NodeIndex> {
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let mut latches = Vec::new();
+    for edge in graph.edges_directed(natural_loop.header, Incoming) {
+        if edge.weight() == "black" && natural_loop.body.contains(&edge.source()) && !latches.contains(&edge.source()) {
+            latches.push(edge.source());
+        }
+    }
+
+    match latches.as_slice() {
+        [latch] if *latch != natural_loop.header => Some(*latch),
+        _ => None,
+    }
+}
+
+fn defined_insts(irgm: &IRGraphManager, blocks: &[NodeIndex]) -> HashSet<usize> {
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let mut defined = HashSet::new();
+    for node_id in blocks {
+        for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+            defined.insert(inst.borrow().get_inst_num());
+        }
+    }
+    defined
+}
+
+fn induction_vars(irgm: &IRGraphManager, header: NodeIndex, defined: &HashSet<usize>) -> Vec<InductionVar> {
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let mut ivs = Vec::new();
+
+    for inst in graph[header].get_data_ref().get_inst_list_ref() {
+        let phi = inst.borrow();
+        if !phi.is_active() || phi.inst_type().clone() != InstTy::phi {
+            continue;
+        }
+        let (next, init) = match (phi.clone_x_val(), phi.clone_y_val()) {
+            (Some(next), Some(init)) => (next, init),
+            _ => continue,
+        };
+        let next = match next.get_var_base() {
+            ValTy::op(next) => next,
+            _ => continue,
+        };
+        if !next.borrow().is_active() || !defined.contains(&next.borrow().get_inst_num()) {
+            continue;
+        }
+
+        let step = {
+            let next = next.borrow();
+            let is_phi = |value: &Option<Value>| match value.as_ref().map(Value::get_var_base) {
+                Some(ValTy::op(op)) => Rc::ptr_eq(&op, inst),
+                _ => false,
+            };
+            let x_con = next.clone_x_val().as_ref().and_then(constant);
+            let y_con = next.clone_y_val().as_ref().and_then(constant);
+            match next.inst_type() {
+                InstTy::add if is_phi(&next.clone_x_val()) => y_con,
+                InstTy::add if is_phi(&next.clone_y_val()) => x_con,
+                InstTy::sub if is_phi(&next.clone_x_val()) => y_con.map(i32::wrapping_neg),
+                _ => None,
+            }
+        };
+
+        match step {
+            Some(step) if step != 0 => ivs.push(InductionVar {
+                phi: Rc::clone(inst),
+                next,
+                step,
+                init,
+            }),
+            _ => {}
+        }
+    }
+    ivs
+}
+
+fn derived_insts(irgm: &IRGraphManager, blocks: &[NodeIndex], iv: &InductionVar, defined: &HashSet<usize>) -> Vec<Derived> {
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let mut derived = Vec::new();
+
+    for node_id in blocks {
+        for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+            if !inst.borrow().is_active() || inst.borrow().inst_type().clone() != InstTy::mul {
+                continue;
+            }
+            match linear_form(&Value::new(ValTy::op(Rc::clone(inst))), iv, defined) {
+                Some((scale, offset)) if scale != 0 => derived.push(Derived {
+                    inst: Rc::clone(inst),
+                    scale,
+                    offset,
+                }),
+                _ => {}
+            }
+        }
+    }
+    derived
+}
+
+/// `(c, b)` when the value is `c * iv + b` on every iteration, following
+/// the additions and multiplications by constants it is made of.
+fn linear_form(value: &Value, iv: &InductionVar, defined: &HashSet<usize>) -> Option<(i32, i32)> {
+    let op = match value.get_var_base() {
+        ValTy::op(op) => op,
+        _ => return None,
+    };
+    if Rc::ptr_eq(&op, &iv.phi) {
+        return Some((1, 0));
+    }
+
+    let op = op.borrow();
+    if !op.is_active() || !defined.contains(&op.get_inst_num()) {
+        return None;
+    }
+    let (x_val, y_val) = (op.clone_x_val()?, op.clone_y_val()?);
+    let (x_con, y_con) = (constant(&x_val), constant(&y_val));
+
+    match (op.inst_type(), x_con, y_con) {
+        (InstTy::add, None, Some(con)) => {
+            linear_form(&x_val, iv, defined).map(|(scale, offset)| (scale, offset.wrapping_add(con)))
+        }
+        (InstTy::add, Some(con), None) => {
+            linear_form(&y_val, iv, defined).map(|(scale, offset)| (scale, offset.wrapping_add(con)))
+        }
+        (InstTy::sub, None, Some(con)) => {
+            linear_form(&x_val, iv, defined).map(|(scale, offset)| (scale, offset.wrapping_sub(con)))
+        }
+        (InstTy::sub, Some(con), None) => linear_form(&y_val, iv, defined)
+            .map(|(scale, offset)| (scale.wrapping_neg(), con.wrapping_sub(offset))),
+        (InstTy::mul, None, Some(con)) => linear_form(&x_val, iv, defined)
+            .map(|(scale, offset)| (scale.wrapping_mul(con), offset.wrapping_mul(con))),
+        (InstTy::mul, Some(con), None) => linear_form(&y_val, iv, defined)
+            .map(|(scale, offset)| (scale.wrapping_mul(con), offset.wrapping_mul(con))),
+        _ => None,
+    }
+}
+
+/// Gives every scale of the induction variable a phi and turns the
+/// multiplications into uses of it. Returns the phi of each scale.
+fn reduce(
+    irgm: &mut IRGraphManager,
+    temp_manager: &mut TempValManager,
+    iv: &InductionVar,
+    derived: Vec<Derived>,
+    header: NodeIndex,
+    preheader: NodeIndex,
+    latch: NodeIndex,
+) -> Vec<(i32, Rc<RefCell<Op>>)> {
+    let mut scaled: Vec<(i32, Rc<RefCell<Op>>)> = Vec::new();
+
+    for derived in derived {
+        let phi = match scaled.iter().find(|(scale, _)| *scale == derived.scale) {
+            Some((_, phi)) => Rc::clone(phi),
+            None => {
+                let phi = if derived.scale == 1 {
+                    Rc::clone(&iv.phi)
+                } else {
+                    scaled_phi(irgm, temp_manager, iv, derived.scale, header, preheader, latch)
+                };
+                scaled.push((derived.scale, Rc::clone(&phi)));
+                phi
+            }
+        };
+        rewrite(temp_manager, &derived, &phi);
+    }
+    scaled
+}
+
+/// A new header phi that is always `scale` times the induction variable.
+fn scaled_phi(
+    irgm: &mut IRGraphManager,
+    temp_manager: &mut TempValManager,
+    iv: &InductionVar,
+    scale: i32,
+    header: NodeIndex,
+    preheader: NodeIndex,
+    latch: NodeIndex,
+) -> Rc<RefCell<Op>> {
+    let mut new_insts = Vec::new();
+    let start = match constant(&iv.init) {
+        Some(init) => constant_value(irgm, init.wrapping_mul(scale), preheader, &mut new_insts),
+        None => {
+            let block_num = irgm.graph_manager_ref().get_ref_graph()[preheader].get_node_id();
+            let start = irgm.build_op_x_y_in_block(iv.init.clone(), Value::new(ValTy::con(scale)), InstTy::mul, block_num);
+            let start = irgm.graph_manager().add_instruction_in_node(start, &preheader);
+            new_insts.push(inst_of(&start));
+            start
+        }
+    };
+
+    // The phi is made with a stand in for its back edge value, which needs
+    // the phi itself.
+    let block_num = irgm.graph_manager_ref().get_ref_graph()[header].get_node_id();
+    let phi = irgm.build_op_x_y_in_block(start.clone(), start, InstTy::phi, block_num);
+    let phi = inst_of(&irgm.graph_manager().insert_instruction_in_node(0, phi, &header));
+
+    let block_num = irgm.graph_manager_ref().get_ref_graph()[latch].get_node_id();
+    let (inst_ty, step) = offset_by(scale.wrapping_mul(iv.step));
+    let next = irgm.build_op_x_y_in_block(Value::new(ValTy::op(Rc::clone(&phi))), step, inst_ty, block_num);
+    let position = {
+        let insts = irgm.graph_manager_ref().get_ref_graph()[latch].get_data_ref().get_inst_list_ref();
+        match insts.last() {
+            Some(last) if last.borrow().inst_type().clone() == InstTy::bra => insts.len() - 1,
+            _ => insts.len(),
+        }
+    };
+    let next = irgm.graph_manager().insert_instruction_in_node(position, next, &latch);
+    phi.borrow_mut().update_x_val(next.clone());
+
+    new_insts.push(Rc::clone(&phi));
+    new_insts.push(inst_of(&next));
+    temp_manager.add_new_insts(&new_insts);
+    phi
+}
+
+/// Has the users of a multiplication read the phi of its scale, or turns
+/// the multiplication into the addition of its offset to the phi.
+fn rewrite(temp_manager: &mut TempValManager, derived: &Derived, phi: &Rc<RefCell<Op>>) {
+    let inst_id = derived.inst.borrow().get_inst_num();
+    let phi_id = phi.borrow().get_inst_num();
+
+    if derived.offset == 0 {
+        let users = temp_manager
+            .borrow_inst(&inst_id)
+            .borrow()
+            .active_uses()
+            .iter()
+            .map(|temp_val| temp_val.borrow().inst_val())
+            .collect::<Vec<Rc<RefCell<Op>>>>();
+        for user in users {
+            user.borrow_mut()
+                .op_cleanup(inst_id, Value::new(ValTy::op(Rc::clone(phi))));
+            let user_temp = temp_manager.borrow_inst(&user.borrow().get_inst_num()).clone();
+            temp_manager.borrow_mut_inst(&phi_id).borrow_mut().add_use(user_temp);
+        }
+        temp_manager.borrow_mut_inst(&inst_id).borrow_mut().deactivate_instruction();
+        temp_manager.clean_instruction_uses(&inst_id);
+        return;
+    }
+
+    for operand_id in operand_ids(&derived.inst.borrow()) {
+        if temp_manager.has_inst(&operand_id) {
+            temp_manager.borrow_mut_inst(&operand_id).borrow_mut().remove_use(&inst_id);
+        }
+    }
+
+    let (inst_ty, offset) = offset_by(derived.offset);
+    {
+        let mut op = derived.inst.borrow_mut();
+        op.update_inst_ty(inst_ty);
+        op.update_x_val(Value::new(ValTy::op(Rc::clone(phi))));
+        op.update_y_val(offset);
+    }
+    let inst_temp = temp_manager.borrow_inst(&inst_id).clone();
+    temp_manager.borrow_mut_inst(&phi_id).borrow_mut().add_use(inst_temp);
+}
+
+/// The header's way out of the loop, if the induction variable is used by
+/// nothing else and the test can move onto `scale` times it.
+fn exit_test(
+    irgm: &IRGraphManager,
+    temp_manager: &TempValManager,
+    natural_loop: &Loop,
+    iv: &InductionVar,
+    scale: i32,
+) -> Option<ExitTest> {
+    let phi_id = iv.phi.borrow().get_inst_num();
+    let next_id = iv.next.borrow().get_inst_num();
+    let init = constant(&iv.init)?;
+
+    let next_uses = active_use_ids(temp_manager, next_id);
+    if next_uses != [phi_id] {
+        return None;
+    }
+    let mut phi_uses = active_use_ids(temp_manager, phi_id);
+    phi_uses.retain(|use_id| *use_id != next_id);
+    let cmp_id = match phi_uses.as_slice() {
+        [cmp_id] => *cmp_id,
+        _ => return None,
+    };
+
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let header = natural_loop.header;
+    let header_insts = graph[header].get_data_ref().get_inst_list_ref();
+    let branch = header_insts.iter().rev().find(|inst| inst.borrow().is_active())?.borrow();
+    let cmp = match branch.clone_x_val()?.get_var_base() {
+        ValTy::op(cmp) => cmp,
+        _ => return None,
+    };
+    if cmp.borrow().get_inst_num() != cmp_id
+        || cmp.borrow().inst_type().clone() != InstTy::cmp
+        || active_use_ids(temp_manager, cmp_id) != [branch.get_inst_num()]
+    {
+        return None;
+    }
+
+    // Only a test that is taken out of the loop tells which values the
+    // loop keeps running on.
+    let control_flow = control_flow_graph(graph);
+    let successors = block_successors(&control_flow, header);
+    let (taken_id, fall_id) = branch_targets(&control_flow, header, &successors, &branch.clone_y_val()?).ok()?;
+    if natural_loop.body.contains(&taken_id) || !natural_loop.body.contains(&fall_id) {
+        return None;
+    }
+
+    let (x_val, y_val) = (cmp.borrow().clone_x_val()?, cmp.borrow().clone_y_val()?);
+    let is_phi = |value: &Value| match value.get_var_base() {
+        ValTy::op(op) => Rc::ptr_eq(&op, &iv.phi),
+        _ => false,
+    };
+    let (iv_is_x, bound) = if is_phi(&x_val) {
+        (true, constant(&y_val)?)
+    } else if is_phi(&y_val) {
+        (false, constant(&x_val)?)
+    } else {
+        return None;
+    };
+
+    // The loop runs on while x is below y for these, above it for the
+    // others, and the variable has to step towards the bound.
+    let runs_below = match branch.inst_type() {
+        InstTy::bge | InstTy::bgt => true,
+        InstTy::ble | InstTy::blt => false,
+        _ => return None,
+    };
+    if (iv.step > 0) != (runs_below == iv_is_x) {
+        return None;
+    }
+
+    // The variable stays between its start and one step past the bound.
+    let reach = i64::from(iv.step).abs();
+    let low = i64::from(init.min(bound)) - reach;
+    let high = i64::from(init.max(bound)) + reach;
+    let fits = |value: i64| i64::from(i32::MIN) <= value && value <= i64::from(i32::MAX);
+    let scale = i64::from(scale);
+    if !(fits(low) && fits(high) && fits(low * scale) && fits(high * scale)) {
+        return None;
+    }
+
+    Some(ExitTest {
+        cmp: Rc::clone(&cmp),
+        iv_is_x,
+        bound,
+    })
+}
+
+/// Compares the scaled phi against the scaled bound, and removes the
+/// induction variable now that only its own step uses it.
+fn move_exit_test(
+    irgm: &mut IRGraphManager,
+    temp_manager: &mut TempValManager,
+    iv: &InductionVar,
+    test: ExitTest,
+    scale: i32,
+    phi: &Rc<RefCell<Op>>,
+    preheader: NodeIndex,
+) {
+    let cmp_id = test.cmp.borrow().get_inst_num();
+    for operand_id in operand_ids(&test.cmp.borrow()) {
+        if temp_manager.has_inst(&operand_id) {
+            temp_manager.borrow_mut_inst(&operand_id).borrow_mut().remove_use(&cmp_id);
+        }
+    }
+
+    let bound = test.bound.wrapping_mul(scale);
+    let phi_value = Value::new(ValTy::op(Rc::clone(phi)));
+    if test.iv_is_x {
+        let mut cmp = test.cmp.borrow_mut();
+        cmp.update_x_val(phi_value);
+        cmp.update_y_val(Value::new(ValTy::con(bound)));
+    } else {
+        // The left side of a comparison has to be an instruction, even for
+        // zero.
+        let block_num = irgm.graph_manager_ref().get_ref_graph()[preheader].get_node_id();
+        let (inst_ty, con) = offset_by(bound);
+        let zero = Value::new(ValTy::reg(RegisterAllocation::allocate_R0()));
+        let bound = irgm.build_op_x_y_in_block(zero, con, inst_ty, block_num);
+        let bound = irgm.graph_manager().add_instruction_in_node(bound, &preheader);
+        temp_manager.add_new_insts(&[inst_of(&bound)]);
+        let mut cmp = test.cmp.borrow_mut();
+        cmp.update_x_val(bound);
+        cmp.update_y_val(phi_value);
+    }
+
+    let cmp_temp = temp_manager.borrow_inst(&cmp_id).clone();
+    for operand_id in operand_ids(&test.cmp.borrow()) {
+        temp_manager.borrow_mut_inst(&operand_id).borrow_mut().add_use(Rc::clone(&cmp_temp));
+    }
+
+    let dead = [iv.phi.borrow().get_inst_num(), iv.next.borrow().get_inst_num()];
+    for inst_id in dead.iter() {
+        temp_manager.borrow_mut_inst(inst_id).borrow_mut().deactivate_instruction();
+    }
+    for inst_id in dead.iter() {
+        temp_manager.clean_instruction_uses(inst_id);
+    }
+}
+
+fn active_use_ids(temp_manager: &TempValManager, inst_id: usize) -> Vec<usize> {
+    if !temp_manager.has_inst(&inst_id) {
+        return Vec::new();
+    }
+    let mut use_ids = temp_manager
+        .borrow_inst(&inst_id)
+        .borrow()
+        .active_uses()
+        .iter()
+        .map(|temp_val| temp_val.borrow().inst_num())
+        .collect::<Vec<usize>>();
+    use_ids.sort_unstable();
+    use_ids
+}
+
+fn operand_ids(op: &Op) -> Vec<usize> {
+    let mut ids = Vec::new();
+    for value in [op.clone_x_val(), op.clone_y_val()].iter().flatten() {
+        if let ValTy::op(operand) = value.get_value() {
+            let operand_id = operand.borrow().get_inst_num();
+            if !ids.contains(&operand_id) {
+                ids.push(operand_id);
+            }
+        }
+    }
+    ids
+}
+
+/// Zero, a constant, or a value folded to one (`add R0 #c`, `sub R0 #c`).
+fn constant(value: &Value) -> Option<i32> {
+    match value.get_var_base() {
+        ValTy::con(con) => Some(con),
+        ValTy::reg(reg) if reg.to_usize() == 0 => Some(0),
+        ValTy::op(op) => match (op.borrow().inst_type(), op.borrow().get_val_ty()) {
+            (InstTy::add, (Some(ValTy::reg(reg)), Some(ValTy::con(con)))) if reg.to_usize() == 0 => Some(con),
+            (InstTy::sub, (Some(ValTy::reg(reg)), Some(ValTy::con(con)))) if reg.to_usize() == 0 => {
+                Some(con.wrapping_neg())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// R0 for zero, otherwise `add R0 #con` at the end of `node_id`.
+fn constant_value(irgm: &mut IRGraphManager, con: i32, node_id: NodeIndex, new_insts: &mut Vec<Rc<RefCell<Op>>>) -> Value {
+    let zero = Value::new(ValTy::reg(RegisterAllocation::allocate_R0()));
+    if con == 0 {
+        return zero;
+    }
+
+    let block_num = irgm.graph_manager_ref().get_ref_graph()[node_id].get_node_id();
+    let (inst_ty, con) = offset_by(con);
+    let inst = irgm.build_op_x_y_in_block(zero, con, inst_ty, block_num);
+    let value = irgm.graph_manager().add_instruction_in_node(inst, &node_id);
+    new_insts.push(inst_of(&value));
+    value
+}
+
+// Negative offsets are subtracted, the same as folded constants.
+fn offset_by(con: i32) -> (InstTy, Value) {
+    if con < 0 && con != i32::MIN {
+        (InstTy::sub, Value::new(ValTy::con(-con)))
+    } else {
+        (InstTy::add, Value::new(ValTy::con(con)))
+    }
+}
+
+fn inst_of(value: &Value) -> Rc<RefCell<Op>> {
+    match value.get_value() {
+        ValTy::op(op) => Rc::clone(op),
+        _ => panic!("Expected a value made by an instruction."),
+    }
+}
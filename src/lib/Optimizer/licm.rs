@@ -0,0 +1,325 @@
+//! Loop invariant code motion. The loops are the natural loops of the
+//! control flow graph, found from its back edges: edges into a block that
+//! dominates their source. Instructions whose operands are all defined
+//! outside a loop move into its preheader, a block placed on the one edge
+//! that enters the header from outside.
+//!
+//! A preheader runs whenever the loop is reached, even when the body never
+//! runs, so only the header's own instructions may move if they can trap:
+//! loads, and divisions by anything but a nonzero constant.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use lib::Graph::control_flow_graph;
+use lib::Graph::node::{Node, NodeType};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::verify::is_conditional_branch;
+
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::{Directed, Graph, Incoming, Outgoing};
+
+/// A natural loop: its header and every block of it, the header included.
+pub struct Loop {
+    pub header: NodeIndex,
+    pub body: HashSet<NodeIndex>,
+}
+
+/// Hoists the invariant instructions of every loop of the function, inner
+/// loops first so what leaves them can leave the outer loops as well.
+pub fn hoist_invariants(irgm: &mut IRGraphManager, root: NodeIndex) {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let dom_space = simple_fast(&graph, root);
+    let mut loops = find_loops(&graph, &dom_space, root);
+    loops.sort_by_key(|natural_loop| natural_loop.body.len());
+
+    for position in 0..loops.len() {
+        let invariants = invariant_insts(irgm, &loops[position], root);
+        if invariants.is_empty() {
+            continue;
+        }
+
+        let preheader = match insert_preheader(irgm, &loops[position]) {
+            Some(preheader) => preheader,
+            None => continue,
+        };
+        let header = loops[position].header;
+        for outer in loops[position + 1..].iter_mut() {
+            if outer.body.contains(&header) {
+                outer.body.insert(preheader);
+            }
+        }
+
+        let graph = irgm.graph_manager().get_mut_ref_graph();
+        let block_num = graph[preheader].get_node_id();
+        for (node_id, inst) in invariants {
+            graph[node_id]
+                .get_mut_data_ref()
+                .get_mut_inst_list_ref()
+                .retain(|other| !Rc::ptr_eq(other, &inst));
+            inst.borrow_mut().update_inst_block(block_num);
+            graph[preheader].get_mut_data_ref().add_instruction(inst);
+        }
+    }
+}
+
+/// The natural loops of the function, one per header, in the order their
+/// headers are first reached.
+pub fn find_loops(graph: &Graph<Node, String, Directed, u32>, dom_space: &Dominators<NodeIndex>, root: NodeIndex) -> Vec<Loop> {
+    let mut reachable = Vec::new();
+    let mut dfs = petgraph::visit::Dfs::new(graph, root);
+    while let Some(node_id) = dfs.next(graph) {
+        reachable.push(node_id);
+    }
+
+    let mut loops: Vec<Loop> = Vec::new();
+    for node_id in reachable.iter() {
+        for header in graph.neighbors_directed(*node_id, Outgoing) {
+            if !dominates(dom_space, header, *node_id) {
+                continue;
+            }
+
+            let position = match loops.iter().position(|natural_loop| natural_loop.header == header) {
+                Some(position) => position,
+                None => {
+                    let mut body = HashSet::new();
+                    body.insert(header);
+                    loops.push(Loop { header, body });
+                    loops.len() - 1
+                }
+            };
+
+            // Everything that reaches the back edge without passing the
+            // header is in the loop.
+            let body = &mut loops[position].body;
+            let mut stack = vec![*node_id];
+            while let Some(body_id) = stack.pop() {
+                if !reachable.contains(&body_id) || !body.insert(body_id) {
+                    continue;
+                }
+                stack.extend(graph.neighbors_directed(body_id, Incoming));
+            }
+        }
+    }
+    loops
+}
+
+fn dominates(dom_space: &Dominators<NodeIndex>, dominator: NodeIndex, node_id: NodeIndex) -> bool {
+    match dom_space.dominators(node_id) {
+        Some(mut dominators) => dominators.any(|dom_id| dom_id == dominator),
+        None => false,
+    }
+}
+
+/// The instructions of the loop that can move to its preheader, in an
+/// order that keeps every definition ahead of its uses.
+fn invariant_insts(irgm: &IRGraphManager, natural_loop: &Loop, root: NodeIndex) -> Vec<(NodeIndex, Rc<RefCell<Op>>)> {
+    let blocks = irgm
+        .graph_manager_ref()
+        .graph_visitor(root)
+        .into_iter()
+        .filter(|node_id| natural_loop.body.contains(node_id))
+        .collect::<Vec<NodeIndex>>();
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+
+    let mut defined = HashSet::new();
+    let mut memory = MemoryWrites::default();
+    for node_id in blocks.iter() {
+        for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+            let op = inst.borrow();
+            defined.insert(op.get_inst_num());
+            if op.is_active() {
+                memory.add(&op);
+            }
+        }
+    }
+
+    let mut hoisted = HashSet::new();
+    let mut invariants = Vec::new();
+    for node_id in blocks.iter() {
+        // Whether the header has already done something a trap moved
+        // ahead of it would change.
+        let mut has_effect = false;
+        let in_header = *node_id == natural_loop.header;
+
+        for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+            let op = inst.borrow();
+            if !op.is_active() {
+                continue;
+            }
+
+            let is_invariant = [op.clone_x_val(), op.clone_y_val()]
+                .iter()
+                .all(|value| is_invariant_value(value, &defined, &hoisted));
+            let can_trap_here = in_header && !has_effect;
+            let can_move = is_invariant
+                && match op.inst_type() {
+                    InstTy::add | InstTy::sub | InstTy::mul | InstTy::adda => !is_constant(&op),
+                    InstTy::div => can_trap_here || is_safe_divisor(op.clone_y_val()),
+                    InstTy::load => can_trap_here && !memory.may_write(op.clone_y_val()),
+                    _ => false,
+                };
+
+            if can_move {
+                hoisted.insert(op.get_inst_num());
+                invariants.push((*node_id, Rc::clone(inst)));
+            } else if has_effect_or_trap(op.inst_type()) {
+                has_effect = true;
+            }
+        }
+    }
+    invariants
+}
+
+fn is_invariant_value(value: &Option<Value>, defined: &HashSet<usize>, hoisted: &HashSet<usize>) -> bool {
+    let value = match value {
+        Some(value) => value,
+        None => return true,
+    };
+
+    match value.get_var_base() {
+        ValTy::op(op) => {
+            let inst_id = op.borrow().get_inst_num();
+            !defined.contains(&inst_id) || hoisted.contains(&inst_id)
+        }
+        ValTy::con(_) | ValTy::adr(_) | ValTy::reg(_) => true,
+        _ => false,
+    }
+}
+
+// Constants are as cheap to make in the loop as to keep in a register
+// through it.
+fn is_constant(op: &Op) -> bool {
+    match (op.inst_type(), op.get_val_ty()) {
+        (InstTy::add, (Some(ValTy::reg(reg)), Some(ValTy::con(_))))
+        | (InstTy::sub, (Some(ValTy::reg(reg)), Some(ValTy::con(_)))) => reg.to_usize() == 0,
+        _ => false,
+    }
+}
+
+fn is_safe_divisor(value: Option<Value>) -> bool {
+    match value.map(|value| value.get_var_base()) {
+        Some(ValTy::con(con)) => con != 0,
+        _ => false,
+    }
+}
+
+fn has_effect_or_trap(inst_ty: &InstTy) -> bool {
+    matches!(
+        inst_ty,
+        InstTy::call | InstTy::store | InstTy::read | InstTy::write | InstTy::writeNL | InstTy::load | InstTy::div
+    )
+}
+
+/// What the loop may store to, by the base address of the array or
+/// variable an address is taken from.
+#[derive(Default)]
+struct MemoryWrites {
+    bases: HashSet<String>,
+    // A call, or a store whose address could not be followed.
+    anything: bool,
+}
+
+impl MemoryWrites {
+    fn add(&mut self, op: &Op) {
+        match op.inst_type() {
+            InstTy::call => self.anything = true,
+            InstTy::store => match memory_base(op.clone_y_val()) {
+                Some(base) => {
+                    self.bases.insert(base);
+                }
+                None => self.anything = true,
+            },
+            _ => {}
+        }
+    }
+
+    fn may_write(&self, address: Option<Value>) -> bool {
+        match memory_base(address) {
+            Some(base) => self.anything || self.bases.contains(&base),
+            None => true,
+        }
+    }
+}
+
+/// Follows an address through its `adda` to the `add &reg &name` it is
+/// based on.
+fn memory_base(address: Option<Value>) -> Option<String> {
+    let mut op = match address?.get_var_base() {
+        ValTy::op(op) => op,
+        _ => return None,
+    };
+
+    if op.borrow().inst_type().clone() == InstTy::adda {
+        let base = match op.borrow().clone_y_val()?.get_var_base() {
+            ValTy::op(base) => base,
+            _ => return None,
+        };
+        op = base;
+    }
+
+    let op = op.borrow();
+    match (op.inst_type(), op.get_val_ty()) {
+        (InstTy::add, (Some(reg @ ValTy::adr(_)), Some(name @ ValTy::adr(_)))) => {
+            Some(format!("{} {}", reg.to_string(), name.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Finds or makes the block the loop's invariants move to. The loop needs
+/// a single way in from outside, and the block it comes from can not be
+/// deciding between the loop and somewhere else.
+pub fn insert_preheader(irgm: &mut IRGraphManager, natural_loop: &Loop) -> Option<NodeIndex> {
+    let header = natural_loop.header;
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+
+    let mut entries = Vec::new();
+    for edge in graph.edges_directed(header, Incoming) {
+        if edge.weight() == "black" && !natural_loop.body.contains(&edge.source()) && !entries.contains(&edge.source()) {
+            entries.push(edge.source());
+        }
+    }
+    if entries.len() != 1 {
+        return None;
+    }
+
+    let entry = entries[0];
+    if graph[entry].get_node_type() == NodeType::loop_preheader {
+        return Some(entry);
+    }
+    let has_branch = graph[entry]
+        .get_data_ref()
+        .get_inst_list_ref()
+        .iter()
+        .any(|inst| inst.borrow().is_active() && is_conditional_branch(inst.borrow().inst_type()));
+    if has_branch {
+        return None;
+    }
+
+    let current_node = irgm.graph_manager().get_current_id();
+    let preheader = *irgm.new_node(String::from("Loop_Preheader"), NodeType::loop_preheader);
+    irgm.graph_manager().switch_current_node_index(current_node);
+
+    let graph = irgm.graph_manager().get_mut_ref_graph();
+    let mut entry_edges = graph
+        .edges_directed(header, Incoming)
+        .filter(|edge| edge.weight() == "black" && edge.source() == entry)
+        .map(|edge| edge.id())
+        .collect::<Vec<_>>();
+    // Removing an edge moves the last one into its place, so the later
+    // edges go first.
+    entry_edges.sort_by_key(|edge_id| std::cmp::Reverse(edge_id.index()));
+    for edge_id in entry_edges {
+        graph.remove_edge(edge_id);
+    }
+
+    irgm.graph_manager().add_edge(entry, preheader);
+    irgm.graph_manager().add_edge(preheader, header);
+    Some(preheader)
+}
+
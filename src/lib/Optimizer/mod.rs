@@ -1,13 +1,19 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::Instant;
 
 pub mod cleaner;
 pub mod constant_evaluation;
 pub mod cse;
 pub mod dce;
+pub mod induction;
+pub mod licm;
 pub mod node_remover;
 pub mod operator_dominator;
+pub mod pass_manager;
+pub mod sccp;
+pub mod stats;
 pub mod temp_value_manager;
 
 use lib::IR::address_manager::{AddressManager, UniqueAddress};
@@ -23,6 +29,8 @@ use lib::Graph::graph_manager::GraphManager;
 use lib::Graph::node::{Node, NodeData, NodeId, NodeType};
 use lib::{clean_base_values, extract_constants};
 
+use self::pass_manager::Pass;
+use self::stats::{graph_size, PassStats, Snapshot};
 use self::temp_value_manager::TempValManager;
 use super::petgraph::Graph;
 use super::{graph, petgraph};
@@ -36,6 +44,9 @@ pub struct Optimizer {
 
     main_temp_val_manager: TempValManager,
     func_temp_val_map: HashMap<String, TempValManager>,
+
+    stats: Vec<PassStats>,
+    count_changes: bool,
 }
 
 impl Optimizer {
@@ -44,9 +55,19 @@ impl Optimizer {
             irgm,
             main_temp_val_manager: TempValManager::new(),
             func_temp_val_map: HashMap::new(),
+            stats: Vec::new(),
+            count_changes: false,
         }
     }
 
+    /// Counts what every pass changed as well as timing it. Comparing the
+    /// instructions before and after each pass is not free, so it is only
+    /// done when the stats are asked for.
+    pub fn with_change_counts(mut self) -> Self {
+        self.count_changes = true;
+        self
+    }
+
     pub fn get_irgm(self) -> IRGraphManager {
         self.irgm
     }
@@ -59,6 +80,11 @@ impl Optimizer {
         self.func_temp_val_map.clone()
     }
 
+    /// What every pass that ran did, one entry per pass and function.
+    pub fn get_stats(&self) -> Vec<PassStats> {
+        self.stats.clone()
+    }
+
     pub fn get_irgm_ref(&self) -> &IRGraphManager {
         &self.irgm
     }
@@ -67,294 +93,142 @@ impl Optimizer {
         &mut self.irgm
     }
 
-    fn get_main_manager(&self) -> TempValManager {
-        self.main_temp_val_manager.clone()
-    }
-
-    fn get_func_manager(&self) -> HashMap<String, TempValManager> {
-        self.func_temp_val_map.clone()
-    }
-
-    pub fn pass_0(&mut self) {
-        // First clean graph
-        clean_base_values(self.get_irgm_mut_ref());
-
-        let main_node_index = self.get_irgm_mut_ref().graph_manager().get_main_node();
-        extract_constants(&mut self.irgm, main_node_index.clone());
-
-        for (func_name, func_index) in self
-            .get_irgm_mut_ref()
-            .function_manager()
-            .list_functions()
-            .iter()
-            {
-                extract_constants(&mut self.irgm, func_index.clone());
-            }
-
-        // Create locals for easier access to them without worrying about borrowing
-        let mut local_main_manager = self.get_main_manager();
-        let mut local_func_map = self.get_func_manager();
-
-        // Second get temp_val_manager for main
-        local_main_manager
-            .pull_temp_values(self.get_irgm_mut_ref().graph_manager(), main_node_index);
-
-        let graph_visitor = self
-            .irgm
-            .graph_manager()
-            .graph_visitor(main_node_index.clone());
-        for node_id in &graph_visitor {
-            if NodeType::exit
-                == self
-                    .irgm
-                    .graph_manager()
-                    .get_ref_graph()
-                    .node_weight(node_id.clone())
-                    .unwrap()
-                    .get_node_type()
-            {
-                constant_evaluation::mark_invalid_nodes(
-                    self.get_irgm_mut_ref().graph_manager(),
-                    main_node_index,
-                    node_id.clone(),
-                    &mut local_main_manager,
-                );
-            }
-        }
-
-        // Build managers for all functions in program
-        for (func_name, func_index) in self
-            .get_irgm_mut_ref()
-            .function_manager()
-            .list_functions()
-            .iter()
-        {
-            let mut temp_manager = TempValManager::new();
-            temp_manager
-                .pull_temp_values(self.get_irgm_mut_ref().graph_manager(), func_index.clone());
+    /// Runs `pass` over main and then every function, in name order.
+    pub fn run_pass(&mut self, pass: &dyn Pass) {
+        let start = Instant::now();
+        pass.start(&mut self.irgm);
+        let start_time = start.elapsed();
 
-            let graph_visitor = self.irgm.graph_manager().graph_visitor(func_index.clone());
-            for node_id in graph_visitor {
-                if NodeType::exit
-                    == self
-                        .irgm
-                        .graph_manager()
-                        .get_ref_graph()
-                        .node_weight(node_id)
-                        .unwrap()
-                        .get_node_type()
-                {
-                    constant_evaluation::mark_invalid_nodes(
-                        self.get_irgm_mut_ref().graph_manager(),
-                        func_index.clone(),
-                        node_id,
-                        &mut temp_manager,
-                    );
-                }
-            }
-
-            local_func_map.insert(func_name.clone(), temp_manager);
-        }
-
-        // Do some ignored path cleanup
-        /*clean_graph(
-            &mut self.irgm,
-            main_node_index,
-            &mut local_main_manager,
-            &graph_visitor,
-        );*/
-
-        // Return values cloned for locals to update Optimizer
-        self.main_temp_val_manager = local_main_manager;
-        self.func_temp_val_map = local_func_map;
-    }
-
-    pub fn pass_1(&mut self) {
-        let mut local_temp_manager = self.main_temp_val_manager.clone();
         let root_node = self.irgm.graph_manager().get_main_node();
-
-        let graph_visitor = self.irgm.graph_manager().graph_visitor(root_node);
-
-        constant_evaluation::eval_program_constants(
-            &mut self.irgm,
-            &mut local_temp_manager,
-            &graph_visitor,
-        );
-
-        let new_root = clean_graph(
+        let (new_root, mut stats) = run_on_function(
+            pass,
+            "main",
+            self.count_changes,
             &mut self.irgm,
+            &mut self.main_temp_val_manager,
             root_node,
-            &mut local_temp_manager,
-            &graph_visitor,
         );
-        self.irgm.graph_manager().update_main_node(new_root);
-
-        // Return temp manager to itself.
-        self.main_temp_val_manager = local_temp_manager;
+        if pass.cleans_graph() {
+            self.irgm.graph_manager().update_main_node(new_root);
+        }
 
-        for (func, temp_manager) in self.func_temp_val_map.iter_mut() {
-            let mut root_node = self
-                .irgm
-                .function_manager()
-                .get_function(func)
-                .clone_index();
+        // Work done once for the whole program is counted towards main.
+        stats.time += start_time;
+        self.record(stats);
 
-            // First, update the root node of the function
-            for node_id in self.irgm.graph_manager().get_ref_graph().node_indices() {
-                let current_node_id = self
-                    .irgm
-                    .graph_manager()
-                    .get_ref_graph()
-                    .node_weight(node_id)
-                    .unwrap()
-                    .get_node_id();
+        let mut functions = self.irgm.function_manager().list_functions();
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
 
-                if root_node.index() == current_node_id {
-                    // Found new node_id for function. Update root node and break loop.
-                    root_node = node_id;
-                    break;
-                }
-            }
+        for (func_name, _) in functions {
+            let root_node = self.function_root(&func_name);
+            let temp_manager = self
+                .func_temp_val_map
+                .entry(func_name.clone())
+                .or_insert_with(TempValManager::new);
 
-            let function_visitor = self.irgm.graph_manager().graph_visitor(root_node);
-            constant_evaluation::eval_program_constants(
+            let (new_root, stats) = run_on_function(
+                pass,
+                &func_name,
+                self.count_changes,
                 &mut self.irgm,
                 temp_manager,
-                &function_visitor,
+                root_node,
             );
-
-            let new_root = clean_graph(&mut self.irgm, root_node, temp_manager, &function_visitor);
-            self.irgm
-                .function_manager()
-                .get_mut_function(func)
-                .update_index(new_root);
-
-            //self.func_temp_val_map.insert(func.clone(), temp_manager.clone());
+            if pass.cleans_graph() {
+                self.irgm
+                    .function_manager()
+                    .get_mut_function(&func_name)
+                    .update_index(new_root);
+            }
+            self.record(stats);
         }
     }
 
-    pub fn pass_2(&mut self) {
-        // Pass 2 consists of CSE
-        let root_node = self.irgm.graph_manager().get_main_node();
-
-        cse::trace_common_expression(
-            &mut self.irgm,
-            &mut self.main_temp_val_manager,
-            root_node.clone(),
-        );
-
-        let graph = self.irgm.graph_manager().get_mut_ref_graph().clone();
-        let dom_space = simple_fast(&graph, root_node.clone());
-
-        let mut load_remover = cse::CLE::new(&mut self.irgm, &root_node, dom_space);
-        load_remover.remove_loads(&mut self.irgm, &mut self.main_temp_val_manager);
-
-        let graph_visitor = self.irgm.graph_manager().graph_visitor(root_node.clone());
+    fn record(&mut self, stats: PassStats) {
+        match self
+            .stats
+            .iter_mut()
+            .find(|recorded| recorded.pass == stats.pass && recorded.func_name == stats.func_name)
+        {
+            Some(recorded) => recorded.merge(&stats),
+            None => self.stats.push(stats),
+        }
+    }
 
-        let new_root = clean_graph(
-            &mut self.irgm,
-            root_node,
-            &mut self.main_temp_val_manager,
-            &graph_visitor,
-        );
-        self.irgm.graph_manager().update_main_node(new_root);
+    /// Removing nodes shifts the indices of the graph, so the root of a
+    /// function is found again by the node id it was created with.
+    fn function_root(&mut self, func_name: &String) -> NodeIndex {
+        let root_node = self
+            .irgm
+            .function_manager()
+            .get_function(func_name)
+            .clone_index();
 
-        for (func_name, temp_manager) in self.func_temp_val_map.iter_mut() {
-            let mut root_node = self
+        for node_id in self.irgm.graph_manager().get_ref_graph().node_indices() {
+            let current_node_id = self
                 .irgm
-                .function_manager()
-                .get_function(func_name)
-                .clone_index();
-
-            for node_id in self.irgm.graph_manager().get_ref_graph().node_indices() {
-                let current_node_id = self
-                    .irgm
-                    .graph_manager()
-                    .get_ref_graph()
-                    .node_weight(node_id)
-                    .unwrap()
-                    .get_node_id();
+                .graph_manager()
+                .get_ref_graph()
+                .node_weight(node_id)
+                .unwrap()
+                .get_node_id();
 
-                if root_node.index() == current_node_id {
-                    root_node = node_id;
-                    break;
-                }
+            if root_node.index() == current_node_id {
+                return node_id;
             }
-
-            cse::trace_common_expression(&mut self.irgm, temp_manager, root_node.clone());
-
-            let dom_space = simple_fast(&graph, root_node.clone());
-
-            let mut load_remover = cse::CLE::new(&mut self.irgm, &root_node, dom_space);
-            load_remover.remove_loads(&mut self.irgm, temp_manager);
-
-            let function_visitor = self.irgm.graph_manager().graph_visitor(root_node.clone());
-            let new_root = clean_graph(&mut self.irgm, root_node, temp_manager, &function_visitor);
-            self.irgm
-                .function_manager()
-                .get_mut_function(func_name)
-                .update_index(new_root);
         }
-    }
-
-    pub fn pass_3(&mut self) {
-        let root_node = self.irgm.graph_manager().get_main_node();
-
-        dce::dead_code_elimination(
-            &mut self.irgm,
-            &mut self.main_temp_val_manager,
-            root_node.clone(),
-        );
-        let graph_visitor = self.irgm.graph_manager().graph_visitor(root_node.clone());
-
-        let new_root = clean_graph(
-            &mut self.irgm,
-            root_node,
-            &mut self.main_temp_val_manager,
-            &graph_visitor,
-        );
-        self.irgm.graph_manager().update_main_node(new_root);
-
-        for (func_name, temp_manager) in self.func_temp_val_map.iter_mut() {
-            let mut func_root_node = self
-                .irgm
-                .function_manager()
-                .get_function(func_name)
-                .clone_index();
 
-            for node_id in self.irgm.graph_manager().get_ref_graph().node_indices() {
-                let current_node_id = self
-                    .irgm
-                    .graph_manager()
-                    .get_ref_graph()
-                    .node_weight(node_id)
-                    .unwrap()
-                    .get_node_id();
+        root_node
+    }
+}
 
-                if func_root_node.index() == current_node_id {
-                    func_root_node = node_id;
-                    break;
-                }
+/// Runs `pass` over the function at `root_node` and the cleaner after it,
+/// returning the root the function has afterwards and what was done. Only
+/// the time is measured unless `count_changes` is set.
+fn run_on_function(
+    pass: &dyn Pass,
+    func_name: &str,
+    count_changes: bool,
+    irgm: &mut IRGraphManager,
+    temp_manager: &mut TempValManager,
+    root_node: NodeIndex,
+) -> (NodeIndex, PassStats) {
+    let mut stats = PassStats::new(pass.name(), func_name);
+
+    // Taken before the pass runs, so the cleaner still sees the blocks the
+    // pass cut off from the root.
+    let graph_visitor = irgm.graph_manager().graph_visitor(root_node);
+    let before = if count_changes {
+        Some(Snapshot::take(irgm, &graph_visitor))
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    pass.run_function(irgm, temp_manager, root_node);
+    stats.time = start.elapsed();
+    if let Some(before) = before {
+        // Blocks the pass added, such as the preheaders of licm, are only
+        // found from the root afterwards.
+        let mut blocks = irgm.graph_manager().graph_visitor(root_node);
+        for node_id in graph_visitor.iter() {
+            if !blocks.contains(node_id) {
+                blocks.push(*node_id);
             }
-
-            dce::dead_code_elimination(&mut self.irgm, temp_manager, func_root_node.clone());
-            let function_visitor = self
-                .irgm
-                .graph_manager()
-                .graph_visitor(func_root_node.clone());
-            let new_root = clean_graph(
-                &mut self.irgm,
-                func_root_node,
-                temp_manager,
-                &function_visitor,
-            );
-            self.irgm
-                .function_manager()
-                .get_mut_function(func_name)
-                .update_index(new_root);
         }
+        stats.count_changes(&before, &Snapshot::take(irgm, &blocks));
     }
-    pub fn pass_4(&mut self) {
-        self.irgm.graph_manager().map_blocks_to_node_ids();
+
+    let mut new_root = root_node;
+    if pass.cleans_graph() {
+        let size = if count_changes { Some(graph_size(irgm)) } else { None };
+        let start = Instant::now();
+        new_root = clean_graph(irgm, root_node, temp_manager, &graph_visitor);
+        stats.time += start.elapsed();
+        if let Some(size) = size {
+            stats.count_cleaned(size, graph_size(irgm));
+        }
     }
+
+    (new_root, stats)
 }
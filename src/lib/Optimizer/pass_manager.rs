@@ -0,0 +1,353 @@
+//! The passes the optimizer knows and the pipeline that picks which of
+//! them run. Every pass is run the same way, over main and then every
+//! function, so a pass only has to know how to treat one function.
+
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::ir_text;
+use lib::Graph::node::NodeType;
+use lib::{clean_base_values, extract_constants};
+
+use super::temp_value_manager::TempValManager;
+use super::{constant_evaluation, cse, dce, induction, licm, sccp, Optimizer};
+use petgraph::algo::dominators::simple_fast;
+use petgraph::prelude::NodeIndex;
+
+/// Rounds `PassPipeline::repeat_until_fixpoint` is given by the driver.
+pub const DEFAULT_FIXPOINT_ROUNDS: usize = 10;
+
+pub trait Pass {
+    /// Name the pass is enabled, disabled and reported by.
+    fn name(&self) -> &'static str;
+
+    /// Runs once, before the pass visits any function.
+    fn start(&self, _irgm: &mut IRGraphManager) {}
+
+    /// Runs over one function, main included, whose graph starts at `root`.
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex);
+
+    /// Whether `clean_graph` tidies each function up after the pass.
+    fn cleans_graph(&self) -> bool {
+        true
+    }
+
+    /// Whether code generation depends on the pass, so it always runs.
+    fn required(&self) -> bool {
+        false
+    }
+}
+
+/// Moves constants out of instructions that can not hold them and finds
+/// the temporary values every later pass tracks.
+pub struct Prepare;
+
+impl Pass for Prepare {
+    fn name(&self) -> &'static str {
+        "prepare"
+    }
+
+    fn start(&self, irgm: &mut IRGraphManager) {
+        clean_base_values(irgm);
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        extract_constants(irgm, root);
+        temp_manager.pull_temp_values(irgm.graph_manager(), root);
+
+        for node_id in irgm.graph_manager().graph_visitor(root) {
+            let node_type = irgm
+                .graph_manager()
+                .get_ref_graph()
+                .node_weight(node_id)
+                .unwrap()
+                .get_node_type();
+            if node_type == NodeType::exit {
+                constant_evaluation::mark_invalid_nodes(irgm.graph_manager(), root, node_id, temp_manager);
+            }
+        }
+    }
+
+    fn cleans_graph(&self) -> bool {
+        false
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+}
+
+/// Folds constant expressions and branches.
+pub struct ConstantEvaluation;
+
+impl Pass for ConstantEvaluation {
+    fn name(&self) -> &'static str {
+        "const-eval"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        let graph_visitor = irgm.graph_manager().graph_visitor(root);
+        constant_evaluation::eval_program_constants(irgm, temp_manager, &graph_visitor);
+    }
+}
+
+/// Sparse conditional constant propagation, for the constants and
+/// branches constant evaluation can not decide in one walk.
+pub struct Sccp;
+
+impl Pass for Sccp {
+    fn name(&self) -> &'static str {
+        "sccp"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        sccp::propagate_constants(irgm, temp_manager, root);
+    }
+}
+
+/// Common subexpression elimination, loads included.
+pub struct CommonSubexpressions;
+
+impl Pass for CommonSubexpressions {
+    fn name(&self) -> &'static str {
+        "cse"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        cse::trace_common_expression(irgm, temp_manager, root);
+
+        let graph = irgm.graph_manager().get_ref_graph().clone();
+        let dom_space = simple_fast(&graph, root);
+        let mut load_remover = cse::CLE::new(irgm, &root, dom_space);
+        load_remover.remove_loads(irgm, temp_manager);
+    }
+}
+
+/// Dead code elimination, for the values nothing uses any more.
+pub struct DeadCode;
+
+impl Pass for DeadCode {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        dce::dead_code_elimination(irgm, temp_manager, root);
+    }
+}
+
+/// Loop invariant code motion into preheaders made in front of the loops.
+pub struct LoopInvariants;
+
+impl Pass for LoopInvariants {
+    fn name(&self) -> &'static str {
+        "licm"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, _temp_manager: &mut TempValManager, root: NodeIndex) {
+        licm::hoist_invariants(irgm, root);
+    }
+}
+
+/// Replaces multiplications of loop induction variables with additions.
+pub struct StrengthReduction;
+
+impl Pass for StrengthReduction {
+    fn name(&self) -> &'static str {
+        "strength-reduce"
+    }
+
+    fn run_function(&self, irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+        induction::reduce_strength(irgm, temp_manager, root);
+    }
+}
+
+/// Renumbers the blocks after the ones the other passes removed.
+pub struct MapBlocks;
+
+impl Pass for MapBlocks {
+    fn name(&self) -> &'static str {
+        "map-blocks"
+    }
+
+    fn start(&self, irgm: &mut IRGraphManager) {
+        irgm.graph_manager().map_blocks_to_node_ids();
+    }
+
+    fn run_function(&self, _irgm: &mut IRGraphManager, _temp_manager: &mut TempValManager, _root: NodeIndex) {}
+
+    fn cleans_graph(&self) -> bool {
+        false
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+}
+
+/// Every pass, in the order they run in.
+pub fn all_passes() -> Vec<Box<dyn Pass>> {
+    vec![
+        Box::new(Prepare),
+        Box::new(ConstantEvaluation),
+        Box::new(Sccp),
+        Box::new(CommonSubexpressions),
+        Box::new(DeadCode),
+        Box::new(LoopInvariants),
+        Box::new(StrengthReduction),
+        Box::new(MapBlocks),
+    ]
+}
+
+/// Names of every pass, in the order they run in.
+pub fn pass_names() -> Vec<&'static str> {
+    all_passes().iter().map(|pass| pass.name()).collect()
+}
+
+/// Which passes run, always in the order of `all_passes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassPipeline {
+    enabled: Vec<&'static str>,
+    // 1 runs every pass once, more repeats the passes between the required
+    // ones until the IR stops changing or that many rounds have run.
+    max_rounds: usize,
+}
+
+impl PassPipeline {
+    /// Only the passes code generation needs.
+    pub fn new() -> Self {
+        let enabled = all_passes()
+            .iter()
+            .filter(|pass| pass.required())
+            .map(|pass| pass.name())
+            .collect();
+        PassPipeline { enabled, max_rounds: 1 }
+    }
+
+    /// The passes of `-O<opt_level>`: 0 only prepares the graph for
+    /// allocation, 1 adds constant evaluation and propagation and 2 adds
+    /// CSE, dead code elimination, loop invariant code motion and strength
+    /// reduction.
+    pub fn for_level(opt_level: u8) -> Result<Self, String> {
+        let pipeline = PassPipeline::new();
+        match opt_level {
+            0 => Ok(pipeline),
+            1 => pipeline.enable("const-eval")?.enable("sccp"),
+            2 => pipeline
+                .enable("const-eval")?
+                .enable("sccp")?
+                .enable("cse")?
+                .enable("dce")?
+                .enable("licm")?
+                .enable("strength-reduce"),
+            _ => Err(format!("Unknown optimization level {}.", opt_level)),
+        }
+    }
+
+    pub fn enable(mut self, name: &str) -> Result<Self, String> {
+        let name = find_pass(name)?;
+        if !self.enabled.contains(&name) {
+            self.enabled.push(name);
+            self.enabled.sort_by_key(|enabled| pass_names().iter().position(|pass| pass == enabled));
+        }
+        Ok(self)
+    }
+
+    pub fn disable(mut self, name: &str) -> Result<Self, String> {
+        let name = find_pass(name)?;
+        if all_passes().iter().any(|pass| pass.name() == name && pass.required()) {
+            return Err(format!("Pass '{}' is needed for code generation and can not be disabled.", name));
+        }
+        self.enabled.retain(|enabled| *enabled != name);
+        Ok(self)
+    }
+
+    /// Repeats the optional passes until a round leaves the IR as it was,
+    /// running at most `max_rounds` rounds.
+    pub fn repeat_until_fixpoint(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds.max(1);
+        self
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(&name)
+    }
+
+    /// Runs the enabled passes over the program, handing the graph to
+    /// `after_pass` along with the position of the pass in `all_passes`
+    /// each time one finishes.
+    pub fn run<F>(&self, optimizer: &mut Optimizer, after_pass: F)
+    where
+        F: FnMut(usize, &mut IRGraphManager),
+    {
+        self.run_observed(optimizer, |_| {}, after_pass);
+    }
+
+    /// Same as `run`, also calling `before_pass` with the position of each
+    /// pass as it starts, so a pass that panics can be told apart from the
+    /// one before it.
+    pub fn run_observed<B, F>(&self, optimizer: &mut Optimizer, mut before_pass: B, mut after_pass: F)
+    where
+        B: FnMut(usize),
+        F: FnMut(usize, &mut IRGraphManager),
+    {
+        let passes = all_passes()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, pass)| self.is_enabled(pass.name()))
+            .collect::<Vec<(usize, Box<dyn Pass>)>>();
+
+        let mut run_pass = |optimizer: &mut Optimizer, index: usize, pass: &dyn Pass| {
+            before_pass(index);
+            optimizer.run_pass(pass);
+            after_pass(index, optimizer.get_irgm_mut_ref());
+        };
+
+        // The passes from the first optional one to the last are the ones
+        // that repeat, the required ones around them run once.
+        let first = passes.iter().position(|(_, pass)| !pass.required()).unwrap_or(passes.len());
+        let last = passes.iter().rposition(|(_, pass)| !pass.required()).map_or(first, |last| last + 1);
+        let (before, rest) = passes.split_at(first);
+        let (repeated, after) = rest.split_at(last - first);
+
+        for (index, pass) in before {
+            run_pass(optimizer, *index, pass.as_ref());
+        }
+
+        for round in 1..=self.max_rounds {
+            let ir = if round < self.max_rounds {
+                Some(ir_text::to_text(optimizer.get_irgm_mut_ref()))
+            } else {
+                None
+            };
+
+            for (index, pass) in repeated {
+                run_pass(optimizer, *index, pass.as_ref());
+            }
+
+            match ir {
+                Some(ir) if ir != ir_text::to_text(optimizer.get_irgm_mut_ref()) => {}
+                _ => break,
+            }
+        }
+
+        for (index, pass) in after {
+            run_pass(optimizer, *index, pass.as_ref());
+        }
+    }
+}
+
+impl Default for PassPipeline {
+    fn default() -> Self {
+        PassPipeline::new()
+    }
+}
+
+fn find_pass(name: &str) -> Result<&'static str, String> {
+    match pass_names().into_iter().find(|pass| *pass == name) {
+        Some(name) => Ok(name),
+        None => Err(format!(
+            "Unknown pass '{}', the passes are {}.",
+            name,
+            pass_names().join(", ")
+        )),
+    }
+}
@@ -0,0 +1,507 @@
+//! Sparse conditional constant propagation (Wegman and Zadeck). Every value
+//! starts out unknown and every block unreachable, and both are only given
+//! up on when an executable path shows otherwise. So a loop phi whose back
+//! edge brings the same constant stays constant, and a branch that is only
+//! decided once another one is gone is decided in the same analysis.
+//!
+//! The edges followed out of each block are the ones code generation would
+//! take, so a pruned graph runs the same as before.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use lib::Graph::node::{Node, NodeType};
+use lib::Graph::{block_successors, branch_targets, control_flow_graph, is_phi_x_side};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::verify::is_conditional_branch;
+use lib::RegisterAllocator::RegisterAllocation;
+
+use super::temp_value_manager::TempValManager;
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::prelude::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::{Directed, Graph, Incoming, Outgoing};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lattice {
+    // Not yet seen on an executable path.
+    Top,
+    Const(i32),
+    Bottom,
+}
+
+impl Lattice {
+    fn meet(self, other: Lattice) -> Lattice {
+        match (self, other) {
+            (Lattice::Top, other) | (other, Lattice::Top) => other,
+            (Lattice::Const(x), Lattice::Const(y)) if x == y => Lattice::Const(x),
+            _ => Lattice::Bottom,
+        }
+    }
+}
+
+/// Folds every value that is constant on all executable paths and removes
+/// the blocks no executable path reaches.
+pub fn propagate_constants(irgm: &mut IRGraphManager, temp_manager: &mut TempValManager, root: NodeIndex) {
+    let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+    let dom_space = simple_fast(&graph, root);
+    let mut analysis = Analysis::new(&graph, &dom_space, root);
+    analysis.run(temp_manager);
+
+    fold_values(&analysis, temp_manager);
+    prune_branches(irgm, &analysis, temp_manager);
+    remove_phis(&analysis, temp_manager);
+    remove_blocks(irgm, &analysis, temp_manager);
+}
+
+struct Analysis<'a> {
+    graph: &'a Graph<Node, String, Directed, u32>,
+    dom_space: &'a Dominators<NodeIndex>,
+    root: NodeIndex,
+
+    // Blocks of the function, in visit order.
+    blocks: Vec<NodeIndex>,
+    insts: HashMap<usize, (NodeIndex, Rc<RefCell<Op>>)>,
+
+    values: HashMap<usize, Lattice>,
+    executable_blocks: HashSet<NodeIndex>,
+    executable_edges: HashSet<(NodeIndex, NodeIndex)>,
+    flow_list: Vec<(NodeIndex, NodeIndex)>,
+    ssa_list: Vec<usize>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(graph: &'a Graph<Node, String, Directed, u32>, dom_space: &'a Dominators<NodeIndex>, root: NodeIndex) -> Self {
+        let mut blocks = Vec::new();
+        let mut dfs = petgraph::visit::Dfs::new(graph, root);
+        while let Some(node_id) = dfs.next(graph) {
+            blocks.push(node_id);
+        }
+
+        let mut insts = HashMap::new();
+        for node_id in blocks.iter() {
+            for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+                if inst.borrow().is_active() {
+                    insts.insert(inst.borrow().get_inst_num(), (*node_id, Rc::clone(inst)));
+                }
+            }
+        }
+
+        Analysis {
+            graph,
+            dom_space,
+            root,
+            blocks,
+            insts,
+            values: HashMap::new(),
+            executable_blocks: HashSet::new(),
+            executable_edges: HashSet::new(),
+            flow_list: Vec::new(),
+            ssa_list: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, temp_manager: &TempValManager) {
+        self.executable_blocks.insert(self.root);
+        self.visit_block(self.root, temp_manager);
+
+        loop {
+            if let Some((parent_id, node_id)) = self.flow_list.pop() {
+                if !self.executable_edges.insert((parent_id, node_id)) {
+                    continue;
+                }
+
+                if self.executable_blocks.insert(node_id) {
+                    self.visit_block(node_id, temp_manager);
+                } else {
+                    // Only the phis see the new edge.
+                    for inst in self.block_insts(node_id) {
+                        if inst.borrow().inst_type().clone() == InstTy::phi {
+                            self.visit_inst(node_id, &inst, temp_manager);
+                        }
+                    }
+                }
+            } else if let Some(inst_id) = self.ssa_list.pop() {
+                let (node_id, inst) = match self.insts.get(&inst_id) {
+                    Some((node_id, inst)) => (*node_id, Rc::clone(inst)),
+                    None => continue,
+                };
+                if self.executable_blocks.contains(&node_id) {
+                    self.visit_inst(node_id, &inst, temp_manager);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn block_insts(&self, node_id: NodeIndex) -> Vec<Rc<RefCell<Op>>> {
+        self.graph[node_id]
+            .get_data_ref()
+            .get_inst_list_ref()
+            .iter()
+            .filter(|inst| inst.borrow().is_active())
+            .cloned()
+            .collect()
+    }
+
+    fn visit_block(&mut self, node_id: NodeIndex, temp_manager: &TempValManager) {
+        let mut has_branch = false;
+        for inst in self.block_insts(node_id) {
+            has_branch |= is_conditional_branch(inst.borrow().inst_type());
+            self.visit_inst(node_id, &inst, temp_manager);
+        }
+
+        // Without a branch every way out is kept, the extra edges to the
+        // exit included.
+        if !has_branch {
+            for child_id in block_successors(self.graph, node_id) {
+                self.flow_list.push((node_id, child_id));
+            }
+        }
+    }
+
+    fn visit_inst(&mut self, node_id: NodeIndex, inst: &Rc<RefCell<Op>>, temp_manager: &TempValManager) {
+        let inst_ty = inst.borrow().inst_type().clone();
+        if is_conditional_branch(&inst_ty) {
+            self.visit_branch(node_id, inst);
+            return;
+        }
+
+        let value = match inst_ty {
+            InstTy::phi => self.eval_phi(node_id, &inst.borrow()),
+            InstTy::add | InstTy::sub | InstTy::mul | InstTy::div | InstTy::cmp => {
+                let op = inst.borrow();
+                let x_val = self.operand(op.clone_x_val());
+                let y_val = self.operand(op.clone_y_val());
+                match (x_val, y_val) {
+                    (Lattice::Const(x), Lattice::Const(y)) => match fold(&inst_ty, x, y) {
+                        Some(result) => Lattice::Const(result),
+                        None => Lattice::Bottom,
+                    },
+                    (Lattice::Bottom, _) | (_, Lattice::Bottom) => Lattice::Bottom,
+                    _ => Lattice::Top,
+                }
+            }
+            InstTy::neg => match self.operand(inst.borrow().clone_x_val()) {
+                Lattice::Const(x) => Lattice::Const(x.wrapping_neg()),
+                other => other,
+            },
+            _ => Lattice::Bottom,
+        };
+
+        let inst_id = inst.borrow().get_inst_num();
+        let old = self.value_of(inst_id);
+        let new = old.meet(value);
+        if new != old {
+            self.values.insert(inst_id, new);
+            if temp_manager.has_inst(&inst_id) {
+                for temp_val in temp_manager.borrow_inst(&inst_id).borrow().active_uses() {
+                    self.ssa_list.push(temp_val.borrow().inst_num());
+                }
+            }
+        }
+    }
+
+    /// Makes the edges the branch can take executable.
+    fn visit_branch(&mut self, node_id: NodeIndex, inst: &Rc<RefCell<Op>>) {
+        if self.operand(inst.borrow().clone_x_val()) == Lattice::Top {
+            return;
+        }
+
+        match self.branch_target(node_id, &inst.borrow()) {
+            Some(child_id) => self.flow_list.push((node_id, child_id)),
+            None => {
+                for child_id in block_successors(self.graph, node_id) {
+                    self.flow_list.push((node_id, child_id));
+                }
+            }
+        }
+    }
+
+    /// The one successor a branch on a constant comparison goes to. A loop
+    /// that is entered on a constant keeps its exit: it can only be left by
+    /// a return, and the later passes expect every loop to have an exit.
+    fn branch_target(&self, node_id: NodeIndex, op: &Op) -> Option<NodeIndex> {
+        let cond = match self.operand(op.clone_x_val()) {
+            Lattice::Const(cond) => cond,
+            _ => return None,
+        };
+        let target_val = op.clone_y_val()?;
+        let successors = block_successors(self.graph, node_id);
+        let (taken_id, fall_id) = branch_targets(self.graph, node_id, &successors, &target_val).ok()?;
+
+        let taken = match op.inst_type() {
+            InstTy::bne => cond != 0,
+            InstTy::beq => cond == 0,
+            InstTy::blt => cond < 0,
+            InstTy::bge => cond >= 0,
+            InstTy::ble => cond <= 0,
+            _ => cond > 0,
+        };
+        let is_loop = self.graph[node_id].get_node_type() == NodeType::while_loop_header;
+        match (taken, is_loop) {
+            (true, _) => Some(taken_id),
+            (false, false) => Some(fall_id),
+            (false, true) => None,
+        }
+    }
+
+    /// Meets the operands of the edges into the phi's block that are
+    /// executable so far.
+    fn eval_phi(&self, node_id: NodeIndex, op: &Op) -> Lattice {
+        let mut value = Lattice::Top;
+        for parent_id in self.parents(node_id) {
+            if !self.executable_edges.contains(&(parent_id, node_id)) {
+                continue;
+            }
+
+            let operand = if is_phi_x_side(self.graph, self.dom_space, parent_id, node_id) {
+                op.clone_x_val()
+            } else {
+                op.clone_y_val()
+            };
+            value = value.meet(self.operand(operand));
+        }
+        value
+    }
+
+    fn parents(&self, node_id: NodeIndex) -> Vec<NodeIndex> {
+        let mut parents = Vec::new();
+        for parent_id in self.graph.neighbors_directed(node_id, Incoming) {
+            if !parents.contains(&parent_id) {
+                parents.push(parent_id);
+            }
+        }
+        parents
+    }
+
+    fn operand(&self, value: Option<Value>) -> Lattice {
+        let value = match value {
+            Some(value) => value,
+            None => return Lattice::Bottom,
+        };
+
+        match value.get_var_base() {
+            ValTy::con(con) => Lattice::Const(con),
+            ValTy::reg(reg) if reg.to_usize() == 0 => Lattice::Const(0),
+            ValTy::op(op) => {
+                // Values from outside the function, or already given up on
+                // by another pass, are not guessed at.
+                let inst_id = op.borrow().get_inst_num();
+                if self.insts.contains_key(&inst_id) {
+                    self.value_of(inst_id)
+                } else {
+                    Lattice::Bottom
+                }
+            }
+            _ => Lattice::Bottom,
+        }
+    }
+
+    fn value_of(&self, inst_id: usize) -> Lattice {
+        self.values.get(&inst_id).cloned().unwrap_or(Lattice::Top)
+    }
+}
+
+fn fold(inst_ty: &InstTy, x: i32, y: i32) -> Option<i32> {
+    match inst_ty {
+        InstTy::add => Some(x.wrapping_add(y)),
+        InstTy::sub => Some(x.wrapping_sub(y)),
+        InstTy::mul => Some(x.wrapping_mul(y)),
+        // Left for the program to trap on.
+        InstTy::div if y == 0 => None,
+        InstTy::div => Some(x.wrapping_div(y)),
+        _ => Some(match x.cmp(&y) {
+            std::cmp::Ordering::Less => -1,
+            std::cmp::Ordering::Equal => 0,
+            std::cmp::Ordering::Greater => 1,
+        }),
+    }
+}
+
+/// Turns constant values into `add R0 #c` (`sub R0 #c` below zero), the
+/// same form constant evaluation leaves. Comparisons are left to their
+/// branches.
+fn fold_values(analysis: &Analysis, temp_manager: &mut TempValManager) {
+    for (inst_id, (node_id, inst)) in analysis.insts.iter() {
+        if !analysis.executable_blocks.contains(node_id) {
+            continue;
+        }
+
+        let con = match analysis.value_of(*inst_id) {
+            Lattice::Const(con) => con,
+            _ => continue,
+        };
+
+        let inst_ty = inst.borrow().inst_type().clone();
+        match inst_ty {
+            InstTy::add | InstTy::sub | InstTy::mul | InstTy::div | InstTy::neg | InstTy::phi => {}
+            _ => continue,
+        }
+        if is_folded(&inst.borrow()) {
+            continue;
+        }
+
+        let operands = operand_ids(&inst.borrow());
+        for operand_id in operands {
+            if temp_manager.has_inst(&operand_id) {
+                temp_manager.borrow_mut_inst(&operand_id).borrow_mut().remove_use(inst_id);
+            }
+        }
+
+        let mut op = inst.borrow_mut();
+        if con < 0 {
+            op.update_inst_ty(InstTy::sub);
+            op.update_y_val(Value::new(ValTy::con(con.wrapping_neg())));
+        } else {
+            op.update_inst_ty(InstTy::add);
+            op.update_y_val(Value::new(ValTy::con(con)));
+        }
+        op.update_x_val(Value::new(ValTy::reg(RegisterAllocation::allocate_R0())));
+    }
+}
+
+fn is_folded(op: &Op) -> bool {
+    match (op.inst_type(), op.get_val_ty()) {
+        (InstTy::add, (Some(ValTy::reg(reg)), Some(ValTy::con(_))))
+        | (InstTy::sub, (Some(ValTy::reg(reg)), Some(ValTy::con(_)))) => reg.to_usize() == 0,
+        _ => false,
+    }
+}
+
+fn operand_ids(op: &Op) -> Vec<usize> {
+    let mut ids = Vec::new();
+    for value in [op.clone_x_val(), op.clone_y_val()].iter().flatten() {
+        if let ValTy::op(operand) = value.get_value() {
+            let operand_id = operand.borrow().get_inst_num();
+            if !ids.contains(&operand_id) {
+                ids.push(operand_id);
+            }
+        }
+    }
+    ids
+}
+
+/// Drops the branches that only go one way, along with their comparison
+/// once nothing else reads it, and the edges no executable path takes.
+fn prune_branches(irgm: &mut IRGraphManager, analysis: &Analysis, temp_manager: &mut TempValManager) {
+    let graph = irgm.graph_manager().get_mut_ref_graph();
+
+    for node_id in analysis.blocks.iter() {
+        if !analysis.executable_blocks.contains(node_id) {
+            continue;
+        }
+
+        for inst in analysis.block_insts(*node_id) {
+            let is_decided = is_conditional_branch(inst.borrow().inst_type())
+                && analysis.branch_target(*node_id, &inst.borrow()).is_some();
+            if is_decided {
+                let inst_id = inst.borrow().get_inst_num();
+                let operands = operand_ids(&inst.borrow());
+                temp_manager.borrow_mut_inst(&inst_id).borrow_mut().deactivate_instruction();
+                temp_manager.clean_instruction_uses(&inst_id);
+
+                for operand_id in operands {
+                    if !temp_manager.has_inst(&operand_id) {
+                        continue;
+                    }
+                    let operand = Rc::clone(temp_manager.borrow_inst(&operand_id));
+                    let is_unused_cmp = operand.borrow().inst_val().borrow().inst_type().clone() == InstTy::cmp
+                        && operand.borrow().active_uses().is_empty();
+                    if is_unused_cmp {
+                        operand.borrow_mut().deactivate_instruction();
+                        temp_manager.clean_instruction_uses(&operand_id);
+                    }
+                }
+            }
+        }
+
+        let mut dead_edges = graph
+            .edges_directed(*node_id, Outgoing)
+            .filter(|edge| edge.weight() == "black")
+            .filter(|edge| !analysis.executable_edges.contains(&(*node_id, edge.target())))
+            .map(|edge| edge.id())
+            .collect::<Vec<_>>();
+        // Removing an edge moves the last one into its place, so the later
+        // edges go first.
+        dead_edges.sort_by_key(|edge_id| std::cmp::Reverse(edge_id.index()));
+        for edge_id in dead_edges {
+            graph.remove_edge(edge_id);
+        }
+    }
+}
+
+/// Phis of a block left with a single way in become the operand of that
+/// edge.
+fn remove_phis(analysis: &Analysis, temp_manager: &mut TempValManager) {
+    for node_id in analysis.blocks.iter() {
+        if !analysis.executable_blocks.contains(node_id) {
+            continue;
+        }
+
+        let parents = analysis
+            .parents(*node_id)
+            .into_iter()
+            .filter(|parent_id| analysis.executable_edges.contains(&(*parent_id, *node_id)))
+            .collect::<Vec<NodeIndex>>();
+        if parents.len() != 1 {
+            continue;
+        }
+        let is_x_side = is_phi_x_side(analysis.graph, analysis.dom_space, parents[0], *node_id);
+
+        for inst in analysis.block_insts(*node_id) {
+            if inst.borrow().inst_type().clone() != InstTy::phi {
+                continue;
+            }
+
+            let inst_id = inst.borrow().get_inst_num();
+            let operand = if is_x_side { inst.borrow().clone_x_val() } else { inst.borrow().clone_y_val() };
+            let operand = match operand {
+                Some(operand) => Value::new(operand.get_var_base()),
+                None => continue,
+            };
+
+            let users = temp_manager.borrow_inst(&inst_id).borrow().active_uses();
+            for user in users {
+                let user_id = user.borrow().inst_num();
+                user.borrow().inst_val().borrow_mut().op_cleanup(inst_id, operand.clone());
+
+                if let ValTy::op(operand_op) = operand.get_value() {
+                    let operand_id = operand_op.borrow().get_inst_num();
+                    if temp_manager.has_inst(&operand_id) {
+                        let user_temp = Rc::clone(temp_manager.borrow_inst(&user_id));
+                        temp_manager.borrow_mut_inst(&operand_id).borrow_mut().add_use(user_temp);
+                    }
+                }
+            }
+
+            temp_manager.borrow_mut_inst(&inst_id).borrow_mut().deactivate_instruction();
+            temp_manager.clean_instruction_uses(&inst_id);
+        }
+    }
+}
+
+/// Marks the blocks no executable path reaches invalid, for the cleaner to
+/// take out.
+fn remove_blocks(irgm: &mut IRGraphManager, analysis: &Analysis, temp_manager: &mut TempValManager) {
+    let graph = irgm.graph_manager().get_mut_ref_graph();
+
+    for node_id in analysis.blocks.iter() {
+        if analysis.executable_blocks.contains(node_id) {
+            continue;
+        }
+
+        for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+            let inst_id = inst.borrow().get_inst_num();
+            if temp_manager.has_inst(&inst_id) {
+                temp_manager.borrow_mut_inst(&inst_id).borrow_mut().deactivate_instruction();
+            } else {
+                inst.borrow_mut().deactivate();
+            }
+        }
+        graph.node_weight_mut(*node_id).unwrap().mark_node_invalid();
+    }
+}
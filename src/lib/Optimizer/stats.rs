@@ -0,0 +1,237 @@
+//! What each pass did to each function, for tracking the compiler across
+//! commits. The passes are not instrumented themselves: the instructions of
+//! a function are compared before and after the pass, keyed by instruction
+//! number along with the block holding them, and the graph is measured
+//! before and after the cleaner.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use lib::IR::ir::{InstTy, Op, ValTy};
+use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::ir_text::inst_text;
+
+use petgraph::prelude::NodeIndex;
+
+/// Counts for one pass over one function, summed over every time it ran.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PassStats {
+    pub pass: &'static str,
+    pub func_name: String,
+    pub runs: usize,
+    // Instructions turned into a constant.
+    pub folded: usize,
+    // Instructions left active whose operands now name other values, such
+    // as the users of a common subexpression.
+    pub replaced: usize,
+    // Instructions now in another block, such as the invariants licm hoists.
+    pub moved: usize,
+    pub deactivated: usize,
+    // Instructions the cleaner took out of the graph.
+    pub removed: usize,
+    pub blocks_deleted: usize,
+    // Only register allocation spills.
+    pub spill_rounds: usize,
+    pub time: Duration,
+}
+
+impl PassStats {
+    pub fn new(pass: &'static str, func_name: &str) -> Self {
+        PassStats {
+            pass,
+            func_name: func_name.to_owned(),
+            runs: 1,
+            ..PassStats::default()
+        }
+    }
+
+    /// Adds the counts of another run of the same pass over the same function.
+    pub fn merge(&mut self, other: &PassStats) {
+        self.runs += other.runs;
+        self.folded += other.folded;
+        self.replaced += other.replaced;
+        self.moved += other.moved;
+        self.deactivated += other.deactivated;
+        self.removed += other.removed;
+        self.blocks_deleted += other.blocks_deleted;
+        self.spill_rounds += other.spill_rounds;
+        self.time += other.time;
+    }
+
+    /// Counts what changed between the function before the pass and right
+    /// after it.
+    pub fn count_changes(&mut self, before: &Snapshot, after: &Snapshot) {
+        for (inst_num, old) in before.insts.iter() {
+            let new = match after.insts.get(inst_num) {
+                Some(new) => new,
+                None => continue,
+            };
+
+            if old.active && !new.active {
+                self.deactivated += 1;
+            } else if new.active && old.text != new.text {
+                if new.constant && !old.constant {
+                    self.folded += 1;
+                } else {
+                    self.replaced += 1;
+                }
+            }
+
+            if new.active && old.block != new.block {
+                self.moved += 1;
+            }
+        }
+    }
+
+    /// Counts what the cleaner took out, given the `graph_size` before and
+    /// after it ran.
+    pub fn count_cleaned(&mut self, before: (usize, usize), after: (usize, usize)) {
+        self.blocks_deleted += before.0.saturating_sub(after.0);
+        self.removed += before.1.saturating_sub(after.1);
+    }
+}
+
+/// Blocks and instructions in the whole graph. Blocks a pass cuts off from
+/// the root stay until some later cleaner deletes them, possibly while
+/// cleaning another function, so they are not looked for by function.
+pub fn graph_size(irgm: &IRGraphManager) -> (usize, usize) {
+    let graph = irgm.graph_manager_ref().get_ref_graph();
+    let insts = graph
+        .node_indices()
+        .map(|node_id| graph[node_id].get_data_ref().get_inst_list_ref().len())
+        .sum();
+    (graph.node_count(), insts)
+}
+
+struct InstState {
+    // The node id of the block holding the instruction.
+    block: usize,
+    text: String,
+    active: bool,
+    constant: bool,
+}
+
+/// The instructions of some blocks at one point of a pass.
+pub struct Snapshot {
+    insts: HashMap<usize, InstState>,
+}
+
+impl Snapshot {
+    pub fn take(irgm: &IRGraphManager, blocks: &[NodeIndex]) -> Self {
+        let graph = irgm.graph_manager_ref().get_ref_graph();
+        let mut insts = HashMap::new();
+
+        for node_id in blocks {
+            for inst in graph[*node_id].get_data_ref().get_inst_list_ref() {
+                let inst = inst.borrow();
+                insts.insert(
+                    inst.get_inst_num(),
+                    InstState {
+                        block: graph[*node_id].get_node_id(),
+                        text: inst_text(&inst),
+                        active: inst.is_active(),
+                        constant: is_constant(&inst),
+                    },
+                );
+            }
+        }
+
+        Snapshot { insts }
+    }
+}
+
+// Constant evaluation leaves a folded instruction as `add R0 #c` (or `sub`
+// for negative values).
+fn is_constant(inst: &Op) -> bool {
+    match (inst.inst_type(), inst.get_val_ty()) {
+        (InstTy::add, (Some(ValTy::reg(reg)), Some(ValTy::con(_))))
+        | (InstTy::sub, (Some(ValTy::reg(reg)), Some(ValTy::con(_)))) => reg.to_usize() == 0,
+        _ => false,
+    }
+}
+
+const COLUMNS: [&str; 11] = [
+    "pass",
+    "function",
+    "runs",
+    "folded",
+    "replaced",
+    "moved",
+    "deactivated",
+    "removed",
+    "blocks",
+    "spills",
+    "time_us",
+];
+
+fn row(stats: &PassStats) -> [String; 11] {
+    [
+        stats.pass.to_string(),
+        stats.func_name.clone(),
+        stats.runs.to_string(),
+        stats.folded.to_string(),
+        stats.replaced.to_string(),
+        stats.moved.to_string(),
+        stats.deactivated.to_string(),
+        stats.removed.to_string(),
+        stats.blocks_deleted.to_string(),
+        stats.spill_rounds.to_string(),
+        stats.time.as_micros().to_string(),
+    ]
+}
+
+/// One line per pass and function, names aligned left and counts right.
+pub fn stats_table(stats: &[PassStats]) -> String {
+    let rows = stats.iter().map(row).collect::<Vec<[String; 11]>>();
+    let mut widths = COLUMNS.map(|column| column.len());
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut text = String::new();
+    let header = COLUMNS.map(String::from);
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (column, (cell, width)) in row.iter().zip(widths.iter()).enumerate() {
+            match column {
+                0 | 1 => write!(line, "{:<width$}  ", cell, width = width),
+                _ => write!(line, "{:>width$}  ", cell, width = width),
+            }
+            .unwrap();
+        }
+        writeln!(text, "{}", line.trim_end()).unwrap();
+    }
+    text
+}
+
+/// A JSON array with one object per pass and function.
+pub fn stats_json(stats: &[PassStats]) -> String {
+    let objects = stats
+        .iter()
+        .map(|stats| {
+            let fields = COLUMNS
+                .iter()
+                .zip(row(stats).iter())
+                .enumerate()
+                .map(|(column, (name, value))| match column {
+                    0 | 1 => format!("\"{}\": \"{}\"", name, json_escape(value)),
+                    _ => format!("\"{}\": {}", name, value),
+                })
+                .collect::<Vec<String>>();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect::<Vec<String>>();
+
+    if objects.is_empty() {
+        String::from("[]\n")
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
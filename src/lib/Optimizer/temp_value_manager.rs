@@ -146,12 +146,33 @@ impl TempValManager {
         self.temp_vec.push(Rc::clone(&ref_temp));
     }
 
+    /// Tracks instructions made after the values were pulled. They may use
+    /// each other in any order, as a new loop phi uses its own update.
+    pub fn add_new_insts(&mut self, insts: &[Rc<RefCell<Op>>]) {
+        let mut revisit_inst = Vec::new();
+        for inst in insts {
+            self.add_inst(inst, &mut revisit_inst);
+        }
+
+        for (inst_id, temp_val) in revisit_inst.iter() {
+            self.op_hash
+                .get_mut(inst_id)
+                .expect("New instruction uses a value that was never added.")
+                .borrow_mut()
+                .add_use(Rc::clone(temp_val));
+        }
+    }
+
     pub fn borrow_mut_inst(&mut self, inst_id: &usize) -> &mut Rc<RefCell<TempVal>> {
         self.op_hash
             .get_mut(inst_id)
             .expect("Attempted to mutably borrow non-existent instruction.")
     }
 
+    pub fn has_inst(&self, inst_id: &usize) -> bool {
+        self.op_hash.contains_key(inst_id)
+    }
+
     pub fn borrow_inst(&self, inst_id: &usize) -> &Rc<RefCell<TempVal>> {
         self.op_hash.get(inst_id).expect(
             &format!(
@@ -333,8 +354,13 @@ impl TempVal {
     }
 
     pub fn add_use(&mut self, temp_val_clone: Rc<RefCell<TempVal>>) {
+        // A loop phi can be its own operand, then the use is the value
+        // already borrowed here. That use alone does not keep it alive.
+        let temp_id = match temp_val_clone.try_borrow() {
+            Ok(temp_val) => temp_val.inst_num(),
+            Err(_) => return,
+        };
         self.op_val.borrow_mut().activate();
-        let temp_id = temp_val_clone.borrow().inst_num();
         self.op_val.borrow_mut().activate();
         self.used.insert(temp_id, temp_val_clone);
     }
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST;
 
 use super::Graph;
@@ -11,26 +13,23 @@ pub struct Array {
     node_type: TokenType,
     arrayDepthVec: Vec<AST::number::Number>,
     identList: Vec<AST::ident::Ident>,
-    debugLine: String,
+    span: Span,
 }
 
 impl Array {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut varList: Vec<AST::ident::Ident> = vec![];
         let mut numList: Vec<AST::number::Number> = vec![];
-        let mut tokenType = TokenType::None;
 
-        match tc.get_next_token().expect("Array Error").get_type() {
-            TokenType::Array => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::Array) => {
                 // proper action, all is well.
-                tokenType = TokenType::Array;
+                tc.get_next_token();
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Array declaration, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'array'"));
             }
         }
 
@@ -38,92 +37,52 @@ impl Array {
             Some(TokenType::LeftBracket) => {
                 //all is well, proceed through
             }
-            None => {
-                // Compiler Error :
-                panic!("Expected Array '[' Token, found EOF.");
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected '[' Token in array declaration, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'[' starting array size"));
             }
         }
 
-        while let Some(next_token) = tc.peek_next_token_type() {
-            match next_token {
-                TokenType::LeftBracket => {
-                    tc.get_next_token();
+        while let Some(TokenType::LeftBracket) = tc.peek_next_token_type() {
+            tc.get_next_token();
 
-                    match tc.peek_next_token_type() {
-                        Some(TokenType::Number) => {
-                            numList.push(AST::number::Number::new(tc));
-                        }
-                        None => {
-                            // Compiler Error :
-                            panic!("Expected Array index Number, found EOF.");
-                        }
-                        err => {
-                            // Compiler Error :
-                            panic!("Expected Number Token in array index, found unexpected Token: {:?}", err);
-                        }
-                    }
-
-                    match tc.peek_next_token_type() {
-                        Some(TokenType::RightBracket) => {
-                            tc.get_next_token();
-                        }
-                        None => {
-                            // Compiler Error :
-                            panic!("Expected Array ']', found EOF.");
-                        }
-                        err => {
-                            // Compiler Error :
-                            panic!("Expected ']' Token in array declaration, found unexpected Token: {:?}", err);
-                        }
-                    }
-                }
-                TokenType::Ident => {
-                    // first ident case found, break from loop.
-                    break;
+            numList.push(AST::number::Number::new(tc)?);
+
+            match tc.peek_next_token_type() {
+                Some(TokenType::RightBracket) => {
+                    tc.get_next_token();
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!("Expected Array T: {:?}", err);
+                    return Err(ParseError::expected(tc, "']' closing array size"));
                 }
             }
         }
 
-        while let Some(next_token) = tc.peek_next_token_type() {
-            match next_token {
-                TokenType::Ident => {
-                    varList.push(AST::ident::Ident::new(tc));
-                }
-                TokenType::Comma => {
+        varList.push(AST::ident::Ident::new(tc)?);
+        loop {
+            match tc.peek_next_token_type() {
+                Some(TokenType::Comma) => {
                     tc.get_next_token();
-                    continue;
+                    varList.push(AST::ident::Ident::new(tc)?);
                 }
-                TokenType::SemiTermination => {
+                Some(TokenType::SemiTermination) => {
                     tc.get_next_token();
                     break;
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!(
-                        "Expected Ident Token in array declaration, found unexpected Token: {:?}",
-                        err
-                    );
+                    return Err(ParseError::expected(tc, "',' or ';' in array declaration"));
                 }
             }
         }
 
-        Array {
-            node_type: tokenType,
+        Ok(Array {
+            node_type: TokenType::Array,
             arrayDepthVec: numList,
             identList: varList,
-            debugLine: String::from("test"),
-        }
+            span: tc.span_from(&start),
+        })
     }
 
     pub fn get_value(&self) -> (Vec<AST::number::Number>, Vec<AST::ident::Ident>) {
@@ -134,8 +93,8 @@ impl Array {
         self.node_type.clone()
     }
 
-    pub fn get_debug(self) -> String {
-        self.debugLine.clone()
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn to_ir(self, irgm: &mut IRGraphManager, is_global: bool, func_name: Option<String>) {
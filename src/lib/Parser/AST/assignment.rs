@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::designator::Designator;
 use Parser::AST::expression::Expression;
 
@@ -13,50 +15,45 @@ pub struct Assignment {
     node_type: TokenType,
     designator: Designator,
     expression: Expression,
+    span: Span,
 }
 
 impl Assignment {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let designator;
         let expression;
 
-        match tc.get_next_token().expect("Assignment Error").get_type() {
-            TokenType::Assignment => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::Assignment) => {
                 //expected assignment token found.
+                tc.get_next_token();
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected to find Assignment token, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'let'"));
             }
         }
 
         match tc.peek_next_token_type() {
             Some(TokenType::Ident) => {
-                designator = Designator::new(tc);
+                designator = Designator::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Designator for assignment variable, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "variable to assign to"));
             }
         }
 
-        match tc.get_next_token().expect("Assignment Op Error").get_type() {
-            TokenType::AssignmentOp => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::AssignmentOp) => {
                 //expected assignment operator found, proceed to expression.
-                expression = Expression::new(tc);
+                tc.get_next_token();
+                expression = Expression::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Assignment Operator '<-', found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'<-'"));
             }
         }
 
@@ -73,24 +70,22 @@ impl Assignment {
             | Some(TokenType::RightPara) => {
                 //';' not required, return without consuming token.
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected end of assignment, found EOF.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected end of assignment, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "';' or end of block"));
             }
         }
 
-        Assignment {
+        Ok(Assignment {
             node_type: TokenType::Assignment,
             designator,
             expression,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Designator, Expression) {
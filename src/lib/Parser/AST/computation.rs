@@ -1,9 +1,12 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 
 use Parser::AST::func_body::FuncBody;
 use Parser::AST::func_decl::FuncDecl;
 use Parser::AST::var_decl::VarDecl;
+use Parser::{sync_func_decl, sync_var_decl};
 
 use super::Graph;
 use super::{IRGraphManager, InstTy, Node, NodeData, NodeId, NodeType, Op, ValTy, Value};
@@ -17,10 +20,12 @@ pub struct Comp {
     varDecl: Vec<VarDecl>,
     funcDecl: Vec<FuncDecl>,
     funcBody: FuncBody,
+    span: Span,
 }
 
 impl Comp {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut varDecl = vec![];
         let mut funcDecl = vec![];
         let funcBody;
@@ -36,18 +41,16 @@ impl Comp {
             }
         }
 
-        match tc.get_next_token().expect("Computation error").get_type() {
-            TokenType::Computation => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::Computation) => {
                 //program does in fact start with main.
                 //dont really need to do anything with that
+                tc.get_next_token();
             }
-            err => {
+            _ => {
                 //How in the world did you not get a main token??
                 // Compiler Error :
-                panic!(
-                    "Expecting file to start with keyword 'main', found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "keyword 'main' at start of file"));
             }
         }
 
@@ -56,7 +59,13 @@ impl Comp {
             match next_token {
                 TokenType::Var | TokenType::Array => {
                     //found variable declaration
-                    varDecl.push(VarDecl::new(tc));
+                    match VarDecl::new(tc) {
+                        Ok(decl) => varDecl.push(decl),
+                        Err(error) => {
+                            tc.report_error(error);
+                            sync_var_decl(tc);
+                        }
+                    }
                 }
                 TokenType::FuncDecl => {
                     //no variable declaration found, but Function delcaration found
@@ -68,9 +77,12 @@ impl Comp {
                     //drop through
                     break;
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!("Expected to find VarDecl, FuncDecl, or Main body start, but found unexpected Token: {:?}", err);
+                    let error = ParseError::expected(tc, "declaration or '{' starting main body");
+                    tc.report_error(error);
+                    tc.get_next_token();
+                    sync_var_decl(tc);
                 }
             }
         }
@@ -79,18 +91,24 @@ impl Comp {
             match next_token {
                 TokenType::FuncDecl => {
                     //found funcDecl
-                    funcDecl.push(FuncDecl::new(tc));
+                    match FuncDecl::new(tc) {
+                        Ok(decl) => funcDecl.push(decl),
+                        Err(error) => {
+                            tc.report_error(error);
+                            sync_func_decl(tc);
+                        }
+                    }
                 }
                 TokenType::LeftBrace => {
                     //no funcDecl found
                     break;
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!(
-                        "Expected FuncDecl or start of Main body, but found unexpected Token: {:?}",
-                        err
-                    );
+                    let error = ParseError::expected(tc, "function or '{' starting main body");
+                    tc.report_error(error);
+                    tc.get_next_token();
+                    sync_func_decl(tc);
                 }
             }
         }
@@ -100,36 +118,22 @@ impl Comp {
                 //found body start
                 tc.get_next_token();
 
-                funcBody = FuncBody::new(tc);
+                funcBody = FuncBody::new(tc)?;
 
                 //look for closing bracket
                 match tc.peek_next_token_type() {
                     Some(TokenType::RightBrace) => {
                         tc.get_next_token();
                     }
-                    None => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected '}}' Token at end of main body, found EOF.");
-                    }
-                    err => {
-                        // Compiler Error :
-                        panic!(
-                            "Expected '}}' Token at end of main body, found unexpected Token: {:?}",
-                            err
-                        );
+                        return Err(ParseError::expected(tc, "'}' at end of main body"));
                     }
                 }
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected start to main body, found EOF.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected '{{' Token to indicate body start, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'{' starting main body"));
             }
         }
 
@@ -138,22 +142,23 @@ impl Comp {
                 //found end of main computation
                 tc.get_next_token(); //consume '.', return
             }
-            None => {
-                // Compiler Error :
-                panic!("Expected end of main body Token '.', found EOF.");
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected end of// fall through, as I cant access var_counter main body Token '.', found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'.' at end of program"));
             }
         }
 
-        Comp {
+        Ok(Comp {
             node_type: TokenType::Computation,
             varDecl,
             funcDecl,
             funcBody,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Vec<VarDecl>, Vec<FuncDecl>, FuncBody) {
@@ -178,6 +183,19 @@ impl Comp {
             var.to_ir(&mut ir_graph_manager, true, None);
         }
 
+        // Every function is declared before any body is lowered, so calls
+        // don't depend on the order functions appear in. Functions pick up
+        // the globals of the ones they call, so go again until that settles.
+        loop {
+            let side_effects = ir_graph_manager.function_manager().side_effect_count();
+            for func in &self.funcDecl {
+                func.declare(&mut ir_graph_manager);
+            }
+            if ir_graph_manager.function_manager().side_effect_count() == side_effects {
+                break;
+            }
+        }
+
         for func in self.funcDecl {
             func.to_ir(&mut ir_graph_manager);
         }
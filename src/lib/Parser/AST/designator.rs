@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::expression::Expression;
 use Parser::AST::ident::Ident;
 
@@ -10,73 +12,52 @@ pub struct Designator {
     node_type: TokenType,
     ident: Ident,
     expressions: Vec<Expression>,
+    span: Span,
 }
 
 impl Designator {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut expList: Vec<Expression> = vec![];
-        let mut tokenType = TokenType::None;
 
-        match tc.peek_next_token_type() {
-            Some(TokenType::Ident) => {
-                let current_ident = Ident::new(tc);
-                tokenType = TokenType::Designator;
+        let current_ident = match tc.peek_next_token_type() {
+            Some(TokenType::Ident) => Ident::new(tc)?,
+            _ => {
+                // Compiler Error :
+                return Err(ParseError::expected(tc, "variable name"));
+            }
+        };
 
-                while let Some(next_token) = tc.peek_next_token_type() {
-                    match next_token {
-                        TokenType::LeftBracket => {
-                            // consume left brace
-                            tc.get_next_token();
+        while let Some(TokenType::LeftBracket) = tc.peek_next_token_type() {
+            // consume left brace
+            tc.get_next_token();
 
-                            expList.push(Expression::new(tc));
+            expList.push(Expression::new(tc)?);
 
-                            // consume next token if right brace
-                            match tc.peek_next_token_type() {
-                                Some(TokenType::RightBracket) => {
-                                    // consume right brace
-                                    tc.get_next_token();
-                                }
-                                None => {
-                                    // Compiler Error :
-                                    panic!("Unexpected EOF, expected ']' token for designator.");
-                                }
-                                err => {
-                                    // Compiler Error :
-                                    panic!("Unexpected Token: {:?}, expected ']' token for designator.", err);
-                                }
-                            }
-                        }
-                        _ => {
-                            // ident already collected, bail. no need for error handling here.
-                            return Designator {
-                                node_type: tokenType,
-                                ident: current_ident,
-                                expressions: expList,
-                            };
-                        }
-                    }
+            // consume next token if right brace
+            match tc.peek_next_token_type() {
+                Some(TokenType::RightBracket) => {
+                    // consume right brace
+                    tc.get_next_token();
+                }
+                _ => {
+                    // Compiler Error :
+                    return Err(ParseError::expected(tc, "']' closing array index"));
                 }
-            }
-
-            None => {
-                // Compiler Error :
-                panic!("Unexpected EOF, expected Ident token for designator.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected Ident Token in designator, found unexpected Token: {:?}",
-                    err
-                );
             }
         }
 
-        // Compiler Error : Should not reach this stage.
-        Designator {
-            node_type: tokenType,
-            ident: Ident::new(tc),
-            expressions: vec![],
-        }
+        // ident already collected, no need for error handling here.
+        Ok(Designator {
+            node_type: TokenType::Designator,
+            ident: current_ident,
+            expressions: expList,
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Ident, Vec<Expression>) {
@@ -1,6 +1,8 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::Token;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::term::Term;
 
 use super::Graph;
@@ -18,13 +20,15 @@ pub enum ExpList {
 pub struct Expression {
     node_type: TokenType,
     exp_list: Vec<ExpList>,
+    span: Span,
 }
 
 impl Expression {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut exp_list = Vec::new();
 
-        exp_list.push(ExpList::term(Term::new(tc)));
+        exp_list.push(ExpList::term(Term::new(tc)?));
 
         loop {
             //handle MathOp possibility
@@ -33,23 +37,24 @@ impl Expression {
                     //MathOp found, call another term.
 
                     exp_list.push(ExpList::math_op(tc.get_next_token().unwrap()));
-                    exp_list.push(ExpList::term(Term::new(tc)));
-                }
-                None => {
-                    // Compiler Error :
-                    panic!("Unexpected EOF in expression.");
+                    exp_list.push(ExpList::term(Term::new(tc)?));
                 }
                 _ => {
                     //If there is no MathOp, return. Dont do any other debugging or logic here.
-                    return Expression {
+                    return Ok(Expression {
                         node_type: TokenType::Expression,
                         exp_list,
-                    };
+                        span: tc.span_from(&start),
+                    });
                 }
             }
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_value(&self) -> Vec<ExpList> {
         return self.exp_list.clone();
     }
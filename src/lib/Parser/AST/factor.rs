@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::designator::Designator;
 use Parser::AST::expression::Expression;
 use Parser::AST::func_call::FuncCall;
@@ -22,27 +24,29 @@ pub enum FactorType {
 pub struct Factor {
     node_type: TokenType,
     factor: Option<FactorType>,
+    span: Span,
 }
 
 impl Factor {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut factor = None;
         let node_type = TokenType::None;
 
         match tc.peek_next_token_type() {
             Some(TokenType::Ident) => {
-                factor = Some(FactorType::desig(Designator::new(tc)));
+                factor = Some(FactorType::desig(Designator::new(tc)?));
             }
             Some(TokenType::Number) => {
-                factor = Some(FactorType::num(Number::new(tc)));
+                factor = Some(FactorType::num(Number::new(tc)?));
             }
             Some(TokenType::FuncCall) => {
-                factor = Some(FactorType::func_call(FuncCall::new(tc)));
+                factor = Some(FactorType::func_call(FuncCall::new(tc)?));
             }
             Some(TokenType::LeftPara) => {
                 //consume token, call self
                 tc.get_next_token();
-                factor = Some(FactorType::expr(Expression::new(tc)));
+                factor = Some(FactorType::expr(Expression::new(tc)?));
 
                 //handle closing brace in initial call of brace so all braces ar self contained.
                 match tc.peek_next_token_type() {
@@ -50,27 +54,27 @@ impl Factor {
                         tc.get_next_token();
                         //fall through
                     }
-                    None => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected Closing ')' Token for expression, found EOF.");
-                    }
-                    err => {
-                        // Compiler Error :
-                        panic!("Expected Closing ')' Token for expression, found unexpected Token: {:?}", err);
+                        return Err(ParseError::expected(tc, "')' closing expression"));
                     }
                 }
             }
-            None => {
-                // Compiler Error :
-                panic!("Expected Designator, Number, Function call, or '(' Token, found EOF.");
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected Designator, Number, Function Call, or '(' Token, found unexpected {:?}", err);
+                return Err(ParseError::expected(tc, "variable, number, function call or '('"));
             }
         }
 
-        Factor { node_type, factor }
+        Ok(Factor {
+            node_type,
+            factor,
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> FactorType {
@@ -1,17 +1,20 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::assignment::Assignment;
 use Parser::AST::func_call::FuncCall;
 use Parser::AST::if_stmt::IfStmt;
 use Parser::AST::return_stmt::ReturnStmt;
 use Parser::AST::while_stmt::WhileStmt;
+use Parser::sync_statement;
 
 use super::Graph;
 use super::{IRGraphManager, InstTy, Node, NodeData, NodeId, Op, ValTy, Value};
 use lib::Graph::graph_manager::GraphManager;
 
 #[derive(Debug, Clone)]
-enum Stmt {
+pub enum Stmt {
     assignment(Assignment),
     if_stmt(IfStmt),
     while_stmt(WhileStmt),
@@ -23,79 +26,96 @@ enum Stmt {
 pub struct FuncBody {
     node_type: TokenType,
     stmt_vec: Vec<Stmt>,
+    span: Span,
 }
 
 impl FuncBody {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut stmt_vec = Vec::new();
 
         while let Some(next_token) = tc.peek_next_token_type() {
-            match next_token {
-                TokenType::Assignment => {
-                    stmt_vec.push(Stmt::assignment(Assignment::new(tc)));
-                }
-                TokenType::IfStatement => {
-                    stmt_vec.push(Stmt::if_stmt(IfStmt::new(tc)));
-                }
-                TokenType::WhileStatement => {
-                    stmt_vec.push(Stmt::while_stmt(WhileStmt::new(tc)));
-                }
-                TokenType::FuncCall => {
-                    stmt_vec.push(Stmt::funcCall(FuncCall::new(tc)));
-
+            let stmt = match next_token {
+                TokenType::Assignment => Assignment::new(tc).map(Stmt::assignment),
+                TokenType::IfStatement => IfStmt::new(tc).map(Stmt::if_stmt),
+                TokenType::WhileStatement => WhileStmt::new(tc).map(Stmt::while_stmt),
+                TokenType::FuncCall => FuncCall::new(tc).and_then(|func_call| {
                     match tc.peek_next_token_type() {
                         Some(TokenType::SemiTermination) => {
                             //consume then resume cycle
                             tc.get_next_token();
-                            continue;
                         }
                         Some(TokenType::RightBrace)
                         | Some(TokenType::FiStatement)
                         | Some(TokenType::OdStatement)
                         | Some(TokenType::ElseStatement) => {
                             //fall through
-                            continue;
                         }
-                        None => {
+                        _ => {
                             // Compiler Error :
-                            panic!("Expected some form of termination after function call in function body.");
-                        }
-                        err => {
-                            // Compiler Error :
-                            panic!("Expected termination sequence after FuncCall, found unexpected Token: {:?}");
+                            return Err(ParseError::expected(tc, "';' or end of block"));
                         }
                     }
-                }
-                TokenType::ReturnStatement => {
-                    stmt_vec.push(Stmt::return_stmt(ReturnStmt::new(tc)));
-                }
+                    Ok(Stmt::funcCall(func_call))
+                }),
+                TokenType::ReturnStatement => ReturnStmt::new(tc).map(Stmt::return_stmt),
 
                 //end of function body sequences
                 TokenType::RightBrace
                 | TokenType::FiStatement
                 | TokenType::OdStatement
-                | TokenType::ElseStatement => {
+                | TokenType::ElseStatement
+                | TokenType::ComputationEnd => {
                     //consume token? or just return?
                     break;
                 }
 
                 // Compiler Error :
-                err => {
-                    panic!("Unable to parse {:?} within function body.", err);
+                _ => {
+                    let error = ParseError::expected(tc, "statement");
+                    tc.get_next_token();
+                    Err(error)
+                }
+            };
+
+            match stmt {
+                Ok(stmt) => stmt_vec.push(stmt),
+                Err(error) => {
+                    // Report it and carry on with the next statement.
+                    tc.report_error(error);
+                    sync_statement(tc, next_token);
                 }
             }
         }
 
-        FuncBody {
+        Ok(FuncBody {
             node_type: TokenType::FuncBody,
             stmt_vec,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> Vec<Stmt> {
         return self.stmt_vec.to_vec();
     }
 
+    /// Whether a return statement shows up anywhere in the body.
+    pub fn has_return(&self) -> bool {
+        self.stmt_vec.iter().any(|stmt| match stmt {
+            Stmt::return_stmt(_) => true,
+            Stmt::if_stmt(if_stmt) => {
+                let (_, if_body, else_body) = if_stmt.get_value();
+                if_body.has_return() || else_body.is_some_and(|body| body.has_return())
+            }
+            Stmt::while_stmt(while_stmt) => while_stmt.get_value().1.has_return(),
+            _ => false,
+        })
+    }
+
     pub fn get_type(&self) -> TokenType {
         self.node_type.clone()
     }
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::expression::Expression;
 use Parser::AST::ident::Ident;
 
@@ -7,99 +9,81 @@ use super::Graph;
 use super::{IRGraphManager, InstTy, Node, NodeData, NodeId, Op, ValTy, Value};
 use super::{Rc, RefCell};
 use lib::Parser::AST::factor::FactorType::expr;
-use lib::IR::ret_register::RetRegister;
 
 #[derive(Debug, Clone)]
 pub struct FuncCall {
     node_type: TokenType,
     funcName: Ident,
     variables: Vec<Expression>,
+    span: Span,
 }
 
 impl FuncCall {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut variables = vec![];
         let funcName;
 
-        match tc.get_next_token().expect("Func Call Error").get_type() {
-            TokenType::FuncCall => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::FuncCall) => {
                 //this is as was expected, call function ident;
+                tc.get_next_token();
+            }
+            _ => {
+                // Compiler Error :
+                return Err(ParseError::expected(tc, "'call'"));
+            }
+        }
+
+        //check for function identity.
+        match tc.peek_next_token_type() {
+            Some(TokenType::Ident) => {
+                funcName = Ident::new(tc)?;
+            }
+            _ => {
+                // Compiler Error :
+                return Err(ParseError::expected(tc, "function name"));
+            }
+        }
 
-                //check for function identity.
+        if let Some(TokenType::LeftPara) = tc.peek_next_token_type() {
+            //function parameter start
+            tc.get_next_token();
+
+            loop {
                 match tc.peek_next_token_type() {
-                    Some(TokenType::Ident) => {
-                        funcName = Ident::new(tc); //this will grab function identity AND parameters.
-
-                        match tc.peek_next_token_type() {
-                            Some(TokenType::LeftPara) => {
-                                //function parameter start
-                                tc.get_next_token();
-
-                                while let Some(next_token) = tc.peek_next_token_type() {
-                                    match next_token {
-                                        TokenType::RightPara => {
-                                            tc.get_next_token();
-                                            break;
-                                        }
-                                        TokenType::Comma => {
-                                            //consume token and get next expr
-                                            tc.get_next_token();
-                                            variables.push(Expression::new(tc));
-                                        }
-                                        exprs => {
-                                            //get next expression
-                                            variables.push(Expression::new(tc));
-                                        }
-                                        TokenType::SemiTermination => {
-                                            panic!(
-                                                "Expected variables, or right brace, found ';'."
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                            None => {
-                                // Compiler Error :
-                                panic!("Expected anything after func_call, found EOF.");
-                            }
-                            ret => {
-                                //it was literally any other case, so just return and handle elsewhere
-                            }
-                        }
-                        // TODO :
-                        //here we add to some table with function declaration and function call.
-                        //depending on the table, we could declare a function after it is used similar to rust.
-                        //this would require meta-data and unwinding possible errors in functions
-                        //not existing.
+                    Some(TokenType::RightPara) if variables.is_empty() => {}
+                    _ => variables.push(Expression::new(tc)?),
+                }
+
+                match tc.peek_next_token_type() {
+                    Some(TokenType::RightPara) => {
+                        tc.get_next_token();
+                        break;
                     }
-                    None => {
-                        // Compiler Error :
-                        panic!("Expected Function Identity, found end of file.");
+                    Some(TokenType::Comma) => {
+                        //consume token and get next expr
+                        tc.get_next_token();
                     }
-                    err => {
+                    _ => {
                         // Compiler Error :
-                        panic!(
-                            "Expected Function Identity, found unexpected Token: {:?}",
-                            err
-                        );
+                        return Err(ParseError::expected(tc, "',' or ')' in argument list"));
                     }
                 }
             }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected Functional Call Token, found unexpected Token: {:?}",
-                    err
-                );
-            }
         }
-
-        //can probably just return after this
-        FuncCall {
+        // Callees are resolved in Comp::to_ir once every function has been
+        // declared, so calls can come before the function they reach.
+        Ok(FuncCall {
             node_type: TokenType::FuncCall,
             funcName,
             variables,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Ident, Vec<Expression>) {
@@ -165,11 +149,13 @@ impl FuncCall {
                     let add_inst = irgm.build_op_x_y(global_addr_val, var_addr_val, InstTy::add);
                     let add_reg_val = irgm.graph_manager().add_instruction(add_inst);
 
+                    // Constants are copied through the variable itself, so a loop
+                    // phi created after this call can still take its place.
                     let inst;
-                    if let ValTy::con(con_val) = uniq_var_val.clone().get_var_base().clone() {
+                    if let ValTy::con(_) = uniq_var_val.clone().get_var_base().clone() {
                         let add_inst = irgm.build_op_x_y(
                             Value::new(ValTy::con(0)),
-                            Value::new(ValTy::con(con_val)),
+                            uniq_var_val.clone(),
                             InstTy::add,
                         );
                         let add_val = irgm.graph_manager().add_instruction(add_inst);
@@ -180,34 +166,38 @@ impl FuncCall {
                     irgm.graph_manager().add_instruction(inst);
                 }
 
-                // Store all called parameters affected.
-                for (count, param) in uniq_func.load_param_list().iter().enumerate() {
-                    let param_addr_val =
-                        Value::new(ValTy::adr(irgm.address_manager().get_frame_pointer()));
-
+                // Evaluate every argument before storing any, a call inside a
+                // later argument would otherwise reuse the slots already filled.
+                let mut arg_vals = Vec::new();
+                for count in 0..uniq_func.load_param_list().len() {
                     // Unlike global this will pull value from the vec<expr> contained, not pull from list.
-                    let uniq_var_val;
                     if count < self.variables.len() {
-                        uniq_var_val = self.variables[count]
-                            .to_owned()
-                            .to_ir(irgm)
-                            .expect("All called variables should have some expr.");
+                        arg_vals.push(
+                            self.variables[count]
+                                .to_owned()
+                                .to_ir(irgm)
+                                .expect("All called variables should have some expr."),
+                        );
                     } else {
-                        uniq_var_val = Value::new(ValTy::con(0));
+                        arg_vals.push(Value::new(ValTy::con(0)));
                     }
+                }
 
-                    let var_addr_val = Value::new(ValTy::adr(
-                        irgm.address_manager().get_addr_assignment(param, 4),
-                    ));
+                // Store the arguments above the stack pointer, where the callee's frame starts.
+                for (count, uniq_var_val) in arg_vals.into_iter().enumerate() {
+                    let stack_addr_val =
+                        Value::new(ValTy::adr(irgm.address_manager().get_stack_pointer()));
+                    let var_addr_val =
+                        Value::new(ValTy::adr(irgm.address_manager().get_arg_slot(count)));
 
-                    let add_inst = irgm.build_op_x_y(param_addr_val, var_addr_val, InstTy::add);
+                    let add_inst = irgm.build_op_x_y(stack_addr_val, var_addr_val, InstTy::add);
                     let add_reg_val = irgm.graph_manager().add_instruction(add_inst);
 
                     let inst;
-                    if let ValTy::con(con_val) = uniq_var_val.clone().get_var_base().clone() {
+                    if let ValTy::con(_) = uniq_var_val.clone().get_var_base().clone() {
                         let add_inst = irgm.build_op_x_y(
                             Value::new(ValTy::con(0)),
-                            Value::new(ValTy::con(con_val)),
+                            uniq_var_val.clone(),
                             InstTy::add,
                         );
                         let add_val = irgm.graph_manager().add_instruction(add_inst);
@@ -218,9 +208,10 @@ impl FuncCall {
                     irgm.graph_manager().add_instruction(inst);
                 }
 
-                // All variables have been loaded, call function
+                // All variables have been loaded, call function. The call itself
+                // stands for the result so it gets a register like any other value.
                 let inst = irgm.build_spec_op(&func_name.to_string(), InstTy::call);
-                irgm.graph_manager().add_instruction(inst);
+                let call_val = irgm.graph_manager().add_instruction(inst);
 
                 // Then I need to load back all the affected globals.
                 for global in &uniq_func.load_assigned_globals() {
@@ -247,9 +238,8 @@ impl FuncCall {
                 }
 
                 // println!("Called function {} has return: {}", func_name, uniq_func.has_return());
-                // TODO : I think I want to change how this is handled to a stack based approach. Not sure.
                 if uniq_func.has_return() {
-                    return Some(Value::new(ValTy::ret(RetRegister::new())));
+                    return Some(call_val);
                 }
             }
         }
@@ -274,6 +264,10 @@ impl FuncCall {
             "InputNum" => {}
             "OutputNum" => {}
             "OutputNewLine" => {}
+            func_name if !irgm.function_manager().has_function(&String::from(func_name)) => {
+                // Not declared yet, its globals are picked up on the next
+                // round of declarations.
+            }
             func_name => {
                 //println!("{} calls {}", func_name, irgm.function_manager().get_mut_function())
                 let affected_globals = irgm
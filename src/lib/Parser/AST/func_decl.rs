@@ -1,14 +1,18 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::func_body::FuncBody;
 use Parser::AST::func_ident::FuncIdent;
 use Parser::AST::var_decl::VarDecl;
+use Parser::sync_var_decl;
 
 use super::Graph;
 use super::{IRGraphManager, InstTy, Node, NodeData, NodeId, NodeType, Op, ValTy, Value};
 use super::{Rc, RefCell};
 use lib::Graph::graph_manager::GraphManager;
 use lib::Graph::node::NodeType::exit;
+use petgraph::prelude::NodeIndex;
 
 #[derive(Debug, Clone)]
 pub struct FuncDecl {
@@ -16,74 +20,67 @@ pub struct FuncDecl {
     funcName: FuncIdent,
     varDecl: Vec<VarDecl>,
     funcBody: FuncBody,
+    span: Span,
 }
 
 impl FuncDecl {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let funcName;
         let mut varDecl = vec![];
         let funcBody;
 
-        match tc.get_next_token().expect("FuncDecl Error").get_type() {
-            TokenType::FuncDecl => {
-                //case matches correctly, token is consumed.
+        match tc.peek_next_token_type() {
+            Some(TokenType::FuncDecl) => {
+                //case matches correctly, consume token.
+                tc.get_next_token();
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Function delcaration token assumed, but not found. Found : {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'function' or 'procedure'"));
             }
         }
 
         match tc.peek_next_token_type() {
             Some(TokenType::Ident) => {
-                funcName = FuncIdent::new(tc);
+                funcName = FuncIdent::new(tc)?;
 
                 match tc.peek_next_token_type() {
                     Some(TokenType::SemiTermination) => {
                         //consume Token then fall through.
                         tc.get_next_token();
                     }
-                    None => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected ';' at end of function ident, but found EOF.");
-                    }
-                    err => {
-                        // Compiler Error :
-                        panic!(
-                            "Expected ';' at end of func_ident, but found unexpected Token: {:?}",
-                            err
-                        );
+                        return Err(ParseError::expected(tc, "';' after function heading"));
                     }
                 }
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected Ident Token at function declaration, found EOF.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected Ident Token at function declaration, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "function name"));
             }
         }
 
         while let Some(next_token) = tc.peek_next_token_type() {
             match next_token {
-                TokenType::Var | TokenType::Array => {
-                    varDecl.push(VarDecl::new(tc));
-                }
+                TokenType::Var | TokenType::Array => match VarDecl::new(tc) {
+                    Ok(decl) => varDecl.push(decl),
+                    Err(error) => {
+                        tc.report_error(error);
+                        sync_var_decl(tc);
+                    }
+                },
                 TokenType::LeftBrace => {
                     //do not consume, fall through
                     break;
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!("Expected Variable Decl or '{{' Token for start of function body, but found unexpected Token {:?}", err);
+                    return Err(ParseError::expected(
+                        tc,
+                        "variable declaration or '{' starting function body",
+                    ));
                 }
             }
         }
@@ -93,35 +90,25 @@ impl FuncDecl {
                 //consume brace, call body
                 tc.get_next_token();
 
-                funcBody = FuncBody::new(tc);
+                funcBody = FuncBody::new(tc)?;
 
                 match tc.peek_next_token_type() {
                     Some(TokenType::RightBrace) => {
                         //all is well, consume token
                         tc.get_next_token();
                     }
-                    None => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected '}' Token in function body, found EOF.");
-                    }
-                    err => {
-                        // Compiler Error :
-                        panic!("Expected '}}' Token at end of function body, found unexpected Token: {:?}", err);
+                        return Err(ParseError::expected(tc, "'}' at end of function body"));
                     }
                 }
             }
-            None => {
-                // Compiler Error :
-                panic!(
-                    "Expected either variable declaration or start of function body, found EOF."
-                );
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected either VarDecl Token or '{{' found unexpected: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(
+                    tc,
+                    "variable declaration or '{' starting function body",
+                ));
             }
         }
 
@@ -130,25 +117,23 @@ impl FuncDecl {
                 //consume token, return
                 tc.get_next_token();
             }
-            None => {
-                // Compiler Error :
-                panic!("Expected ';' Token at end of function body, found EOF.");
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected ';' Token at end of function body, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "';' after function body"));
             }
         }
 
-        FuncDecl {
+        Ok(FuncDecl {
             node_type: TokenType::FuncDecl,
             funcName,
             varDecl,
             funcBody,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (FuncIdent, Vec<VarDecl>, FuncBody) {
@@ -163,6 +148,31 @@ impl FuncDecl {
         self.node_type.clone()
     }
 
+    /// Registers the function's parameters, whether it returns a value and
+    /// the globals it reads and writes (directly or through its calls)
+    /// without lowering it, so calls to it can be built before its body is.
+    pub fn declare(&self, irgm: &mut IRGraphManager) {
+        let (func_name, func_param) = self.funcName.get_value();
+
+        irgm.new_function(func_name.get_value(), &NodeIndex::end());
+
+        if let Some(parameters) = func_param {
+            for variable in parameters.get_value() {
+                irgm.variable_manager()
+                    .active_function()
+                    .add_parameter(&variable.get_value());
+            }
+        }
+
+        self.funcBody.scan_globals(irgm);
+        if self.funcBody.has_return() {
+            irgm.variable_manager().active_function().set_return(true);
+        }
+
+        let uniq_func = irgm.end_function();
+        irgm.function_manager().add_func_to_manager(uniq_func);
+    }
+
     pub fn to_ir(self, irgm: &mut IRGraphManager) {
         let (func_name, func_param) = self.funcName.get_value();
 
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::func_param::FuncParam;
 use Parser::AST::ident::Ident;
 
@@ -8,28 +10,23 @@ pub struct FuncIdent {
     node_type: TokenType,
     funcName: Ident,
     funcParam: Option<FuncParam>,
+    span: Span,
 }
 
 impl FuncIdent {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let funcName;
         let mut funcParam = Option::None;
 
         match tc.peek_next_token_type() {
             Some(TokenType::Ident) => {
                 //function name
-                funcName = Ident::new(tc);
+                funcName = Ident::new(tc)?;
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected Ident Token for function name, found EOF.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected Ident Token for function name, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "function name"));
             }
         }
 
@@ -38,43 +35,37 @@ impl FuncIdent {
                 //function parameter start
                 tc.get_next_token();
 
-                funcParam = Some(FuncParam::new(tc));
+                funcParam = Some(FuncParam::new(tc)?);
 
                 match tc.peek_next_token_type() {
                     Some(TokenType::RightPara) => {
                         tc.get_next_token();
                     }
-                    Some(TokenType::SemiTermination) => {
-                        //pass through to return statement
-                    }
-                    None => {
-                        // Compiler Error :
-                        panic!("Expected ')' Token, found EOF.");
-                    }
-                    err => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected ')' Token at end of function parameters, found unexpected Token: {:?}", err);
+                        return Err(ParseError::expected(tc, "')' closing parameter list"));
                     }
                 }
             }
             Some(TokenType::SemiTermination) => {
                 //no parameters to pass, fall through
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected '(' Token, found EOF.");
-            }
-            err => {
-                // Compiler Error :
-                panic!("Expected '(' Token at end of function parameters, found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'(' or ';' after function name"));
             }
         }
 
-        FuncIdent {
+        Ok(FuncIdent {
             node_type: TokenType::FuncIdent,
             funcName,
             funcParam,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Ident, Option<FuncParam>) {
@@ -1,62 +1,41 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::ident::Ident;
 
 #[derive(Debug, Clone)]
 pub struct FuncParam {
     node_type: TokenType,
     parameters: Vec<Ident>,
+    span: Span,
 }
 
 impl FuncParam {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut parameters = vec![];
 
-        while let Some(next_token) = tc.peek_next_token_type() {
-            match next_token {
-                TokenType::Ident => {
-                    //get parameter ident
-                    parameters.push(Ident::new(tc));
-                }
-                TokenType::Comma => {
-                    //consume token
-                    tc.get_next_token();
-                    match tc.peek_next_token_type() {
-                        Some(TokenType::Ident) => {
-                            //all is well, drop through
-                            continue;
-                        }
-                        None => {
-                            // Compiler Error :
-                            panic!("Unexpected EOF, expected Ident Token following ',' in function param.");
-                        }
-                        err => {
-                            // Compiler Error :
-                            panic!(
-                                "Expected Ident Token following ',', found unexpected Token: {:?}",
-                                err
-                            );
-                        }
-                    }
-                }
-                TokenType::RightPara => {
-                    //end of function, return to func_ident but do not consume token
-                    break;
-                }
-                err => {
-                    // Compiler Error :
-                    panic!(
-                        "Expected Function Parameters, was unable to parse: {:?}",
-                        err
-                    );
-                }
+        if let Some(TokenType::Ident) = tc.peek_next_token_type() {
+            parameters.push(Ident::new(tc)?);
+
+            while let Some(TokenType::Comma) = tc.peek_next_token_type() {
+                //consume token
+                tc.get_next_token();
+                parameters.push(Ident::new(tc)?);
             }
         }
 
-        FuncParam {
+        //end of parameters, return to func_ident but do not consume token
+        Ok(FuncParam {
             node_type: TokenType::FuncParam,
             parameters,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> Vec<Ident> {
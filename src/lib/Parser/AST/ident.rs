@@ -1,20 +1,31 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 
 #[derive(Debug, Clone)]
 pub struct Ident {
     ident_type: TokenType,
     ident_value: String,
-    debugLine: String,
+    span: Span,
 }
 impl Ident {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
+        match tc.peek_next_token_type() {
+            Some(TokenType::Ident) => {}
+            _ => {
+                // Compiler Error :
+                return Err(ParseError::expected(tc, "identifier"));
+            }
+        }
+
         let token = tc.get_next_token().unwrap();
-        Ident {
+        Ok(Ident {
             ident_type: token.get_type(),
             ident_value: token.get_contents(),
-            debugLine: String::from("test"),
-        }
+            span: tc.span_from(&start),
+        })
     }
 
     pub fn get_value(&self) -> String {
@@ -25,7 +36,7 @@ impl Ident {
         self.ident_type.clone()
     }
 
-    pub fn get_debug(self) -> String {
-        self.debugLine.clone()
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 }
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::func_body::FuncBody;
 use Parser::AST::relation::Relation;
 
@@ -13,23 +15,26 @@ pub struct IfStmt {
     relation: Relation,
     funcIfBody: FuncBody,
     funcElseBody: Option<FuncBody>,
+    span: Span,
 }
 
 impl IfStmt {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let relation;
         let funcIfBody;
         let mut funcElseBody = Option::None;
 
-        match tc.get_next_token().expect("If Statment Error").get_type() {
-            TokenType::IfStatement => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::IfStatement) => {
                 //expected if statement token found
                 //Next statement should be a relation type expression
-                relation = Relation::new(tc);
+                tc.get_next_token();
+                relation = Relation::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected If Statement, found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'if'"));
             }
         }
 
@@ -37,15 +42,11 @@ impl IfStmt {
             Some(TokenType::ThenStatement) => {
                 //Found Then token, consume token and move forward.
                 tc.get_next_token();
-                funcIfBody = FuncBody::new(tc);
-            }
-            None => {
-                // Compiler Error :
-                panic!("Unexpected end of file after if relation.");
+                funcIfBody = FuncBody::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected Then token, found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'then'"));
             }
         }
 
@@ -53,21 +54,14 @@ impl IfStmt {
             Some(TokenType::ElseStatement) => {
                 //consume the else, pass body of statement
                 tc.get_next_token();
-                funcElseBody = Option::Some(FuncBody::new(tc));
+                funcElseBody = Option::Some(FuncBody::new(tc)?);
             }
             Some(TokenType::FiStatement) => {
                 //fall through to next match case. this is just an else handler.
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Unexpected end of file. Expected Else or fi statement.");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected Else or fi statment, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'else' or 'fi'"));
             }
         }
 
@@ -86,31 +80,29 @@ impl IfStmt {
                     | Some(TokenType::ElseStatement) => {
                         //';' not required, return without consuming token.
                     }
-                    None => {
-                        // Compiler Error :
-                        panic!("Expected Fi statement, none was found.");
-                    }
-                    err => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected Fi statement, found unexpected Token: {:?}", err);
+                        return Err(ParseError::expected(tc, "';' or end of block after 'fi'"));
                     }
                 }
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Else or fi statment, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'fi'"));
             }
         }
 
-        IfStmt {
+        Ok(IfStmt {
             node_type: TokenType::IfStatement,
             relation,
             funcIfBody,
             funcElseBody,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Relation, FuncBody, Option<FuncBody>) {
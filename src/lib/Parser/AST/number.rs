@@ -1,24 +1,36 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 
 #[derive(Debug, Clone)]
 pub struct Number {
     number_type: TokenType,
     number_value: i32,
-    debugLine: String,
+    span: Span,
 }
 
 impl Number {
-    pub fn new(tc: &mut TokenCollection) -> Self {
-        let token = tc.get_next_token().unwrap();
-        Number {
-            number_type: token.get_type(),
-            number_value: token.get_contents().parse::<i32>().unwrap(),
-            // this will be awesome later, promise
-            // probably want to do some cool combo
-            // where i include both line, line #,
-            // and point out specific spot in the line
-            debugLine: String::from("test"),
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
+        let number_value = match tc.peek_next_token() {
+            Some(ref token) if token.get_type() == TokenType::Number => {
+                token.get_contents().parse::<i32>().ok()
+            }
+            _ => None,
+        };
+
+        match number_value {
+            Some(number_value) => {
+                let token = tc.get_next_token().unwrap();
+                Ok(Number {
+                    number_type: token.get_type(),
+                    number_value,
+                    span: tc.span_from(&start),
+                })
+            }
+            // Compiler Error :
+            None => Err(ParseError::expected(tc, "number that fits in 32 bits")),
         }
     }
 
@@ -30,7 +42,7 @@ impl Number {
         self.number_type.clone()
     }
 
-    pub fn get_debug(self) -> String {
-        self.debugLine.clone()
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 }
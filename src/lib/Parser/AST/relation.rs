@@ -1,6 +1,8 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::Token;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::expression::Expression;
 
 use super::Graph;
@@ -14,40 +16,38 @@ pub struct Relation {
     leftExp: Expression,
     relOp: Token,
     rightExp: Expression,
+    span: Span,
 }
 
 impl Relation {
-    pub fn new(tc: &mut TokenCollection) -> Self {
-        let leftExp = Expression::new(tc);
-        let mut relOp = Token::new(TokenType::None, String::from(""));
-
-        match tc.peek_next_token_type() {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
+        let leftExp = Expression::new(tc)?;
+        let relOp = match tc.peek_next_token_type() {
             Some(TokenType::RelOp) => {
                 //consume token
-                relOp = tc.get_next_token().unwrap();
-            }
-            None => {
-                // Compiler Error :
-                panic!("Expected RelOp token, found EOF.");
+                tc.get_next_token().unwrap()
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected RelOp token, unexpected Token {:?} was found instead.",
-                    err
-                );
+                return Err(ParseError::expected(tc, "comparison operator"));
             }
-        }
+        };
 
-        let rightExp = Expression::new(tc);
+        let rightExp = Expression::new(tc)?;
 
         //relation is built, return
-        Relation {
+        Ok(Relation {
             node_type: TokenType::Relation,
             leftExp,
             relOp,
             rightExp,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Expression, Token, Expression) {
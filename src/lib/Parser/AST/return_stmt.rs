@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::expression::Expression;
 
 use super::Graph;
@@ -11,29 +13,24 @@ use lib::Graph::node::NodeType::exit;
 pub struct ReturnStmt {
     node_type: TokenType,
     expression: Expression,
+    span: Span,
 }
 
 impl ReturnStmt {
-    pub fn new(tc: &mut TokenCollection) -> Self {
-        match tc
-            .get_next_token()
-            .expect("Return Statement Error")
-            .get_type()
-        {
-            TokenType::ReturnStatement => {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
+        match tc.peek_next_token_type() {
+            Some(TokenType::ReturnStatement) => {
                 // return token found, pass through to handle expression.
-                // Otherwise, error handle.
+                tc.get_next_token();
+            }
+            _ => {
+                // Compiler Error :
+                return Err(ParseError::expected(tc, "'return'"));
             }
-            // TODO : fix up to proper error handler
-            err => {
-                println!(
-                    "Expected Return Statement, found unexpected Token: {:?}",
-                    err
-                );
-            } //proper method of error handling unexpected tokens
         }
 
-        let expression = Expression::new(tc);
+        let expression = Expression::new(tc)?;
 
         match tc.peek_next_token_type() {
             Some(TokenType::SemiTermination) => {
@@ -47,23 +44,21 @@ impl ReturnStmt {
             | Some(TokenType::ElseStatement) => {
                 //';' not required, return without consuming token.
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("End of file found, do should be appended by '}' if end of statement");
-            }
-            err => {
-                // Compiler Error :
-                panic!(
-                    "Expected to find ';' or end  statement, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "';' or end of block"));
             }
         }
 
-        ReturnStmt {
+        Ok(ReturnStmt {
             node_type: TokenType::ReturnStatement,
             expression,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> Expression {
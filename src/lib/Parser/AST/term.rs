@@ -1,6 +1,8 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::Token;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::factor::{Factor, FactorType};
 
 use super::Graph;
@@ -9,7 +11,7 @@ use super::{Rc, RefCell};
 use lib::Graph::graph_manager::GraphManager;
 
 #[derive(Debug, Clone)]
-enum TermList {
+pub enum TermList {
     factor(Factor),
     operation(Token),
 }
@@ -18,13 +20,15 @@ enum TermList {
 pub struct Term {
     node_type: TokenType,
     term_list: Vec<TermList>,
+    span: Span,
 }
 
 impl Term {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut term_list = Vec::new();
 
-        term_list.push(TermList::factor(Factor::new(tc)));
+        term_list.push(TermList::factor(Factor::new(tc)?));
 
         loop {
             //handle MulOp possibility
@@ -32,23 +36,24 @@ impl Term {
                 Some(TokenType::MulOp) | Some(TokenType::DivOp) => {
                     //MulOp found, consume then call factor again
                     term_list.push(TermList::operation(tc.get_next_token().unwrap()));
-                    term_list.push(TermList::factor(Factor::new(tc)));
-                }
-                None => {
-                    // Compiler Error :
-                    panic!("Unexpected EOF in term.");
+                    term_list.push(TermList::factor(Factor::new(tc)?));
                 }
                 _ => {
                     //If no MulOp, return
-                    return Term {
+                    return Ok(Term {
                         node_type: TokenType::Term,
                         term_list,
-                    };
+                        span: tc.span_from(&start),
+                    });
                 }
             }
         }
     }
 
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
     pub fn get_value(&self) -> Vec<TermList> {
         return self.term_list.clone();
     }
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST;
 
 use super::Graph;
@@ -10,54 +12,50 @@ use lib::Graph::graph_manager::GraphManager;
 pub struct Var {
     var_type: TokenType,
     var_vec: Vec<AST::ident::Ident>,
-    debugLine: String,
+    span: Span,
 }
 
 impl Var {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut varList: Vec<AST::ident::Ident> = vec![];
-        let mut varTokenType = TokenType::None;
 
-        match tc.get_next_token().expect("Var Error").get_type() {
-            TokenType::Var => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::Var) => {
                 //This is accepted behavior, pass through.
-                varTokenType = TokenType::Var;
+                tc.get_next_token();
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Variable declaration, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'var'"));
             }
         }
 
-        while let Some(next_token) = tc.peek_next_token_type() {
-            match next_token {
-                TokenType::Ident => {
-                    varList.push(AST::ident::Ident::new(tc));
-                }
-                TokenType::Comma => {
+        varList.push(AST::ident::Ident::new(tc)?);
+        loop {
+            match tc.peek_next_token_type() {
+                Some(TokenType::Comma) => {
                     //consume comma token
                     tc.get_next_token();
+                    varList.push(AST::ident::Ident::new(tc)?);
                 }
-                TokenType::SemiTermination => {
+                Some(TokenType::SemiTermination) => {
                     //consume semicolon and return.
                     tc.get_next_token();
                     break;
                 }
-                err => {
+                _ => {
                     // Compiler Error :
-                    panic!("Unable to parse token in variable declaration: {:?}", err);
+                    return Err(ParseError::expected(tc, "',' or ';' in variable declaration"));
                 }
             }
         }
 
-        Var {
-            var_type: varTokenType,
+        Ok(Var {
+            var_type: TokenType::Var,
             var_vec: varList,
-            debugLine: String::from("test"),
-        }
+            span: tc.span_from(&start),
+        })
     }
 
     pub fn get_value(&self) -> Vec<AST::ident::Ident> {
@@ -68,8 +66,8 @@ impl Var {
         self.var_type.clone()
     }
 
-    pub fn get_debug(self) -> String {
-        self.debugLine.clone()
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn to_ir(self, irgm: &mut IRGraphManager, is_global: bool, func_name: Option<String>) {
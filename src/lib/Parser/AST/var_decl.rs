@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::array::Array;
 use Parser::AST::var::Var;
 
@@ -12,38 +14,38 @@ pub struct VarDecl {
     node_type: TokenType,
     var: Option<Var>,
     array: Option<Array>,
+    span: Span,
 }
 
 impl VarDecl {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let mut var = Option::None;
         let mut array = Option::None;
 
         match tc.peek_next_token_type() {
             Some(TokenType::Var) => {
-                var = Some(Var::new(tc));
+                var = Some(Var::new(tc)?);
             }
             Some(TokenType::Array) => {
-                array = Some(Array::new(tc));
+                array = Some(Array::new(tc)?);
             }
-            None => {
-                // Compiler Error :
-                panic!("Expected variable declaration Var or Array, found EOF.");
-            }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected Var or Array Token, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'var' or 'array'"));
             }
         }
 
-        VarDecl {
+        Ok(VarDecl {
             node_type: TokenType::VarDecl,
             var,
             array,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Option<Var>, Option<Array>) {
@@ -1,5 +1,7 @@
+use lib::Lexer::token::Span;
 use lib::Lexer::token::TokenCollection;
 use lib::Lexer::token::TokenType;
+use Parser::parse_error::ParseError;
 use Parser::AST::func_body::FuncBody;
 use Parser::AST::relation::Relation;
 
@@ -13,43 +15,35 @@ pub struct WhileStmt {
     node_type: TokenType,
     relation: Relation,
     body: FuncBody,
+    span: Span,
 }
 
 impl WhileStmt {
-    pub fn new(tc: &mut TokenCollection) -> Self {
+    pub fn new(tc: &mut TokenCollection) -> Result<Self, ParseError> {
+        let start = tc.next_span();
         let relation;
         let body;
 
-        match tc
-            .get_next_token()
-            .expect("While Statement Error")
-            .get_type()
-        {
-            TokenType::WhileStatement => {
+        match tc.peek_next_token_type() {
+            Some(TokenType::WhileStatement) => {
                 //expected token was found, next do relation
-                relation = Relation::new(tc);
+                tc.get_next_token();
+                relation = Relation::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!(
-                    "Expected While statement, found unexpected Token: {:?}",
-                    err
-                );
+                return Err(ParseError::expected(tc, "'while'"));
             }
         }
 
         match tc.peek_next_token_type() {
             Some(TokenType::DoStatement) => {
                 tc.get_next_token();
-                body = FuncBody::new(tc);
-            }
-            None => {
-                // Compiler Error :
-                panic!("Unexpected End of File, expected do statement.");
+                body = FuncBody::new(tc)?;
             }
-            err => {
+            _ => {
                 // Compiler Error :
-                panic!("Expected do statement, found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'do'"));
             }
         }
 
@@ -68,33 +62,28 @@ impl WhileStmt {
                     | Some(TokenType::ElseStatement) => {
                         //';' not required, return without consuming token.
                     }
-                    None => {
-                        // Compiler Error :
-                        panic!(
-                            "End of file found, do should be appended by '}' if end of statement"
-                        );
-                    }
-                    err => {
+                    _ => {
                         // Compiler Error :
-                        panic!("Expected to find ';' or end of block after Od statement, found unexpected Token: {:?}", err);
+                        return Err(ParseError::expected(tc, "';' or end of block after 'od'"));
                     }
                 }
             }
-            None => {
+            _ => {
                 // Compiler Error :
-                panic!("Unexpected End of File, expected Od Token.");
-            }
-            err => {
-                // Compiler Error :
-                panic!("Expected Od Token, found unexpected Token: {:?}", err);
+                return Err(ParseError::expected(tc, "'od'"));
             }
         }
 
-        WhileStmt {
+        Ok(WhileStmt {
             node_type: TokenType::WhileStatement,
             relation,
             body,
-        }
+            span: tc.span_from(&start),
+        })
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 
     pub fn get_value(&self) -> (Relation, FuncBody) {
@@ -0,0 +1,471 @@
+//! Runs a program straight from the AST. This is the reference for what a
+//! PL241 program means, the IR and the generated code are checked against
+//! it: integers wrap around, uninitialized variables and array elements are
+//! 0 and arguments are passed by value. Expects a program that passed
+//! `Semantic::check`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::panic;
+use std::thread;
+
+use lib::IR::interpreter::{Io, StopKind};
+use lib::Lexer::token::{Span, TokenType};
+use lib::Parser::AST::assignment::Assignment;
+use lib::Parser::AST::computation::Comp;
+use lib::Parser::AST::designator::Designator;
+use lib::Parser::AST::expression::{ExpList, Expression};
+use lib::Parser::AST::factor::{Factor, FactorType};
+use lib::Parser::AST::func_body::{FuncBody, Stmt};
+use lib::Parser::AST::func_call::FuncCall;
+use lib::Parser::AST::func_decl::FuncDecl;
+use lib::Parser::AST::if_stmt::IfStmt;
+use lib::Parser::AST::relation::Relation;
+use lib::Parser::AST::term::{Term, TermList};
+use lib::Parser::AST::var_decl::VarDecl;
+use lib::Parser::AST::while_stmt::WhileStmt;
+
+// Statements run before a program is taken to be stuck in a loop.
+pub const DEFAULT_STEP_BUDGET: u64 = 1_000_000;
+
+// The DLX runs out of memory for frames a little short of this many calls,
+// so the cap only stops what the generated code could not run either.
+const MAX_CALL_DEPTH: usize = 500;
+// Each PL241 call nests a few Rust calls, around 12 KiB of stack in a debug
+// build. The rest leaves room for expressions nested inside the calls.
+const STACK_PER_CALL: usize = 32 << 10;
+const STACK_SIZE: usize = MAX_CALL_DEPTH * STACK_PER_CALL;
+
+/// Why a program stopped early, with the part of the source it was running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    kind: StopKind,
+    message: String,
+    span: Span,
+}
+
+impl EvalError {
+    pub fn new(message: String, span: Span) -> Self {
+        EvalError {
+            kind: StopKind::Other,
+            message,
+            span,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: StopKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn get_kind(&self) -> StopKind {
+        self.kind
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+/// Runs `comp` to completion.
+pub fn interpret(comp: &Comp, io: &mut (dyn Io + Send)) -> Result<(), EvalError> {
+    thread::scope(|scope| {
+        let evaluator = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || Evaluator::new(comp).run(io))
+            .expect("Unable to start the interpreter thread.");
+        match evaluator.join() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    })
+}
+
+/// A variable, or an array stored row by row.
+#[derive(Debug, Clone)]
+enum Slot {
+    Scalar(i32),
+    Array(Vec<i32>, Vec<i32>),
+}
+
+/// How a body was left.
+enum Exit {
+    Done,
+    Return(i32),
+}
+
+pub struct Evaluator {
+    comp: Comp,
+    functions: HashMap<String, FuncDecl>,
+    globals: HashMap<String, Slot>,
+    // Parameters and locals of each call in progress, main has none.
+    frames: Vec<HashMap<String, Slot>>,
+    steps: u64,
+    step_budget: Option<u64>,
+}
+
+impl Evaluator {
+    pub fn new(comp: &Comp) -> Self {
+        let (var_decls, func_decls, _) = comp.get_value();
+
+        let mut functions = HashMap::new();
+        for func_decl in func_decls {
+            let name = func_decl.get_value().0.get_value().0.get_value();
+            functions.insert(name, func_decl);
+        }
+
+        Evaluator {
+            comp: comp.clone(),
+            functions,
+            globals: declare_vars(&var_decls),
+            frames: Vec::new(),
+            steps: 0,
+            step_budget: Some(DEFAULT_STEP_BUDGET),
+        }
+    }
+
+    /// `None` lets the program run for as long as it takes.
+    pub fn set_step_budget(&mut self, budget: Option<u64>) {
+        self.step_budget = budget;
+    }
+
+    pub fn run(&mut self, io: &mut dyn Io) -> Result<(), EvalError> {
+        let body = self.comp.get_value().2;
+        self.exec_body(&body, io)?;
+        Ok(())
+    }
+
+    fn exec_body(&mut self, body: &FuncBody, io: &mut dyn Io) -> Result<Exit, EvalError> {
+        for stmt in body.get_value() {
+            if let Exit::Return(value) = self.exec_stmt(&stmt, io)? {
+                return Ok(Exit::Return(value));
+            }
+        }
+
+        Ok(Exit::Done)
+    }
+
+    // Each kind of statement and factor is run by a function of its own, so
+    // a call nested in it only keeps the locals of the kinds it went through
+    // on the stack.
+    fn exec_stmt(&mut self, stmt: &Stmt, io: &mut dyn Io) -> Result<Exit, EvalError> {
+        match stmt {
+            Stmt::assignment(assignment) => {
+                self.step(assignment.get_span())?;
+                self.exec_assignment(assignment, io)
+            }
+            Stmt::if_stmt(if_stmt) => {
+                self.step(if_stmt.get_span())?;
+                self.exec_if(if_stmt, io)
+            }
+            Stmt::while_stmt(while_stmt) => self.exec_while(while_stmt, io),
+            Stmt::funcCall(func_call) => {
+                self.step(func_call.get_span())?;
+                self.call(func_call, io)?;
+                Ok(Exit::Done)
+            }
+            Stmt::return_stmt(return_stmt) => {
+                self.step(return_stmt.get_span())?;
+                let value = self.eval_expression(&return_stmt.get_value(), io)?;
+                Ok(Exit::Return(value))
+            }
+        }
+    }
+
+    fn exec_assignment(&mut self, assignment: &Assignment, io: &mut dyn Io) -> Result<Exit, EvalError> {
+        let (designator, expression) = assignment.get_value();
+        let value = self.eval_expression(&expression, io)?;
+        self.assign(&designator, value, io)?;
+        Ok(Exit::Done)
+    }
+
+    fn exec_if(&mut self, if_stmt: &IfStmt, io: &mut dyn Io) -> Result<Exit, EvalError> {
+        let (relation, if_body, else_body) = if_stmt.get_value();
+        if self.eval_relation(&relation, io)? {
+            self.exec_body(&if_body, io)
+        } else if let Some(else_body) = else_body {
+            self.exec_body(&else_body, io)
+        } else {
+            Ok(Exit::Done)
+        }
+    }
+
+    fn exec_while(&mut self, while_stmt: &WhileStmt, io: &mut dyn Io) -> Result<Exit, EvalError> {
+        let (relation, body) = while_stmt.get_value();
+        loop {
+            self.step(while_stmt.get_span())?;
+            if !self.eval_relation(&relation, io)? {
+                return Ok(Exit::Done);
+            }
+            if let Exit::Return(value) = self.exec_body(&body, io)? {
+                return Ok(Exit::Return(value));
+            }
+        }
+    }
+
+    fn step(&mut self, span: Span) -> Result<(), EvalError> {
+        if let Some(budget) = self.step_budget {
+            if self.steps >= budget {
+                let message = format!("stopped after {} steps", self.steps);
+                return Err(EvalError::new(message, span).with_kind(StopKind::BudgetExhausted));
+            }
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    fn eval_relation(&mut self, relation: &Relation, io: &mut dyn Io) -> Result<bool, EvalError> {
+        let (left, rel_op, right) = relation.get_value();
+        let left = self.eval_expression(&left, io)?;
+        let right = self.eval_expression(&right, io)?;
+
+        match rel_op.get_contents().as_ref() {
+            "==" => Ok(left == right),
+            "!=" => Ok(left != right),
+            "<" => Ok(left < right),
+            "<=" => Ok(left <= right),
+            ">" => Ok(left > right),
+            ">=" => Ok(left >= right),
+            rel_op => Err(EvalError::new(
+                format!("unknown comparison '{}'", rel_op),
+                relation.get_span(),
+            )),
+        }
+    }
+
+    fn eval_expression(&mut self, expression: &Expression, io: &mut dyn Io) -> Result<i32, EvalError> {
+        let mut value: i32 = 0;
+        let mut math_op = TokenType::AddOp;
+
+        for exp in expression.get_value() {
+            match exp {
+                ExpList::term(term) => {
+                    let term_value = self.eval_term(&term, io)?;
+                    value = match math_op {
+                        TokenType::SubOp => value.wrapping_sub(term_value),
+                        _ => value.wrapping_add(term_value),
+                    };
+                }
+                ExpList::math_op(token) => math_op = token.get_type(),
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn eval_term(&mut self, term: &Term, io: &mut dyn Io) -> Result<i32, EvalError> {
+        let mut value: i32 = 1;
+        let mut math_op = TokenType::MulOp;
+
+        for term_part in term.get_value() {
+            match term_part {
+                TermList::factor(factor) => {
+                    let factor_value = self.eval_factor(&factor, io)?;
+
+                    value = match math_op {
+                        TokenType::DivOp if factor_value == 0 => {
+                            let error = EvalError::new(String::from("division by zero"), factor.get_span());
+                            return Err(error.with_kind(StopKind::DivisionByZero));
+                        }
+                        TokenType::DivOp => value.wrapping_div(factor_value),
+                        _ => value.wrapping_mul(factor_value),
+                    };
+                }
+                TermList::operation(token) => math_op = token.get_type(),
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn eval_factor(&mut self, factor: &Factor, io: &mut dyn Io) -> Result<i32, EvalError> {
+        match factor.get_value() {
+            FactorType::desig(designator) => self.load(&designator, io),
+            FactorType::num(number) => Ok(number.get_value()),
+            FactorType::func_call(func_call) => match self.call(&func_call, io)? {
+                Some(value) => Ok(value),
+                None => {
+                    let name = func_call.get_value().0.get_value();
+                    Err(EvalError::new(
+                        format!("'{}' ended without returning a value", name),
+                        func_call.get_span(),
+                    ))
+                }
+            },
+            FactorType::expr(expression) => self.eval_expression(&expression, io),
+        }
+    }
+
+    /// Runs a call, giving back what it returned if it returned anything.
+    fn call(&mut self, func_call: &FuncCall, io: &mut dyn Io) -> Result<Option<i32>, EvalError> {
+        let (ident, args) = func_call.get_value();
+        let name = ident.get_value();
+
+        let mut arg_values = Vec::new();
+        for arg in &args {
+            arg_values.push(self.eval_expression(arg, io)?);
+        }
+
+        match name.as_ref() {
+            "InputNum" => {
+                let value = io.read().map_err(|message| {
+                    EvalError::new(message, func_call.get_span()).with_kind(StopKind::InputExhausted)
+                })?;
+                return Ok(Some(value));
+            }
+            "OutputNum" => {
+                io.write(arg_values.first().cloned().unwrap_or(0));
+                return Ok(None);
+            }
+            "OutputNewLine" => {
+                io.write_nl();
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let func_decl = match self.functions.get(&name) {
+            Some(func_decl) => func_decl.clone(),
+            None => {
+                let message = format!("function '{}' is not declared", name);
+                return Err(EvalError::new(message, ident.get_span()));
+            }
+        };
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            let message = format!("calls nested more than {} deep", MAX_CALL_DEPTH);
+            return Err(EvalError::new(message, func_call.get_span()));
+        }
+
+        let (func_ident, var_decls, body) = func_decl.get_value();
+        let params = func_ident
+            .get_value()
+            .1
+            .map(|params| params.get_value())
+            .unwrap_or_default();
+        if params.len() != arg_values.len() {
+            let message = format!(
+                "function '{}' takes {} argument(s) but {} were given",
+                name,
+                params.len(),
+                arg_values.len()
+            );
+            return Err(EvalError::new(message, ident.get_span()));
+        }
+
+        let mut frame = declare_vars(&var_decls);
+        for (param, value) in params.iter().zip(arg_values) {
+            frame.insert(param.get_value(), Slot::Scalar(value));
+        }
+
+        self.frames.push(frame);
+        let exit = self.exec_body(&body, io);
+        self.frames.pop();
+
+        match exit? {
+            Exit::Return(value) => Ok(Some(value)),
+            Exit::Done => Ok(None),
+        }
+    }
+
+    fn load(&mut self, designator: &Designator, io: &mut dyn Io) -> Result<i32, EvalError> {
+        let (slot_name, index) = self.locate(designator, io)?;
+        match (self.slot(&slot_name), index) {
+            (Some(Slot::Scalar(value)), None) => Ok(*value),
+            (Some(Slot::Array(_, data)), Some(index)) => Ok(data[index]),
+            _ => Err(self.bad_designator(designator)),
+        }
+    }
+
+    fn assign(&mut self, designator: &Designator, value: i32, io: &mut dyn Io) -> Result<(), EvalError> {
+        let (slot_name, index) = self.locate(designator, io)?;
+        match (self.slot_mut(&slot_name), index) {
+            (Some(Slot::Scalar(slot_value)), None) => *slot_value = value,
+            (Some(Slot::Array(_, data)), Some(index)) => data[index] = value,
+            _ => return Err(self.bad_designator(designator)),
+        }
+        Ok(())
+    }
+
+    /// Name of the variable `designator` refers to and, for an array
+    /// element, its position in the array.
+    fn locate(&mut self, designator: &Designator, io: &mut dyn Io) -> Result<(String, Option<usize>), EvalError> {
+        let (ident, indices) = designator.get_value();
+        let name = ident.get_value();
+        if indices.is_empty() {
+            return Ok((name, None));
+        }
+
+        let mut index_values = Vec::new();
+        for index in &indices {
+            index_values.push(self.eval_expression(index, io)?);
+        }
+
+        let dims = match self.slot(&name) {
+            Some(Slot::Array(dims, _)) if dims.len() == index_values.len() => dims.clone(),
+            _ => return Err(self.bad_designator(designator)),
+        };
+
+        let mut position = 0;
+        for ((index, dim), expression) in index_values.iter().zip(&dims).zip(&indices) {
+            if *index < 0 || index >= dim {
+                let message = format!("index {} is out of bounds for '{}' (size {})", index, name, dim);
+                return Err(EvalError::new(message, expression.get_span()).with_kind(StopKind::IndexOutOfBounds));
+            }
+            position = position * dim + index;
+        }
+
+        Ok((name, Some(position as usize)))
+    }
+
+    fn slot(&self, name: &String) -> Option<&Slot> {
+        match self.frames.last() {
+            Some(frame) if frame.contains_key(name) => frame.get(name),
+            _ => self.globals.get(name),
+        }
+    }
+
+    fn slot_mut(&mut self, name: &String) -> Option<&mut Slot> {
+        match self.frames.last_mut() {
+            Some(frame) if frame.contains_key(name) => frame.get_mut(name),
+            _ => self.globals.get_mut(name),
+        }
+    }
+
+    fn bad_designator(&self, designator: &Designator) -> EvalError {
+        let name = designator.get_value().0.get_value();
+        EvalError::new(format!("'{}' can not be used this way", name), designator.get_span())
+    }
+}
+
+fn declare_vars(var_decls: &[VarDecl]) -> HashMap<String, Slot> {
+    let mut slots = HashMap::new();
+    for var_decl in var_decls {
+        match var_decl.get_value() {
+            (Some(var), _) => {
+                for ident in var.get_value() {
+                    slots.insert(ident.get_value(), Slot::Scalar(0));
+                }
+            }
+            (None, Some(array)) => {
+                let (dims, idents) = array.get_value();
+                let dims = dims.iter().map(|dim| dim.get_value()).collect::<Vec<i32>>();
+                let size = dims.iter().product::<i32>().max(0) as usize;
+                for ident in idents {
+                    slots.insert(ident.get_value(), Slot::Array(dims.clone(), vec![0; size]));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    slots
+}
@@ -2,5 +2,121 @@
 //modules by AST type. Or possibly even have a totally separate AST node
 //and just have the actual parsing logic here.
 pub mod AST;
+pub mod interpreter;
+pub mod parse_error;
 
-use self::AST::computation;
+use self::parse_error::ParseError;
+use self::AST::computation::Comp;
+use lib::Lexer::token::{TokenCollection, TokenType};
+
+/// Parses a whole program, returning every syntax error found in it if
+/// there were any.
+pub fn parse(tc: &mut TokenCollection) -> Result<Comp, Vec<ParseError>> {
+    let comp = Comp::new(tc);
+    let mut errors = tc.take_errors();
+
+    match comp {
+        Ok(comp) => {
+            if errors.is_empty() {
+                Ok(comp)
+            } else {
+                Err(errors)
+            }
+        }
+        Err(error) => {
+            errors.push(error);
+            Err(errors)
+        }
+    }
+}
+
+/// Skips past the rest of a statement that failed to parse, so the body it
+/// is in can carry on with the next one. `opener` is the token the
+/// statement started with: a `while` or `if` owns everything up to its
+/// `od` or `fi`, anything else ends at the next ';'. Stops without
+/// consuming at the end of the enclosing block.
+pub fn sync_statement(tc: &mut TokenCollection, opener: TokenType) {
+    let mut closers = match opener {
+        TokenType::WhileStatement => vec![TokenType::OdStatement],
+        TokenType::IfStatement => vec![TokenType::FiStatement],
+        _ => vec![],
+    };
+
+    while let Some(next_token) = tc.peek_next_token_type() {
+        match next_token {
+            TokenType::WhileStatement => closers.push(TokenType::OdStatement),
+            TokenType::IfStatement => closers.push(TokenType::FiStatement),
+            TokenType::OdStatement | TokenType::FiStatement => {
+                if closers.last() != Some(&next_token) {
+                    // Closes a block the statement is nested in.
+                    return;
+                }
+                closers.pop();
+                if closers.is_empty() {
+                    tc.get_next_token();
+                    if tc.peek_next_token_type() == Some(TokenType::SemiTermination) {
+                        tc.get_next_token();
+                    }
+                    return;
+                }
+            }
+            TokenType::SemiTermination | TokenType::ElseStatement if closers.is_empty() => {
+                if next_token == TokenType::SemiTermination {
+                    tc.get_next_token();
+                }
+                return;
+            }
+            TokenType::RightBrace | TokenType::ComputationEnd => return,
+            _ => {}
+        }
+        tc.get_next_token();
+    }
+}
+
+/// Skips past the rest of a variable declaration that failed to parse,
+/// stopping at its ';' or wherever the next declaration or body starts.
+pub fn sync_var_decl(tc: &mut TokenCollection) {
+    while let Some(next_token) = tc.peek_next_token_type() {
+        match next_token {
+            TokenType::SemiTermination => {
+                tc.get_next_token();
+                return;
+            }
+            TokenType::Var
+            | TokenType::Array
+            | TokenType::FuncDecl
+            | TokenType::LeftBrace
+            | TokenType::ComputationEnd => return,
+            _ => {
+                tc.get_next_token();
+            }
+        }
+    }
+}
+
+/// Skips past the rest of a function declaration that failed to parse,
+/// up to the '}' closing its body (and the ';' after it), or the start of
+/// the next declaration.
+pub fn sync_func_decl(tc: &mut TokenCollection) {
+    let mut depth = 0;
+
+    while let Some(next_token) = tc.peek_next_token_type() {
+        match next_token {
+            TokenType::LeftBrace => depth += 1,
+            TokenType::RightBrace => {
+                if depth <= 1 {
+                    tc.get_next_token();
+                    if tc.peek_next_token_type() == Some(TokenType::SemiTermination) {
+                        tc.get_next_token();
+                    }
+                    return;
+                }
+                depth -= 1;
+            }
+            TokenType::FuncDecl if depth == 0 => return,
+            TokenType::ComputationEnd => return,
+            _ => {}
+        }
+        tc.get_next_token();
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use lib::Lexer::token::{Span, Token, TokenCollection};
+
+/// Syntax error: what the parser was looking for, the token it found
+/// instead (`None` once the file ran out) and where that token is.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    expected: String,
+    found: Option<Token>,
+    span: Span,
+}
+
+impl ParseError {
+    /// Error against the next token in `tc`, which is left unconsumed.
+    pub fn expected(tc: &mut TokenCollection, expected: &str) -> Self {
+        ParseError {
+            expected: String::from(expected),
+            found: tc.peek_next_token(),
+            span: tc.next_span(),
+        }
+    }
+
+    pub fn get_expected(&self) -> String {
+        self.expected.clone()
+    }
+
+    pub fn get_found(&self) -> Option<Token> {
+        self.found.clone()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.found {
+            Some(token) => write!(
+                f,
+                "{}: expected {}, found '{}'",
+                self.span,
+                self.expected,
+                token.get_contents()
+            ),
+            None => write!(f, "{}: expected {}, found end of file", self.span, self.expected),
+        }
+    }
+}
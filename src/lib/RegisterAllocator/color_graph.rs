@@ -95,7 +95,9 @@ pub fn color(int_graph: &mut Graph<OpNode, String, Directed, u32>) -> Result<(),
             let lowest_weight = int_graph.node_weight(lowest_node_id.clone()).unwrap().get_weight();
             let current_inst_weight = int_graph.node_weight(node_id.clone()).unwrap().get_weight();
 
-            if current_inst_weight < (lowest_weight * 2) {
+            // Spill temporaries (and values spilled before) only get more of them
+            // when spilled again, those fall through to a register past the eighth.
+            if current_inst_weight < 100000 && current_inst_weight < (lowest_weight * 2) {
                 // Spilling the lowest one every time seems to generate a lot more spills.
                 // Perhaps if spilling only if the lowest is a factor of 3 lower in weight
                 // will reduce the amount of spills.
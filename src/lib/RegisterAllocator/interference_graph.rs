@@ -7,14 +7,14 @@ use lib::IR::ir::ValTy;
 use lib::IR::ir::{InstTy, Op};
 use lib::IR::ir_manager::IRGraphManager;
 
-use petgraph::algo::dominators::Dominators;
+use lib::Graph::{control_flow_graph, is_phi_x_side};
+use petgraph::algo::dominators::{simple_fast, Dominators};
+use petgraph::visit::DfsPostOrder;
 use petgraph::Graph;
 use petgraph::{Directed, Incoming, Outgoing};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use lib::Optimizer::temp_value_manager::TempValManager;
-use lib::Graph::node::NodeType;
 
 #[derive(Clone)]
 pub struct OpNode {
@@ -118,30 +118,26 @@ impl Debug for OpNode {
     }
 }
 
-pub struct RecurseTraverse {
-    current_node: NodeIndex,
+// Phis and the remaining instructions of a block.
+type BlockInsts = (Vec<Rc<RefCell<Op>>>, Vec<Rc<RefCell<Op>>>);
+type LiveSet = HashMap<usize, Rc<RefCell<Op>>>;
+
+/// Builds the interference graph of a single function (or main) using a
+/// backwards liveness analysis over its control flow blocks. Phi operands
+/// are live at the end of the predecessor they come from, and every phi in
+/// a block is defined together at the top of that block.
+pub struct InterferenceBuilder {
+    root_node: NodeIndex,
     interference_graph: Graph<OpNode, String, Directed, u32>,
-    temp_val_manager: TempValManager,
     inst_node_map: HashMap<usize, NodeIndex>,
-    live_inst_map: HashMap<usize, NodeIndex>,
-    coalescence_map: HashMap<NodeIndex, (Option<NodeIndex>,Option<NodeIndex>)>,
-    dominators: Dominators<NodeIndex>,
-    while_bp: Option<NodeIndex>,
-    if_bp: Option<NodeIndex>,
 }
 
-impl RecurseTraverse {
-    pub fn new(current_node: NodeIndex, temp_val_manager: & TempValManager, dominators: Dominators<NodeIndex>) -> Self {
-        RecurseTraverse {
-            current_node,
+impl InterferenceBuilder {
+    pub fn new(root_node: NodeIndex) -> Self {
+        InterferenceBuilder {
+            root_node,
             interference_graph: Graph::new(),
-            temp_val_manager: temp_val_manager.clone(),
             inst_node_map: HashMap::new(),
-            live_inst_map: HashMap::new(),
-            coalescence_map: HashMap::new(),
-            dominators,
-            while_bp: None,
-            if_bp: None,
         }
     }
 
@@ -149,546 +145,332 @@ impl RecurseTraverse {
         self.interference_graph
     }
 
-    pub fn set_starting_exit(&mut self, exit_id: NodeIndex) {
-        self.current_node = exit_id;
-    }
+    pub fn build(&mut self, irgm: &mut IRGraphManager, spilled_inst: &HashMap<usize, usize>) {
+        let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+        let dom_space = simple_fast(&graph, self.root_node);
 
-    /// Recursing options:
-    /// 0-Parents                    -> End of recursion, return completed live range
-    /// 1-Parent                     -> Continue directly up graph
-    /// 2-Parents (neither dominate) -> An if branch. go up left (until loop header is found), then traverse up right.
-    ///                                 Continue with the loop header after joining live range from left and right.
-    /// 2-Parents (one dominates)    -> An else branch, loop through the branch dominated by loop twice, then traverse
-    ///                                 up the dominating path.
-    pub fn recursive_traversal(&mut self, irgm: &mut IRGraphManager, spilled_inst: & HashMap<usize, usize>) {
-        if let Some(node_id) = self.if_bp {
-            if self.current_node == node_id.clone() {
-                return;
-            }
+        let mut post_order = Vec::new();
+        let mut dfs = DfsPostOrder::new(&graph, self.root_node);
+        while let Some(node_id) = dfs.next(&graph) {
+            post_order.push(node_id);
         }
 
-        let mut parents = Vec::new();
-        for parent_id in irgm
-            .clone()
-            .graph_manager()
-            .get_ref_graph()
-            .neighbors_directed(self.current_node.clone(), Incoming)
-        {
-            parents.push(parent_id);
-        }
+        let loop_blocks = Self::loop_blocks(&graph, &dom_space, &post_order);
 
-        match parents.len() {
-            0 => {
-                // Final node, perform any required actions then simply return.
-                self.grab_live_ranges(irgm, BlockType::standard, spilled_inst);
-                return;
-            }
-            1 => {
-                self.grab_live_ranges(irgm, BlockType::standard, spilled_inst);
-                self.current_node = parents.pop().unwrap();
-                self.recursive_traversal(irgm, spilled_inst);
-                return;
-            }
-            2 => {
-                // two cases here, if or while
-                let mut ordered_parents = Vec::new();
-                let mut is_while = false;
-
-                if NodeType::while_loop_header == irgm.graph_manager()
-                    .get_ref_graph()
-                    .node_weight(self.current_node)
-                    .unwrap()
-                    .get_node_type() {
-                    is_while = true;
+        // Active instructions of each block, phis split off from the rest.
+        let mut block_insts = HashMap::new();
+        for node_id in post_order.iter() {
+            let mut phis = Vec::new();
+            let mut body = Vec::new();
+            for inst in graph.node_weight(*node_id).unwrap().get_data_ref().get_inst_list_ref() {
+                if !inst.borrow().is_active() {
+                    continue;
                 }
-
-                // This gives both information as to which control flow type it
-                // is, as well as sorting for the while case.
-                for node_id in parents.iter() {
-                    if self.dominators.immediate_dominator(self.current_node)
-                        == Some(node_id.clone())
-                    {
-                        if is_while {
-                            ordered_parents.insert(0, node_id.clone());
-                        } else {
-                            ordered_parents.push(node_id.clone());
-                        }
-                    } else {
-                        ordered_parents.push(node_id.clone());
-                    }
+                if inst.borrow().inst_type().clone() == InstTy::phi {
+                    phis.push(Rc::clone(inst));
+                } else {
+                    body.push(Rc::clone(inst));
                 }
+            }
+            block_insts.insert(*node_id, (phis, body));
+        }
 
-                if is_while {
-                    // This path will require two runs through the non-dominating path.
-                    // 0 is the dominating path, thus goes second.
-                    // 1 is the looping path, thus must go through it twice.
-
-                    // Ensure that the while loops dont recurse through the graph infinitely
-                    // but also goes through the header again.
-                    if let Some(node_id) = &self.while_bp {
-                        if self.current_node == node_id.clone() {
-                            return;
+        // Values each block hands to the phis of its successors.
+        let mut phi_uses: HashMap<NodeIndex, Vec<Rc<RefCell<Op>>>> = HashMap::new();
+        for node_id in post_order.iter() {
+            let mut uses = Vec::new();
+            for child_id in graph.neighbors_directed(*node_id, Outgoing) {
+                let is_x_side = is_phi_x_side(&graph, &dom_space, *node_id, child_id);
+                let (child_phis, _) = &block_insts[&child_id];
+                for phi in child_phis {
+                    let phi_val = if is_x_side {
+                        phi.borrow().clone_x_val()
+                    } else {
+                        phi.borrow().clone_y_val()
+                    };
+                    if let Some(phi_val) = phi_val {
+                        if let ValTy::op(used_op) = phi_val.get_value() {
+                            uses.push(Rc::clone(used_op));
                         }
                     }
+                }
+            }
+            phi_uses.insert(*node_id, uses);
+        }
 
+        // Iterate the live in sets until nothing changes, post order
+        // visits successors first so this settles quickly.
+        let mut live_in: HashMap<NodeIndex, LiveSet> = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node_id in post_order.iter() {
+                let live = self.block_liveness(&graph, &block_insts, &phi_uses, &live_in, *node_id, None);
+                let is_same = match live_in.get(node_id) {
+                    Some(old_live) => old_live.len() == live.len(),
+                    None => false,
+                };
+                if !is_same {
+                    live_in.insert(*node_id, live);
+                    changed = true;
+                }
+            }
+        }
 
+        // Walk every block one final time, this time recording interference.
+        for node_id in post_order.iter() {
+            let is_loop = loop_blocks.contains(node_id);
+            self.block_liveness(
+                &graph,
+                &block_insts,
+                &phi_uses,
+                &live_in,
+                *node_id,
+                Some((spilled_inst, is_loop)),
+            );
+        }
+    }
 
-                    // Make save point of previous while break point
-                    let prev_while_bp = self.while_bp.clone();
-                    let current_id_recovery = self.current_node.clone();
-
-                    // Set while break point
-                    self.grab_live_ranges(irgm, BlockType::while_loop, spilled_inst);
-                    self.while_bp = Some(self.current_node.clone());
-                    self.current_node = ordered_parents[1].clone();
-                    self.recursive_traversal(irgm, spilled_inst);
-
-                    // Grab live ranges for the loop of the while
-                    self.current_node = current_id_recovery;
-                    self.grab_live_ranges(irgm, BlockType::while_loop, spilled_inst);
-                    self.current_node = ordered_parents[1].clone();
-                    self.recursive_traversal(irgm, spilled_inst);
-
-                    // This is the final run through the current node, this time only grab the right side of any phis.
-                    // Grab live ranges for the loop of the while
-                    self.current_node = current_id_recovery;
-                    self.grab_live_ranges(irgm, BlockType::while_cont, spilled_inst);
-                    self.while_bp = prev_while_bp;
-                    self.current_node = ordered_parents[0].clone();
-                    self.recursive_traversal(irgm, spilled_inst);
-                    return;
-                } else {
+    /// Computes the values live at the top of `node_id`. When `record` is
+    /// given, every definition and use is also added to the interference
+    /// graph (using the spill counts and whether the block is in a loop).
+    fn block_liveness(
+        &mut self,
+        graph: &Graph<Node, String, Directed, u32>,
+        block_insts: &HashMap<NodeIndex, BlockInsts>,
+        phi_uses: &HashMap<NodeIndex, Vec<Rc<RefCell<Op>>>>,
+        live_in: &HashMap<NodeIndex, LiveSet>,
+        node_id: NodeIndex,
+        record: Option<(&HashMap<usize, usize>, bool)>,
+    ) -> LiveSet {
+        let mut live = HashMap::new();
+        for child_id in graph.neighbors_directed(node_id, Outgoing) {
+            if let Some(child_live) = live_in.get(&child_id) {
+                for (inst_id, inst) in child_live.iter() {
+                    live.insert(*inst_id, Rc::clone(inst));
+                }
+            }
+        }
+        for used_op in phi_uses[&node_id].iter() {
+            self.add_use(used_op, &mut live, record);
+        }
 
-                    // This is the if case. Traverse up both paths until the dominator is hit, then return
-                    // and merge the two live ranges and go through the dominating path.
-                    let immediate_dominator = self
-                        .dominators
-                        .immediate_dominator(self.current_node)
-                        .expect(
-                            &format!("No dominating path found for: {:?}", self.current_node)[..],
-                        )
-                        .clone();
-
-                    // Clone current live range so it can be used for both left and right side
-                    let live_range_copy = self.live_inst_map.clone();
-                    let if_bp_recovery = self.if_bp.clone();
-                    let current_node_recovery = self.current_node.clone();
-
-                    // Grab live range of this block for right case
-                    self.grab_live_ranges(irgm, BlockType::if_phi_right, spilled_inst);
-                    self.if_bp = Some(immediate_dominator.clone());
-                    self.current_node = ordered_parents[0].clone();
-
-                    self.recursive_traversal(irgm, spilled_inst);
-
-                    // Save new live range, recover old one
-                    let mut final_live_range = self.live_inst_map.clone();
-                    self.live_inst_map = live_range_copy;
-                    self.current_node = current_node_recovery;
-
-                    self.grab_live_ranges(irgm, BlockType::if_phi_left, spilled_inst);
-                    self.current_node = ordered_parents[1].clone();
-
-                    self.recursive_traversal(irgm, spilled_inst);
-
-                    // The current live_inst_map should contain all the live range information from the if branch
-                    // and so simply comparing with the stored final_live_range should get a completed list.
-                    for (key, value) in self.live_inst_map.iter() {
-                        if !final_live_range.contains_key(key) {
-                            final_live_range.insert(key.clone(), value.clone());
-                        }
-                    }
+        let (phis, body) = &block_insts[&node_id];
+        for inst in body.iter().rev() {
+            let inst_id = inst.borrow().get_inst_num();
+            self.add_def(inst_id, &live, record);
+            live.remove(&inst_id);
 
-                    // Once the two lists have been combined, make it the live_inst_map
-                    self.live_inst_map = final_live_range;
+            for val in [inst.borrow().clone_x_val(), inst.borrow().clone_y_val()].iter().flatten() {
+                if let ValTy::op(used_op) = val.get_value() {
+                    self.add_use(used_op, &mut live, record);
+                }
+            }
+        }
 
-                    // set next node to the immediate_dominator node
-                    self.if_bp = if_bp_recovery;
-                    self.current_node = immediate_dominator;
+        // The phis are all written on the way into the block, so they
+        // interfere with each other as well as everything live after them.
+        for phi in phis.iter() {
+            let phi_id = phi.borrow().get_inst_num();
+            self.add_def(phi_id, &live, record);
+        }
+        for phi in phis.iter() {
+            live.remove(&phi.borrow().get_inst_num());
+        }
 
-                    self.recursive_traversal(irgm, spilled_inst);
+        live
+    }
 
-                    return;
-                }
+    fn add_def(
+        &mut self,
+        inst_id: usize,
+        live: &LiveSet,
+        record: Option<(&HashMap<usize, usize>, bool)>,
+    ) {
+        let spilled_inst = match record {
+            Some((spilled_inst, _)) => spilled_inst,
+            None => return,
+        };
+
+        // Values nobody reads never get a node (or a register).
+        let inst_node_id = match self.inst_node_map.get(&inst_id) {
+            Some(inst_node_id) => *inst_node_id,
+            None => return,
+        };
+
+        for (live_id, live_inst) in live.iter() {
+            if *live_id == inst_id {
+                continue;
             }
-            _ => {
-                panic!("Should be no more than 2 parents for any given node of the graph.");
+            // Uses reached through a back edge may not have been walked yet.
+            let live_node_id = self.get_node(live_inst, spilled_inst);
+            if self.interference_graph.find_edge_undirected(inst_node_id, live_node_id).is_none() {
+                self.interference_graph
+                    .update_edge(inst_node_id, live_node_id, String::from("black"));
             }
         }
     }
 
-    fn grab_live_ranges(&mut self, irgm: &mut IRGraphManager, block_type: BlockType, spilled_inst: & HashMap<usize, usize>) {
-        // Get current node's instructions
-        let mut inst_list = irgm
-            .graph_manager()
-            .get_ref_graph()
-            .node_weight(self.current_node.clone())
-            .unwrap()
-            .get_data_ref()
-            .get_inst_list_ref()
-            .clone();
-
-        // Reverse instruction to traverse inst from bottom to top
-        inst_list.reverse();
-
-        for inst in inst_list.iter() {
-            // Get current instruction ID and remove from live range
-            let inst_id = inst.borrow().get_inst_num();
-            let inst_type = inst.borrow().inst_type().clone();
+    fn add_use(
+        &mut self,
+        used_op: &Rc<RefCell<Op>>,
+        live: &mut LiveSet,
+        record: Option<(&HashMap<usize, usize>, bool)>,
+    ) {
+        let inst_id = used_op.borrow().get_inst_num();
+        live.insert(inst_id, Rc::clone(used_op));
+
+        if let Some((spilled_inst, is_loop)) = record {
+            let inst_node_id = self.get_node(used_op, spilled_inst);
+            // Uses inside of a loop make a value much more costly to spill.
+            let weight = if is_loop { 20 } else { 1 };
+            self.interference_graph
+                .node_weight_mut(inst_node_id)
+                .unwrap()
+                .add_weight(weight);
+        }
+    }
 
-            // Remove instruction from live range
-            if self.live_inst_map.contains_key(&inst_id) {
-                self.live_inst_map.remove(&inst_id);
-            }
+    fn get_node(&mut self, inst: &Rc<RefCell<Op>>, spilled_inst: &HashMap<usize, usize>) -> NodeIndex {
+        let inst_id = inst.borrow().get_inst_num();
+        if let Some(inst_node_id) = self.inst_node_map.get(&inst_id) {
+            return *inst_node_id;
+        }
 
-            // Check for x and y values, only Ops can produce result and must be tracked.
-
-            // Check for an x_value
-            if let Some(x_val) = inst.borrow().clone_x_val() {
-                if let ValTy::op(x_inst) = x_val.get_value() {
-                    let x_inst_id = x_inst.borrow().get_inst_num();
-                    let x_inst_type = x_inst.borrow().inst_type().clone();
-                    if !self.live_inst_map.contains_key(&x_inst_id) {
-                        let inst_node_id;
-                        if !self.inst_node_map.contains_key(&x_inst_id) {
-                            // This instruction is not already part of the live range.
-                            // Create new node and add to the graph.
-                            let mut weight = 2;
-                            //weight += self.temp_val_manager.borrow_inst(&x_inst_id).borrow().active_uses().len();
-                            if spilled_inst.contains_key(&x_inst_id) {
-                                let modifier = spilled_inst.get(&x_inst_id).unwrap().clone();
-                                weight += 1000000 * modifier;
-                            }
-                            if x_inst_type == InstTy::sadd {
-                                weight += 100000;
-                            }
-                            if x_inst_type == InstTy::sload {
-                                weight += 100000;
-                            }
-                            let op_node = OpNode::new(Rc::clone(x_inst), weight * 2);
-                            inst_node_id = self.interference_graph.add_node(op_node);
-                            self.inst_node_map.insert(x_inst_id, inst_node_id.clone());
-                        } else {
-                            inst_node_id = self.inst_node_map.get(&x_inst_id).unwrap().clone();
-                        }
+        let inst_type = inst.borrow().inst_type().clone();
+        let mut weight = 2;
+        if let Some(modifier) = spilled_inst.get(&inst_id) {
+            weight += 1000000 * modifier;
+        }
+        if inst_type == InstTy::sadd || inst_type == InstTy::sload {
+            weight += 100000;
+        }
 
-                        if let Some(while_node) = self.while_bp.clone() {
-                            // if this is true it indicates being in a while loop
-                            // add 10 every time a value gets called in a loop
-                            self.interference_graph
-                                .node_weight_mut(inst_node_id)
-                                .unwrap()
-                                .add_weight(20);
-                        } else {
-                            // It is not a loop, but it is a use.
-                            // Add single point of weight
-                            self.interference_graph
-                                .node_weight_mut(inst_node_id)
-                                .unwrap()
-                                .add_weight(1);
-                        }
+        let inst_node_id = self
+            .interference_graph
+            .add_node(OpNode::new(Rc::clone(inst), weight * 2));
+        self.inst_node_map.insert(inst_id, inst_node_id);
+        inst_node_id
+    }
 
-                        if inst_type == InstTy::phi {
-                            //x_index = Some(inst_node_id.clone());
-                            if block_type == BlockType::while_loop
-                                || block_type == BlockType::if_phi_left
-                            {
-                                // Make an edge between all nodes currently in live range, then add to live range
-                                for (_, node_id) in self.live_inst_map.iter() {
-                                    if None
-                                        == self
-                                            .interference_graph
-                                            .find_edge_undirected(inst_node_id, node_id.clone())
-                                    {
-                                        self.interference_graph.update_edge(
-                                            inst_node_id,
-                                            node_id.clone(),
-                                            String::from("black"),
-                                        );
-                                    }
-                                }
-
-                                self.live_inst_map.insert(x_inst_id, inst_node_id);
-                            } else {
-                                // Do not add to the liveness range, fall through to y.
-                            }
-                        } else {
-                            // Make an edge between all nodes currently in live range, then add to live range
-                            for (_, node_id) in self.live_inst_map.iter() {
-                                if None
-                                    == self
-                                        .interference_graph
-                                        .find_edge_undirected(inst_node_id, node_id.clone())
-                                {
-                                    self.interference_graph.update_edge(
-                                        inst_node_id,
-                                        node_id.clone(),
-                                        String::from("black"),
-                                    );
-                                }
-                            }
-
-                            self.live_inst_map.insert(x_inst_id, inst_node_id);
-                        }
-                    }
+    /// Blocks belonging to the body of any loop, found from the back
+    /// edges into each loop header.
+    fn loop_blocks(
+        graph: &Graph<Node, String, Directed, u32>,
+        dom_space: &Dominators<NodeIndex>,
+        blocks: &[NodeIndex],
+    ) -> HashSet<NodeIndex> {
+        let mut loop_blocks = HashSet::new();
+        for header_id in blocks.iter() {
+            for tail_id in graph.neighbors_directed(*header_id, Incoming) {
+                let is_back_edge = match dom_space.dominators(tail_id) {
+                    Some(mut dominators) => dominators.any(|dom_id| dom_id == *header_id),
+                    None => false,
+                };
+                if !is_back_edge {
+                    continue;
                 }
-            }
 
-            // Check for a y_value
-            if let Some(y_val) = inst.borrow().clone_y_val() {
-                if let ValTy::op(y_inst) = y_val.get_value() {
-                    let y_inst_id = y_inst.borrow().get_inst_num();
-                    let y_inst_type = y_inst.borrow().inst_type().clone();
-                    if !self.live_inst_map.contains_key(&y_inst_id) {
-                        let inst_node_id;
-                        if !self.inst_node_map.contains_key(&y_inst_id) {
-                            // This instruction is not already part of the live range.
-                            // Create new node and add to the graph.
-                            let mut weight = 2;
-                            //weight += self.temp_val_manager.borrow_inst(&y_inst_id).borrow().active_uses().len();
-                            if spilled_inst.contains_key(&y_inst_id) {
-                                let modifier = spilled_inst.get(&y_inst_id).unwrap().clone();
-                                weight += 1000000 * modifier;
-                            }
-                            if y_inst_type == InstTy::sadd {
-                                weight += 100000;
-                            }
-                            if y_inst_type == InstTy::sload {
-                                weight += 100000;
-                            }
-                            let op_node = OpNode::new(Rc::clone(y_inst), weight * 2);
-                            inst_node_id = self.interference_graph.add_node(op_node);
-                            self.inst_node_map.insert(y_inst_id, inst_node_id.clone());
-                        } else {
-                            inst_node_id = self.inst_node_map.get(&y_inst_id).unwrap().clone();
-                        }
-
-                        if let Some(while_node) = self.while_bp.clone() {
-                            // if this is true it indicates being in a while loop
-                            // add 10 every time a value gets called in a loop
-                            self.interference_graph
-                                .node_weight_mut(inst_node_id)
-                                .unwrap()
-                                .add_weight(20);
-                        } else {
-                            // It is not a loop, but it is a use.
-                            // Add single point of weight
-                            self.interference_graph
-                                .node_weight_mut(inst_node_id)
-                                .unwrap()
-                                .add_weight(1);
-                        }
-
-                        if inst_type == InstTy::phi {
-                            //y_index = Some(inst_node_id.clone());
-                            if block_type == BlockType::if_phi_right
-                                || block_type == BlockType::while_cont
-                            {
-                                // Make an edge between all nodes currently in live range, then add to live range
-                                for (_, node_id) in self.live_inst_map.iter() {
-                                    if None
-                                        == self
-                                            .interference_graph
-                                            .find_edge_undirected(inst_node_id, node_id.clone())
-                                    {
-                                        self.interference_graph.update_edge(
-                                            inst_node_id,
-                                            node_id.clone(),
-                                            String::from("black"),
-                                        );
-                                    }
-                                }
-
-                                self.live_inst_map.insert(y_inst_id, inst_node_id);
-                            } else {
-                                // Dont add anything other than if_phi_right. Then continue.
-                            }
-                        } else {
-                            // Make an edge between all nodes currently in live range, then add to live range
-                            for (_, node_id) in self.live_inst_map.iter() {
-                                if None
-                                    == self
-                                        .interference_graph
-                                        .find_edge_undirected(inst_node_id, node_id.clone())
-                                {
-                                    self.interference_graph.update_edge(
-                                        inst_node_id,
-                                        node_id.clone(),
-                                        String::from("black"),
-                                    );
-                                }
-                            }
-
-                            self.live_inst_map.insert(y_inst_id, inst_node_id);
-                        }
+                let mut body = HashSet::new();
+                body.insert(*header_id);
+                let mut work_list = vec![tail_id];
+                while let Some(node_id) = work_list.pop() {
+                    if body.insert(node_id) {
+                        work_list.extend(graph.neighbors_directed(node_id, Incoming));
                     }
                 }
+                loop_blocks.extend(body);
             }
         }
+        loop_blocks
     }
 
+    /// Merges phis with their operands wherever they do not interfere, so
+    /// no move is needed along that edge. An operand already merged into
+    /// another phi is left alone and moved into place instead.
     pub fn coalesce_phis(&mut self) {
-        let mut walkable_graph = self.interference_graph.clone();
-        //let mut secondary_walk_graph = self.interference_graph.clone();
-
-        // Collecting phis before did not seem to work very well,
-        // so manual collecting will be done here.
-        // First grab all phi instructions:
-        let mut phi_nodes = walkable_graph
+        let mut phi_nodes = self
+            .interference_graph
             .node_indices()
-            .filter_map(|node_id| {
-                let node_inst = self.interference_graph
-                    .node_weight(node_id)
-                    .unwrap()
-                    .get_inst_ref()[0].clone();
-                if InstTy::phi != node_inst.borrow().inst_type().clone() {
-                    return None
-                } else {
-                    let x_id;
-                    let y_id;
-
-                    match node_inst.borrow().clone_x_val() {
-                        Some(x_val) => {
-                            if let ValTy::op(x_op) = x_val.get_value() {
-                                x_id = Some(self.inst_node_map.get(&x_op.borrow().get_inst_num()).unwrap().clone());
-                            } else {
-                                x_id = None;
-                            }
-                        },
-                        None => x_id = None,
-                    }
-
-                    match node_inst.borrow().clone_y_val() {
-                        Some(y_val) => {
-                            if let ValTy::op(y_op) = y_val.get_value() {
-                                y_id = Some(self.inst_node_map.get(&y_op.borrow().get_inst_num()).unwrap().clone());
-                            } else {
-                                y_id = None;
-                            }
-                        },
-                        None => y_id = None,
-                    }
+            .filter(|node_id| {
+                let node_inst = self.interference_graph.node_weight(*node_id).unwrap().get_inst_ref()[0].clone();
+                let is_phi = InstTy::phi == node_inst.borrow().inst_type().clone();
+                is_phi
+            })
+            .collect::<Vec<NodeIndex>>();
+        phi_nodes.sort_by_key(|node_id| {
+            self.interference_graph.node_weight(*node_id).unwrap().get_inst_ref()[0]
+                .borrow()
+                .get_inst_num()
+        });
 
-                    return Some((node_id, (x_id,y_id)))
+        let mut merged_into: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+
+        for phi_id in phi_nodes {
+            let phi_inst = self.interference_graph.node_weight(phi_id).unwrap().get_inst_ref()[0].clone();
+            let phi_id = Self::representative(&merged_into, phi_id);
+
+            for val in [phi_inst.borrow().clone_x_val(), phi_inst.borrow().clone_y_val()].iter() {
+                let op_inst_id = match val {
+                    Some(val) => match val.get_value() {
+                        ValTy::op(op) => op.borrow().get_inst_num(),
+                        _ => continue,
+                    },
+                    None => continue,
+                };
+
+                let op_id = match self.inst_node_map.get(&op_inst_id) {
+                    Some(op_id) => *op_id,
+                    None => continue,
+                };
+
+                // Operands already merged somewhere keep their own register.
+                if op_id == phi_id || merged_into.contains_key(&op_id) {
+                    continue;
                 }
-            }).collect::<Vec<(NodeIndex, (Option<NodeIndex>,Option<NodeIndex>))>>();
-
-        let mut values_to_coalesce = phi_nodes
-            .iter()
-            .filter(|(
-                         phi_node_id, (
-                            x_node_id,
-                            y_node_id)
-                     )|
-                {
-                    // Check if there is an x node first
-                    if let Some(x_id) = x_node_id {
-                        // Check if there is an edge between x and phi
-                        if let Some(edge) = self.interference_graph
-                            .find_edge_undirected(phi_node_id.clone(),x_id.clone()) {
-                            return false
-                        }
-
-                        // Check if there is a y_node
-                        if let Some(y_id) = y_node_id {
-                            // Check if there is an edge between x and y
-                            if let Some(edge) = self.interference_graph
-                                .find_edge_undirected(x_id.clone(),y_id.clone()) {
-                                return false
-                            }
-                        }
-                    }
-
-                    // In case there is no x_node, run check for y_node again
-                    if let Some(y_id) = y_node_id {
-                        // Check for edge between phi and y
-                        if let Some(edge) = self.interference_graph
-                            .find_edge_undirected(phi_node_id.clone(), y_id.clone()) {
-                            return false
-                        }
-                    }
-
-                    true
-            }).map(|(op_node,(x_option,y_option))| {
-            (op_node.clone(),(x_option.clone(),y_option.clone()))
-        }).collect::<Vec<(NodeIndex,(Option<NodeIndex>,Option<NodeIndex>))>>();
-
-        let mut nodes_to_remove = Vec::new();
-
-        for (phi_id, (x_op_id, y_op_id)) in values_to_coalesce.clone() {
-            //println!("Phi to coalesce: {:?}: {:?} {:?}", self.interference_graph.node_weight(phi_id).unwrap().get_inst_ref()[0], x_op_id, y_op_id);
-            // check check to see if either value is a phi which will be skipped
-            if let Some(x_id) = x_op_id.clone() {
-                let x_op = self.interference_graph.node_weight(x_id.clone()).unwrap().get_inst_ref()[0].clone();
-                if InstTy::phi == x_op.borrow().inst_type().clone() {
-                    // Skip the phi instructions, do not coalesce (unless I have a ton of spare time later)
-                    continue
+                if self.interference_graph.find_edge_undirected(phi_id, op_id).is_some() {
+                    continue;
                 }
-            }
 
-            if let Some(y_id) = y_op_id.clone() {
-                let y_op = self.interference_graph.node_weight(y_id.clone()).unwrap().get_inst_ref()[0].clone();
-                if InstTy::phi == y_op.borrow().inst_type().clone() {
-                    // Skip the phi instructions, do not coalesce (unless I have a ton of spare time later)
-                    continue
+                for op_inst in self.interference_graph.node_weight(op_id).unwrap().get_inst_ref().clone() {
+                    self.interference_graph.node_weight_mut(phi_id).unwrap().coalesce_inst(op_inst);
                 }
-            }
-
-            if let Some(x_id) = x_op_id {
-                nodes_to_remove.push(x_id.clone());
-                let x_op = self.interference_graph.node_weight(x_id.clone()).unwrap().get_inst_ref()[0].clone();
-                self.interference_graph.node_weight_mut(phi_id).unwrap().coalesce_inst(x_op);
-
-                let mut neighbor_walker = self.interference_graph.neighbors_undirected(x_id.clone()).detach();
-
-                while let Some(neighbor_id) = neighbor_walker.next_node(&walkable_graph) {
-                    if let Some(edge) = self.interference_graph
-                        .find_edge_undirected(phi_id,neighbor_id) {
-                        // Dont add any edges
-                    } else {
-                        // No edge was found, add an edge
-                        self.interference_graph.update_edge(phi_id, neighbor_id, String::from("black"));
+                let op_weight = self.interference_graph.node_weight(op_id).unwrap().get_weight();
+                self.interference_graph.node_weight_mut(phi_id).unwrap().add_weight(op_weight);
+
+                let neighbors = self
+                    .interference_graph
+                    .neighbors_undirected(op_id)
+                    .collect::<Vec<NodeIndex>>();
+                for neighbor_id in neighbors {
+                    if neighbor_id != phi_id
+                        && self.interference_graph.find_edge_undirected(phi_id, neighbor_id).is_none()
+                    {
+                        self.interference_graph
+                            .update_edge(phi_id, neighbor_id, String::from("black"));
                     }
                 }
-            }
-
-            if let Some(y_id) = y_op_id {
-                nodes_to_remove.push(y_id.clone());
-                let y_op = self.interference_graph.node_weight(y_id.clone()).unwrap().get_inst_ref()[0].clone();
-                self.interference_graph.node_weight_mut(phi_id).unwrap().coalesce_inst(y_op);
 
-                let mut neighbor_walker = self.interference_graph.neighbors_undirected(y_id.clone()).detach();
-
-                while let Some(neighbor_id) = neighbor_walker.next_node(&walkable_graph) {
-                    if let Some(edge) = self.interference_graph
-                        .find_edge_undirected(phi_id,neighbor_id) {
-                        // Dont add any edges
-                    } else {
-                        // No edge was found, add an edge
-                        self.interference_graph.update_edge(phi_id, neighbor_id, String::from("black"));
-                    }
-                }
+                merged_into.insert(op_id, phi_id);
             }
         }
 
-        // Sort the node_ids and then reverse so that
-        // the highest numbers are removed first
-        nodes_to_remove.sort_by_key(|node_id| {
-            node_id.index()
-        });
+        // Remove the highest indices first, petgraph moves the last node
+        // into the hole left behind.
+        let mut nodes_to_remove = merged_into.keys().cloned().collect::<Vec<NodeIndex>>();
+        nodes_to_remove.sort_by_key(|node_id| node_id.index());
         nodes_to_remove.reverse();
 
         for node_id in nodes_to_remove {
             self.interference_graph.remove_node(node_id);
         }
     }
-}
 
-#[derive(PartialEq, Debug)]
-enum BlockType {
-    standard,
-    while_loop,
-    while_cont,
-    if_phi_left,
-    if_phi_right,
+    fn representative(merged_into: &HashMap<NodeIndex, NodeIndex>, node_id: NodeIndex) -> NodeIndex {
+        let mut node_id = node_id;
+        while let Some(parent_id) = merged_into.get(&node_id) {
+            node_id = *parent_id;
+        }
+        node_id
+    }
 }
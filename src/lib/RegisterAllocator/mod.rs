@@ -3,12 +3,9 @@ pub mod spill_handler;
 
 
 pub mod interference_graph;
-use self::interference_graph::{OpNode,RecurseTraverse};
+use self::interference_graph::{InterferenceBuilder, OpNode};
 
 use lib::IR::ir_manager::IRGraphManager;
-use std::ffi::OsString;
-use std::path::PathBuf;
-use std::fs::{self};
 use std::fmt::Write;
 
 
@@ -27,42 +24,35 @@ use lib::RegisterAllocator::color_graph::color;
 use lib::Optimizer::temp_value_manager::TempValManager;
 use lib::RegisterAllocator::spill_handler::SpillHandler;
 
+/// Colors the function at `root_node`, spilling until the colors fit.
+/// Returns the register of every instruction and the number of spill rounds.
 pub fn analyze_live_range(
     irgm: &mut IRGraphManager,
     temp_manager: &mut TempValManager,
     root_node: NodeIndex,
-    exit_nodes: Vec<NodeIndex>,
-    func_name: Option<String>,
-    path: PathBuf,
-    entry: OsString,
-) {
+    interference_dot: Option<&mut String>,
+) -> (HashMap<usize, RegisterAllocation>, usize) {
     // Create a new graph which will contain each instruction as a node,
     // and edges between instructions represent the interference.
-
-    let graph = irgm.graph_manager().get_mut_ref_graph().clone();
-    let dom_space = simple_fast(&graph, root_node.clone());
     let mut spill_handler = SpillHandler::new();
 
     let mut needs_coloring = true;
     let mut spilled_instructions = HashMap::new();
+    let mut spill_rounds = 0;
 
-    let mut round_count = 0;
+    // Maps each instruction number to the register it was colored with,
+    // coalesced phi operands share the register of their phi.
+    let mut register_map = HashMap::new();
+    let mut interference_dot = interference_dot;
 
     temp_manager.pull_temp_values(irgm.graph_manager(), root_node);
 
     while needs_coloring {
-        //println!("Round: {}", round_count);
-        round_count += 1;
-        let mut recurse_graph = RecurseTraverse::new(root_node, temp_manager,dom_space.clone());
-
-        for exit_node_id in exit_nodes.clone().iter() {
-            recurse_graph.set_starting_exit(exit_node_id.clone());
-            recurse_graph.recursive_traversal(irgm, &spilled_instructions);
-        }
-
-        recurse_graph.coalesce_phis();
+        let mut builder = InterferenceBuilder::new(root_node);
+        builder.build(irgm, &spilled_instructions);
+        builder.coalesce_phis();
 
-        let mut interference_graph = recurse_graph.get_interference_graph();
+        let mut interference_graph = builder.get_interference_graph();
 
         // TODO : Need to find a way to get consistent results as this is looping very occasionally on 24_b
         let color_result = color(&mut interference_graph);
@@ -71,31 +61,32 @@ pub fn analyze_live_range(
             Ok(_) => {
                 needs_coloring = false;
 
-                let mut dot_graph_path = entry.clone();
-                let mut file_name = path.to_str().unwrap().to_owned()
-                    + "/"
-                    + dot_graph_path.to_str().unwrap().trim_end_matches(".txt");
-
-                if let Some(func_name) = &func_name {
-                    file_name += func_name;
+                for op_node in interference_graph.node_weights_mut() {
+                    let register = op_node.get_register();
+                    for inst in op_node.get_inst_ref() {
+                        register_map.insert(
+                            inst.borrow().get_inst_num(),
+                            RegisterAllocation::allocate_register(register),
+                        );
+                    }
                 }
 
-                file_name += "_interference.dot";
-
-                let mut output = String::new();
-                write!(
-                    output,
-                    "{:?}",
-                    display::Dot::with_config(&interference_graph, &[display::Config::InterferenceGraph])
-                );
-                fs::write(file_name, output);
+                // The caller decides where (and if) the colored graph is written.
+                if let Some(output) = interference_dot.as_mut() {
+                    write!(
+                        output,
+                        "{:?}",
+                        display::Dot::with_config(&interference_graph, &[display::Config::InterferenceGraph])
+                    );
+                }
             },
             Err(spill_node) => {
                 //println!("Splitting instruction: {:?}", interference_graph.node_weight(spill_node)
                 //    .unwrap().get_inst_ref()[0]);
                 let inst_id = interference_graph.node_weight(spill_node)
                     .unwrap().get_inst_ref()[0].borrow().get_inst_num();
-                spill_handler.spill_value(irgm, temp_manager, inst_id.clone());
+                spill_handler.spill_value(irgm, temp_manager, root_node, inst_id.clone());
+                spill_rounds += 1;
                 if !spilled_instructions.contains_key(&inst_id) {
                     spilled_instructions.insert(inst_id, 1);
                 } else {
@@ -106,6 +97,8 @@ pub fn analyze_live_range(
             }
         }
     }
+
+    (register_map, spill_rounds)
 }
 
 #[derive(Clone)]
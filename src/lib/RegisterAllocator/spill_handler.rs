@@ -1,9 +1,14 @@
 use super::{IRGraphManager, TempValManager};
-use lib::IR::ir::{Value, ValTy, InstTy};
+use lib::Graph::{control_flow_graph, is_phi_x_side};
+use lib::IR::ir::{Value, ValTy, InstTy, Op};
+use lib::IR::verify::is_branch;
 use lib::RegisterAllocator::RegisterAllocation;
 use std::cell::RefCell;
 use std::rc::Rc;
 use lib::IR::address_manager::AddressManager;
+use petgraph::algo::dominators::simple_fast;
+use petgraph::prelude::NodeIndex;
+use petgraph::Incoming;
 
 pub struct SpillHandler {
     current_spill_counter: usize
@@ -18,6 +23,7 @@ impl SpillHandler {
         &mut self,
         irgm: &mut IRGraphManager,
         temp_manager: &mut TempValManager,
+        root_node: NodeIndex,
         inst_id: usize
     ) {
         // Grab definition block from temp_manager
@@ -96,7 +102,11 @@ impl SpillHandler {
 
             for (position, inst) in inst_list.iter().enumerate() {
                 let inst_block = inst.borrow().get_inst_block();
-                if inst.borrow().get_inst_num() == active_use.borrow().inst_num() {
+                if inst.borrow().get_inst_num() == active_use.borrow().inst_num()
+                    && inst.borrow().inst_type().clone() == InstTy::phi
+                {
+                    self.reload_for_phi(irgm, root_node, inst_id, &spill_addr_value, inst, inst_use_node_id);
+                } else if inst.borrow().get_inst_num() == active_use.borrow().inst_num() {
                     let fp_address = Value::new(ValTy::adr(irgm.address_manager().get_frame_pointer()));
                     let add_op = irgm.build_op_x_y_in_block(
                         fp_address,
@@ -130,4 +140,83 @@ impl SpillHandler {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// A phi reads its operands on the way in from each parent, so the
+    /// value is reloaded at the end of the parents that hand it over.
+    fn reload_for_phi(
+        &mut self,
+        irgm: &mut IRGraphManager,
+        root_node: NodeIndex,
+        inst_id: usize,
+        spill_addr_value: &Value,
+        phi: &Rc<RefCell<Op>>,
+        phi_node_id: NodeIndex,
+    ) {
+        let graph = control_flow_graph(irgm.graph_manager_ref().get_ref_graph());
+        let dom_space = simple_fast(&graph, root_node);
+
+        let mut parents = Vec::new();
+        for parent_id in graph.neighbors_directed(phi_node_id, Incoming) {
+            if !parents.contains(&parent_id) {
+                parents.push(parent_id);
+            }
+        }
+
+        for parent_id in parents {
+            let is_x_side = is_phi_x_side(&graph, &dom_space, parent_id, phi_node_id);
+            let operand = if is_x_side {
+                phi.borrow().clone_x_val()
+            } else {
+                phi.borrow().clone_y_val()
+            };
+
+            let is_spilled_value = match operand.map(|operand| operand.clone_value()) {
+                Some(ValTy::op(op)) => op.borrow().get_inst_num() == inst_id,
+                _ => false,
+            };
+            if !is_spilled_value {
+                continue;
+            }
+
+            // The reload goes ahead of the branch that leaves the parent.
+            let parent_block = graph.node_weight(parent_id).unwrap().get_node_id();
+            let inst_list = graph.node_weight(parent_id).unwrap().get_data_ref().get_inst_list_ref();
+            let position = match inst_list.last() {
+                Some(last_inst) if is_branch(last_inst.borrow().inst_type()) => inst_list.len() - 1,
+                _ => inst_list.len(),
+            };
+
+            let fp_address = Value::new(ValTy::adr(irgm.address_manager().get_frame_pointer()));
+            let add_op = irgm.build_op_x_y_in_block(
+                fp_address,
+                spill_addr_value.clone(),
+                InstTy::sadd,
+                parent_block
+            );
+            let storage_location = irgm.graph_manager()
+                .insert_instruction_in_node(
+                    position,
+                    add_op,
+                    &parent_id
+                );
+
+            let load_op = irgm.build_op_y_in_block(
+                storage_location,
+                InstTy::sload,
+                parent_block
+            );
+            let load_value = irgm.graph_manager()
+                .insert_instruction_in_node(
+                    position + 1,
+                    load_op,
+                    &parent_id
+                );
+
+            if is_x_side {
+                phi.borrow_mut().update_x_val(load_value);
+            } else {
+                phi.borrow_mut().update_y_val(load_value);
+            }
+        }
+    }
+}
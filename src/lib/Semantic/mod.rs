@@ -0,0 +1,278 @@
+//  Checks run over the AST once it parses and before any IR is built, so
+//problems in the program come back as diagnostics instead of panics in the
+//IR construction.
+pub mod semantic_error;
+
+use std::collections::HashMap;
+
+use lib::Parser::AST::computation::Comp;
+use lib::Parser::AST::designator::Designator;
+use lib::Parser::AST::expression::{ExpList, Expression};
+use lib::Parser::AST::factor::FactorType;
+use lib::Parser::AST::func_body::{FuncBody, Stmt};
+use lib::Parser::AST::func_call::FuncCall;
+use lib::Parser::AST::func_decl::FuncDecl;
+use lib::Parser::AST::ident::Ident;
+use lib::Parser::AST::relation::Relation;
+use lib::Parser::AST::term::TermList;
+use lib::Parser::AST::var_decl::VarDecl;
+
+use self::semantic_error::SemanticError;
+
+/// Checks `comp`, returning every problem found in it if there were any.
+pub fn check(comp: &Comp) -> Result<(), Vec<SemanticError>> {
+    let mut checker = Checker::new();
+    checker.check_comp(comp);
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FuncSig {
+    param_count: usize,
+    returns_value: bool,
+}
+
+struct Checker {
+    // Variable name to its number of array dimensions, 0 for scalars.
+    globals: HashMap<String, usize>,
+    // Parameters and locals of the function being checked, None in main.
+    locals: Option<HashMap<String, usize>>,
+    // Built-ins and every function declared in the program.
+    functions: HashMap<String, FuncSig>,
+    errors: Vec<SemanticError>,
+}
+
+impl Checker {
+    fn new() -> Self {
+        let mut functions = HashMap::new();
+        for (name, param_count, returns_value) in
+            &[("InputNum", 0, true), ("OutputNum", 1, false), ("OutputNewLine", 0, false)]
+        {
+            functions.insert(
+                String::from(*name),
+                FuncSig {
+                    param_count: *param_count,
+                    returns_value: *returns_value,
+                },
+            );
+        }
+
+        Checker {
+            globals: HashMap::new(),
+            locals: None,
+            functions,
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, message: String, ident: &Ident) {
+        self.errors.push(SemanticError::new(message, ident.get_span()));
+    }
+
+    fn check_comp(&mut self, comp: &Comp) {
+        let (var_decls, func_decls, body) = comp.get_value();
+
+        let mut globals = HashMap::new();
+        self.declare_vars(&var_decls, &mut globals);
+        self.globals = globals;
+
+        // Functions can be called from anywhere, including before they
+        // are declared.
+        for func_decl in &func_decls {
+            self.declare_func(func_decl);
+        }
+
+        for func_decl in &func_decls {
+            self.check_func_decl(func_decl);
+        }
+
+        self.locals = None;
+        self.check_body(&body);
+    }
+
+    fn declare_func(&mut self, func_decl: &FuncDecl) {
+        let (func_ident, _, body) = func_decl.get_value();
+        let (name, params) = func_ident.get_value();
+
+        let sig = FuncSig {
+            param_count: params.map_or(0, |params| params.get_value().len()),
+            returns_value: body.has_return(),
+        };
+        if self.functions.insert(name.get_value(), sig).is_some() {
+            let message = format!("function '{}' is already declared", name.get_value());
+            self.error(message, &name);
+        }
+    }
+
+    fn check_func_decl(&mut self, func_decl: &FuncDecl) {
+        let (func_ident, var_decls, body) = func_decl.get_value();
+        let params = func_ident
+            .get_value()
+            .1
+            .map(|params| params.get_value())
+            .unwrap_or_default();
+
+        let mut locals = HashMap::new();
+        for param in &params {
+            self.declare(param, 0, &mut locals);
+        }
+        self.declare_vars(&var_decls, &mut locals);
+
+        self.locals = Some(locals);
+        self.check_body(&body);
+    }
+
+    fn declare_vars(&mut self, var_decls: &[VarDecl], scope: &mut HashMap<String, usize>) {
+        for var_decl in var_decls {
+            match var_decl.get_value() {
+                (Some(var), _) => {
+                    for ident in var.get_value() {
+                        self.declare(&ident, 0, scope);
+                    }
+                }
+                (None, Some(array)) => {
+                    let (dims, idents) = array.get_value();
+                    for ident in idents {
+                        self.declare(&ident, dims.len(), scope);
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn declare(&mut self, ident: &Ident, dims: usize, scope: &mut HashMap<String, usize>) {
+        if scope.insert(ident.get_value(), dims).is_some() {
+            let message = format!("'{}' is already declared", ident.get_value());
+            self.error(message, ident);
+        }
+    }
+
+    fn check_body(&mut self, body: &FuncBody) {
+        for stmt in body.get_value() {
+            match stmt {
+                Stmt::assignment(assignment) => {
+                    let (designator, expression) = assignment.get_value();
+                    self.check_designator(&designator);
+                    self.check_expression(&expression);
+                }
+                Stmt::if_stmt(if_stmt) => {
+                    let (relation, if_body, else_body) = if_stmt.get_value();
+                    self.check_relation(&relation);
+                    self.check_body(&if_body);
+                    if let Some(else_body) = else_body {
+                        self.check_body(&else_body);
+                    }
+                }
+                Stmt::while_stmt(while_stmt) => {
+                    let (relation, body) = while_stmt.get_value();
+                    self.check_relation(&relation);
+                    self.check_body(&body);
+                }
+                Stmt::funcCall(func_call) => self.check_call(&func_call, false),
+                Stmt::return_stmt(return_stmt) => {
+                    // Main has no caller to hand the value to.
+                    if self.locals.is_none() {
+                        let message = String::from("'return' can only be used in a function");
+                        self.errors.push(SemanticError::new(message, return_stmt.get_span()));
+                    }
+                    self.check_expression(&return_stmt.get_value());
+                }
+            }
+        }
+    }
+
+    fn check_relation(&mut self, relation: &Relation) {
+        let (left, _, right) = relation.get_value();
+        self.check_expression(&left);
+        self.check_expression(&right);
+    }
+
+    fn check_expression(&mut self, expression: &Expression) {
+        for exp in expression.get_value() {
+            if let ExpList::term(term) = exp {
+                for term_part in term.get_value() {
+                    if let TermList::factor(factor) = term_part {
+                        match factor.get_value() {
+                            FactorType::desig(designator) => self.check_designator(&designator),
+                            FactorType::func_call(func_call) => self.check_call(&func_call, true),
+                            FactorType::expr(expression) => self.check_expression(&expression),
+                            FactorType::num(_) => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_designator(&mut self, designator: &Designator) {
+        let (ident, indices) = designator.get_value();
+        let name = ident.get_value();
+
+        let dims = match &self.locals {
+            Some(locals) if locals.contains_key(&name) => locals.get(&name).cloned(),
+            _ => self.globals.get(&name).cloned(),
+        };
+
+        match dims {
+            None => {
+                let message = format!("'{}' is not declared", name);
+                self.error(message, &ident);
+            }
+            Some(0) if !indices.is_empty() => {
+                let message = format!("'{}' is not an array", name);
+                self.error(message, &ident);
+            }
+            Some(dims) if dims != indices.len() => {
+                let message = format!(
+                    "array '{}' has {} dimension(s) but {} index(es) were given",
+                    name,
+                    dims,
+                    indices.len()
+                );
+                self.error(message, &ident);
+            }
+            Some(_) => {}
+        }
+
+        for index in &indices {
+            self.check_expression(index);
+        }
+    }
+
+    fn check_call(&mut self, func_call: &FuncCall, needs_value: bool) {
+        let (ident, args) = func_call.get_value();
+        let name = ident.get_value();
+
+        match self.functions.get(&name).cloned() {
+            None => {
+                let message = format!("function '{}' is not declared", name);
+                self.error(message, &ident);
+            }
+            Some(sig) => {
+                if sig.param_count != args.len() {
+                    let message = format!(
+                        "function '{}' takes {} argument(s) but {} were given",
+                        name,
+                        sig.param_count,
+                        args.len()
+                    );
+                    self.error(message, &ident);
+                }
+                if needs_value && !sig.returns_value {
+                    let message = format!("'{}' does not return a value", name);
+                    self.error(message, &ident);
+                }
+            }
+        }
+
+        for arg in &args {
+            self.check_expression(arg);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+use lib::Lexer::token::Span;
+
+/// Problem with a program that parsed fine but can't be compiled, such as
+/// a variable that was never declared or a call with the wrong arguments.
+#[derive(Debug, Clone)]
+pub struct SemanticError {
+    message: String,
+    span: Span,
+}
+
+impl SemanticError {
+    pub fn new(message: String, span: Span) -> Self {
+        SemanticError { message, span }
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
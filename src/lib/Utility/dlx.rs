@@ -3,7 +3,12 @@
 
 // All variables and methods are realized as class variables/methods which
 // means that just one processor can be emulated at a time.
+use std::fmt;
+use std::fmt::Write;
 use std::io;
+use std::io::{BufRead, Cursor};
+
+use lib::Utility::DEFAULT_INSTRUCTION_BUDGET;
 
 pub struct DLX {
     // processor state variables
@@ -18,14 +23,135 @@ pub struct DLX {
     // emulated memory
      // bytes in memory (divisible by 4)
     M: [i32; (MemSize/4) as usize],
+
+    // everything written by WRD, WRH and WRL during execute
+    output: String,
+    instruction_budget: Option<u64>,
+
+    // RDI reads from stdin unless given another source, output can also
+    // be forwarded to a writer as it is produced.
+    input: Option<Box<dyn BufRead>>,
+    output_writer: Option<Box<dyn io::Write>>,
+}
+
+/// State of the machine after the program returned to address 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitState {
+    pub output: String,
+    pub registers: Vec<i32>,
+    pub instructions: u64,
+}
+
+/// A fault raised while executing, along with where it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlxError {
+    pub kind: DlxErrorKind,
+    pub pc: i32,
+    pub registers: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DlxErrorKind {
+    /// Bug 1, shift amount outside of -31..31.
+    IllegalShift(i32),
+    /// Bug 1, the word at PC does not hold a known instruction.
+    UnknownOpcode(i32),
+    /// Bug 1, execution ran past the end of the loaded program.
+    DroppedOffEnd,
+    /// Bug 14, CHK found R[reg] outside of 0..bound.
+    CheckFailed { reg: i32, value: i32, bound: i32 },
+    /// Bugs 40-49, a jump left memory. The bug number is the jump's opcode.
+    BranchOutOfRange { opcode: i32, target: i32 },
+    MemoryOutOfRange(i32),
+    DivisionByZero,
+    InvalidInput(String),
+    InputExhausted,
+    BudgetExhausted(u64),
+    /// An operand does not fit into the F1, F2 or F3 format it is encoded in.
+    IllegalOperands { format: u8 },
+    /// Only WRL is assembled without arguments.
+    MissingOperands,
+    /// The program, along with the ERR word after it, is larger than memory.
+    ProgramTooLarge(usize),
+}
+
+impl DlxErrorKind {
+    /// Number used by the original DLX emulator for this fault, if it had one.
+    pub fn bug_number(&self) -> Option<u32> {
+        match self {
+            DlxErrorKind::IllegalShift(_)
+            | DlxErrorKind::UnknownOpcode(_)
+            | DlxErrorKind::DroppedOffEnd => Some(1),
+            DlxErrorKind::CheckFailed { .. } => Some(14),
+            DlxErrorKind::BranchOutOfRange { opcode, .. } => Some(*opcode as u32),
+            _ => None,
+        }
+    }
+}
+
+impl DlxError {
+    // Assembling and loading happen before there is any machine state.
+    fn outside_execute(kind: DlxErrorKind) -> Self {
+        DlxError {
+            kind,
+            pc: 0,
+            registers: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for DlxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            DlxErrorKind::IllegalOperands { .. } | DlxErrorKind::MissingOperands => write!(f, "DLX.assemble: ")?,
+            DlxErrorKind::ProgramTooLarge(_) => write!(f, "DLX.load: ")?,
+            _ => write!(f, "DLX.Execute: {}: ", self.pc * 4)?,
+        }
+        match &self.kind {
+            DlxErrorKind::IllegalShift(shift) => {
+                write!(f, "Illegal value {} of operand c or register c!", shift)?
+            }
+            DlxErrorKind::UnknownOpcode(op) => write!(f, "Unknown opcode {} encountered!", op)?,
+            DlxErrorKind::DroppedOffEnd => write!(f, "Program dropped off the end!")?,
+            DlxErrorKind::CheckFailed { reg, value, bound } => {
+                write!(f, "R[{}] == {} is not in 0..{}", reg, value, bound)?
+            }
+            DlxErrorKind::BranchOutOfRange { target, .. }
+            | DlxErrorKind::MemoryOutOfRange(target) => {
+                write!(f, "{} is no address in memory (0..{}).", target, MemSize)?
+            }
+            DlxErrorKind::DivisionByZero => write!(f, "Division by zero!")?,
+            DlxErrorKind::InvalidInput(input) => write!(f, "RDI expects an integer, got {:?}.", input)?,
+            DlxErrorKind::InputExhausted => write!(f, "RDI found no more input.")?,
+            DlxErrorKind::BudgetExhausted(executed) => {
+                write!(f, "Stopped after {} instructions.", executed)?
+            }
+            DlxErrorKind::IllegalOperands { format: 3 } => {
+                write!(f, "Operand for F3 Format is referencing non-existent memory location.")?
+            }
+            DlxErrorKind::IllegalOperands { format } => write!(f, "Illegal Operand(s) for F{} Format.", format)?,
+            DlxErrorKind::MissingOperands => write!(f, "the only instruction without arguments is WRL!")?,
+            DlxErrorKind::ProgramTooLarge(words) => {
+                write!(f, "{} words do not fit into memory ({} bytes).", words, MemSize)?
+            }
+        }
+
+        match self.kind.bug_number() {
+            Some(bug_num) => write!(f, " (Bug number: {})", bug_num),
+            None => Ok(()),
+        }
+    }
 }
 
 impl DLX {
     pub fn new() -> Self {
-        DLX{ R:[0; 32], PC: 0, op: 0, a: 0, b: 0, c: 0, format: 0, M:[0; (MemSize/4) as usize] }
+        DLX{ R:[0; 32], PC: 0, op: 0, a: 0, b: 0, c: 0, format: 0, M:[0; (MemSize/4) as usize], output: String::new(), instruction_budget: Some(DEFAULT_INSTRUCTION_BUDGET), input: None, output_writer: None }
     }
 
-    pub fn load(&mut self, program: &mut [i32]) {
+    pub fn load(&mut self, program: &[i32]) -> Result<(), DlxError> {
+        if program.len() >= self.M.len() {
+            return Err(DlxError::outside_execute(DlxErrorKind::ProgramTooLarge(program.len())));
+        }
         let mut i = 0;
         for OpCode in program {
             self.M[i] = *OpCode;
@@ -33,19 +159,62 @@ impl DLX {
         }
         self.M[i] = -1;  // set first opcode of first instruction after program
         // to ERR in order to detect 'fall off the edge' errors
+        Ok(())
+    }
+
+    /// Limits how many instructions `execute` may run before giving up,
+    /// `None` lets a program run for as long as it likes.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
     }
 
-    // TODO : Add return type for error handling
-    pub fn execute(&mut self) {
+    /// Reads RDI values from `reader`, one integer per line, instead of stdin.
+    pub fn set_input_reader<R: BufRead + 'static>(&mut self, reader: R) {
+        self.input = Some(Box::new(reader));
+    }
+
+    /// Queues up the values RDI will read, in order.
+    pub fn set_input(&mut self, values: &[i32]) {
+        let mut lines = String::new();
+        for value in values {
+            writeln!(lines, "{}", value);
+        }
+        self.set_input_reader(Cursor::new(lines.into_bytes()));
+    }
+
+    /// Forwards output to `writer` as it is produced, it is still captured as well.
+    pub fn set_output_writer<W: io::Write + 'static>(&mut self, writer: W) {
+        self.output_writer = Some(Box::new(writer));
+    }
+
+    /// Everything written by the program so far, still available after a fault.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Runs the loaded program until it returns to address 0.
+    pub fn execute(&mut self) -> Result<ExitState, DlxError> {
         let mut origc = 0;
+        let mut executed: u64 = 0;
         for i in 0..32 {
             self.R[i] = 0;
         }
         self.PC = 0;
         self.R[30] = (MemSize - 1) as i32;
+        self.output = String::new();
 
         loop {
+            if let Some(budget) = self.instruction_budget {
+                if executed >= budget {
+                    return Err(self.fault(DlxErrorKind::BudgetExhausted(executed)));
+                }
+            }
+            executed += 1;
+
             self.R[0] = 0;
+            if !(0..MemSize/4).contains(&self.PC) {
+                return Err(self.fault(DlxErrorKind::DroppedOffEnd));
+            }
             let instructionWord : i32 = self.M[self.PC as usize];
             self.disassem(instructionWord); // initializes op, a, b, c
 
@@ -56,29 +225,38 @@ impl DLX {
             }
             match self.op {
                 ADD | ADDI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] + self.c;
+                    self.R[self.a as usize] = self.R[self.b as usize].wrapping_add(self.c);
                 }
                 SUB | SUBI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] - self.c;
+                    self.R[self.a as usize] = self.R[self.b as usize].wrapping_sub(self.c);
                 }
                 CMP | CMPI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] - self.c; // can not create overflow
-                    if self.R[self.a as usize] < 0 {
+                    // compared directly so large operands can not overflow
+                    let value = self.R[self.b as usize];
+                    if value < self.c {
                         self.R[self.a as usize] = -1;
                     }
-                    else if self.R[self.a as usize] > 0 {
+                    else if value > self.c {
                         self.R[self.a as usize] = 1;
                     }
-                    // do nothing if R[a] == 0;
+                    else {
+                        self.R[self.a as usize] = 0;
+                    }
                 }
                 MUL | MULI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] * self.c;
+                    self.R[self.a as usize] = self.R[self.b as usize].wrapping_mul(self.c);
                 }
                 DIV | DIVI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] / self.c;
+                    if self.c == 0 {
+                        return Err(self.fault(DlxErrorKind::DivisionByZero));
+                    }
+                    self.R[self.a as usize] = self.R[self.b as usize].wrapping_div(self.c);
                 }
                 MOD | MODI => {
-                    self.R[self.a as usize] = self.R[self.b as usize] % self.c;
+                    if self.c == 0 {
+                        return Err(self.fault(DlxErrorKind::DivisionByZero));
+                    }
+                    self.R[self.a as usize] = self.R[self.b as usize].wrapping_rem(self.c);
                 }
                 OR | ORI => {
                     self.R[self.a as usize] = self.R[self.b as usize] | self.c;
@@ -94,125 +272,103 @@ impl DLX {
                 }
                 LSH | LSHI => {
                     if (self.c < -31) || (self.c > 31) {
-                        println!("Illegal value {} of operand c or register c!", self.c);
-                        self.bug(1);
+                        return Err(self.fault(DlxErrorKind::IllegalShift(self.c)));
                     }
-                    if self.c < 0 { self.R[self.a as usize] = (self.R[self.b as usize] as usize >> -self.c as usize) as i32 }
+                    if self.c < 0 { self.R[self.a as usize] = ((self.R[self.b as usize] as u32) >> -self.c) as i32 }
                     else {self.R[self.a as usize] = self.R[self.b as usize] << self.c }
                 }
                 ASH | ASHI => {
                     if (self.c < -31) || (self.c > 31) {
-                        println!("DLX.Execute: Illegal value {} of operand c or register c!", self.c);
-                        self.bug(1);
+                        return Err(self.fault(DlxErrorKind::IllegalShift(self.c)));
                     }
                     if self.c < 0 { self.R[self.a as usize] = self.R[self.b as usize] >> -self.c }
                         else {self.R[self.a as usize] = self.R[self.b as usize] << self.c }
                 }
                 CHK | CHKI => {
-                    if self.R[self.a as usize] < 0 {
-                        println!("DLX.Execute: {}: R[{}] == {} < 0", self.PC*4, self.a, self.R[self.a as usize]);
-                        self.bug(14);
-                    } else if self.R[self.a as usize] >= self.c {
-                        println!("DLX.Execute: {}: R[{}] == {} >= {}", self.PC*4, self.a, self.R[self.a as usize], self.c);
-                        self.bug(14);
+                    let value = self.R[self.a as usize];
+                    if !(0..self.c).contains(&value) {
+                        return Err(self.fault(DlxErrorKind::CheckFailed { reg: self.a, value, bound: self.c }));
                     }
                 }
                 LDW | LDX => {
                     // remember: c == R[origc] because of F2 format
-                    self.R[self.a as usize] = self.M[((self.R[self.b as usize] + self.c) / 4) as usize];
+                    let word = self.word_index(self.R[self.b as usize].wrapping_add(self.c))?;
+                    self.R[self.a as usize] = self.M[word];
                 }
                 STW | STX => {
                     // remember: c == R[origc] because of F2 format
-                    self.M[((self.R[self.b as usize] + self.c) / 4) as usize] = self.R[self.a as usize];
+                    let word = self.word_index(self.R[self.b as usize].wrapping_add(self.c))?;
+                    self.M[word] = self.R[self.a as usize];
                 }
                 POP => {
-                    self.R[self.a as usize] = self.M[(self.R[self.b as usize] / 4) as usize];
-                    self.R[self.b as usize] = self.R[self.b as usize] + self.c;
+                    let word = self.word_index(self.R[self.b as usize])?;
+                    self.R[self.a as usize] = self.M[word];
+                    self.R[self.b as usize] = self.R[self.b as usize].wrapping_add(self.c);
                 }
                 PSH => {
-                    self.R[self.b as usize] = self.R[self.b as usize] + self.c;
-                    self.M[(self.R[self.b as usize] / 4) as usize] = self.R[self.a as usize];
-                }
-                BEQ => {
-                    if self.R[self.a as usize] == 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(40);
-                    }
-                }
-                BNE => {
-                    if self.R[self.a as usize] != 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(41);
-                    }
-                }
-                BLT => {
-                    if self.R[self.a as usize] < 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(42);
-                    }
-                }
-                BGE => {
-                    if self.R[self.a as usize] >= 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(42);
-                    }
-                }
-                BLE => {
-                    if self.R[self.a as usize] <= 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(42);
-                    }
-                }
-                BGT => {
-                    if self.R[self.a as usize] > 0 { nextPC = self.PC + self.c; }
-                    if (nextPC < 0) || (nextPC > MemSize/4) {
-                        println!("{} is no address in memory (0..{}).", 4*nextPC, MemSize);
-                        self.bug(42);
-                    }
+                    self.R[self.b as usize] = self.R[self.b as usize].wrapping_add(self.c);
+                    let word = self.word_index(self.R[self.b as usize])?;
+                    self.M[word] = self.R[self.a as usize];
+                }
+                BEQ | BNE | BLT | BGE | BLE | BGT => {
+                    let value = self.R[self.a as usize];
+                    let is_taken = match self.op {
+                        BEQ => value == 0,
+                        BNE => value != 0,
+                        BLT => value < 0,
+                        BGE => value >= 0,
+                        BLE => value <= 0,
+                        _ => value > 0,
+                    };
+                    if is_taken { nextPC = self.PC + self.c; }
+                    self.check_target(nextPC)?;
                 }
                 BSR => {
                     self.R[31] = (self.PC + 1) * 4;
                     nextPC = self.PC + self.c;
+                    self.check_target(nextPC)?;
                 }
                 JSR => {
                     self.R[31] = (self.PC + 1) * 4;
                     nextPC = self.c / 4;
+                    self.check_target(nextPC)?;
                 }
                 RET => {
-                    if origc == 0 { return; } // remember: c==R[origc]
-                    if (self.c < 0) || (self.c > MemSize) {
-                        println!("{} is no address in memory (0..{}).", self.c, MemSize);
-                        self.bug(49);
+                    if origc == 0 { // remember: c==R[origc]
+                        return Ok(ExitState {
+                            output: self.output.clone(),
+                            registers: self.R.to_vec(),
+                            instructions: executed,
+                        });
+                    }
+                    if !(0..MemSize).contains(&self.c) {
+                        return Err(self.fault(DlxErrorKind::BranchOutOfRange { opcode: RET, target: self.c }));
                     }
                     nextPC = self.c / 4;
                 }
                 RDI => {
-                    println!("?:");
-                    let mut input = String::new();
-                    io::stdin().read_line(&mut input);
-                    self.R[self.a as usize] = input.parse().unwrap();
+                    let input = self.read_input()?;
+                    match input.trim().parse() {
+                        Ok(value) => self.R[self.a as usize] = value,
+                        Err(_) => return Err(self.fault(DlxErrorKind::InvalidInput(input.trim().to_owned()))),
+                    }
                 }
                 WRD => {
-                    println!("{}  ", self.R[self.b as usize]);
+                    let text = format!("{}  ", self.R[self.b as usize]);
+                    self.write_output(&text);
                 }
                 WRH => {
-                    println!("0x{:x}  ", self.R[self.b as usize]);
+                    let text = format!("0x{:x}  ", self.R[self.b as usize]);
+                    self.write_output(&text);
                 }
                 WRL => {
-                    println!();
+                    self.write_output("\n");
                 }
                 ERR => {
-                    println!("Program dropped off the end!");
-                    self.bug(1);
+                    return Err(self.fault(DlxErrorKind::DroppedOffEnd));
                 }
                 _ => {
-                    println!("DLX.Execute: Unknown opcode encountered!");
-                    self.bug(1);
+                    return Err(self.fault(DlxErrorKind::UnknownOpcode(self.op)));
                 }
             }
 
@@ -220,8 +376,55 @@ impl DLX {
         }
     }
 
+    fn read_input(&mut self) -> Result<String, DlxError> {
+        let mut input = String::new();
+        let read_result = match &mut self.input {
+            Some(reader) => reader.read_line(&mut input),
+            None => {
+                println!("?:");
+                io::stdin().read_line(&mut input)
+            }
+        };
+
+        match read_result {
+            Ok(0) | Err(_) => Err(self.fault(DlxErrorKind::InputExhausted)),
+            Ok(_) => Ok(input),
+        }
+    }
+
+    fn write_output(&mut self, text: &str) {
+        self.output.push_str(text);
+        if let Some(writer) = &mut self.output_writer {
+            writer.write_all(text.as_bytes());
+            writer.flush();
+        }
+    }
+
+    /// Converts a byte address into an index of M, faulting when it is outside memory.
+    fn word_index(&self, address: i32) -> Result<usize, DlxError> {
+        if !(0..MemSize).contains(&address) {
+            return Err(self.fault(DlxErrorKind::MemoryOutOfRange(address)));
+        }
+        Ok((address / 4) as usize)
+    }
+
+    fn check_target(&self, target_pc: i32) -> Result<(), DlxError> {
+        if !(0..MemSize/4).contains(&target_pc) {
+            return Err(self.fault(DlxErrorKind::BranchOutOfRange { opcode: self.op, target: 4*target_pc }));
+        }
+        Ok(())
+    }
+
+    fn fault(&self, kind: DlxErrorKind) -> DlxError {
+        DlxError {
+            kind,
+            pc: self.PC,
+            registers: self.R.to_vec(),
+        }
+    }
+
     pub fn disassem(&mut self, instructionWord: i32) {
-        self.op = instructionWord >> 26 as usize;    // without sign extension
+        self.op = (instructionWord >> 26) & 0x3F;    // without sign extension
         match self.op {
             // F1 Format
             BSR | RDI | WRD | WRH | WRL | CHKI | BEQ |
@@ -229,9 +432,9 @@ impl DLX {
             MULI | DIVI | MODI | CMPI | ORI | ANDI | BICI |
             XORI | LSHI | ASHI | LDW | POP | STW | PSH => {
                 self.format = 1;
-                self.a = (instructionWord >> 21 as usize) & 0x1F;
-                self.b = (instructionWord >> 16 as usize) & 0x1F;
-                self.c = (instructionWord as u8) as i32;
+                self.a = (instructionWord >> 21) & 0x1F;
+                self.b = (instructionWord >> 16) & 0x1F;
+                self.c = (instructionWord as i16) as i32;   // with sign extension
             }
             // F2 Format
              RET | CHK | ADD | SUB | MUL |
@@ -239,8 +442,8 @@ impl DLX {
              BIC | XOR | LSH | ASH | LDX |
              STX => {
                  self.format = 2;
-                 self.a = (instructionWord >> 21 as usize) & 0x1F;
-                 self.b = (instructionWord >> 16 as usize) & 0x1F;
+                 self.a = (instructionWord >> 21) & 0x1F;
+                 self.b = (instructionWord >> 16) & 0x1F;
                  self.c = instructionWord & 0x1F;
              }
             // F3 Format
@@ -251,7 +454,8 @@ impl DLX {
                 self.c = instructionWord & 0x3FFFFFF;
             }
             _ => {
-                println!("Illegal instruction! ({})", self.PC);
+                // left for execute to report as an unknown opcode
+                self.format = 0;
             }
         }
     }
@@ -313,10 +517,9 @@ impl DLX {
         }
     }
 
-    pub fn assemble(op: i32, arg1: i32, arg2: i32, mut arg3: i32) -> i32 {
+    pub fn assemble(op: i32, arg1: i32, arg2: i32, arg3: i32) -> Result<i32, DlxError> {
         if (arg1 == 0) & (arg2 == 0) & (arg3 == 0) & (op != WRL) {
-            println!("DLX.assemble: the only instruction without arguments is WRL!");
-            return -1;
+            return Err(DlxError::outside_execute(DlxErrorKind::MissingOperands));
         } else if (arg1 == 0) & (arg2 == 0) & (arg3 == 0) & (op == WRL)  {
             return DLX::F1(op,0,0,0);
         }
@@ -342,39 +545,32 @@ impl DLX {
 
             // F3 Format
             JSR => DLX::F3(op,arg1),
-            _ => return -1,
+            _ => Err(DlxError::outside_execute(DlxErrorKind::UnknownOpcode(op))),
         }
     }
 
-    pub fn F1(op: i32, a: i32, b: i32, mut c: i32) -> i32 {
+    pub fn F1(op: i32, a: i32, b: i32, mut c: i32) -> Result<i32, DlxError> {
         if c < 0 {
-            c ^= 0xFFFF0000;
+            c ^= 0xFFFF0000u32 as i32;
         }
         if (a & !0x1F)|(b & !0x1F)|(c & !0xFFFF) != 0 {
-            println!("Illegal Operand(s) for F1 Format.");
-            return -1;
+            return Err(DlxError::outside_execute(DlxErrorKind::IllegalOperands { format: 1 }));
         }
-        return op << 26 | a << 21 | b << 16 | c;
+        Ok(op << 26 | a << 21 | b << 16 | c)
     }
 
-    pub fn F2(op: i32, a: i32, b: i32, c: i32) -> i32 {
+    pub fn F2(op: i32, a: i32, b: i32, c: i32) -> Result<i32, DlxError> {
         if (a & !0x1F)|(b & !0x1F)|(c & !0x1F) != 0 {
-            println!("Illegal Operand(s) for F2 Format.");
-            return -1;
+            return Err(DlxError::outside_execute(DlxErrorKind::IllegalOperands { format: 2 }));
         }
-        return op << 26 | a << 21 | b << 16 | c;
+        Ok(op << 26 | a << 21 | b << 16 | c)
     }
 
-    pub fn F3(op: i32, c: i32) -> i32 {
+    pub fn F3(op: i32, c: i32) -> Result<i32, DlxError> {
         if (c < 0) || (c > MemSize) {
-            println!("Operand for F3 Format is referencing non-existent memory location.");
-            return -1;
+            return Err(DlxError::outside_execute(DlxErrorKind::IllegalOperands { format: 3 }));
         }
-        return op << 26 | c;
-    }
-
-    pub fn bug(&mut self, errNum: u32) {
-        println!("Bug number: {}", errNum);
+        Ok(op << 26 | c)
     }
 }
 
@@ -386,56 +582,56 @@ const mnemo : [&str; 80] = [
 "ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR","ERR"];
 
 // Const Keywords
-const MemSize: i32 = 10000;
-const ADD : i32 = 0;
-const SUB : i32 = 1;
-const MUL : i32 = 2;
-const DIV : i32 = 3;
-const MOD : i32 = 4;
-const CMP : i32 = 5;
-const OR  : i32 = 8;
-const AND : i32 = 9;
-const BIC : i32 = 10;
-const XOR : i32 = 11;
-const LSH : i32 = 12;
-const ASH : i32 = 13;
-const CHK : i32 = 14;
-
-const ADDI : i32 = 16;
-const SUBI : i32 = 17;
-const MULI : i32 = 18;
-const DIVI : i32 = 19;
-const MODI : i32 = 20;
-const CMPI : i32 = 21;
-const ORI  : i32 = 24;
-const ANDI : i32 = 25;
-const BICI : i32 = 26;
-const XORI : i32 = 27;
-const LSHI : i32 = 28;
-const ASHI : i32 = 29;
-const CHKI : i32 = 30;
-
-const LDW : i32 = 32;
-const LDX : i32 = 33;
-const POP : i32 = 34;
-const STW : i32 = 36;
-const STX : i32 = 37;
-const PSH : i32 = 38;
-
-const BEQ : i32 = 40;
-const BNE : i32 = 41;
-const BLT : i32 = 42;
-const BGE : i32 = 43;
-const BLE : i32 = 44;
-const BGT : i32 = 45;
-const BSR : i32 = 46;
-const JSR : i32 = 48;
-const RET : i32 = 49;
-
-const RDI : i32 = 50;
-const WRD : i32 = 51;
-const WRH : i32 = 52;
-const WRL : i32 = 53;
-
-const ERR : i32 = 63;   // error opcode which is insertered by loader
+pub const MemSize: i32 = 10000;
+pub const ADD : i32 = 0;
+pub const SUB : i32 = 1;
+pub const MUL : i32 = 2;
+pub const DIV : i32 = 3;
+pub const MOD : i32 = 4;
+pub const CMP : i32 = 5;
+pub const OR  : i32 = 8;
+pub const AND : i32 = 9;
+pub const BIC : i32 = 10;
+pub const XOR : i32 = 11;
+pub const LSH : i32 = 12;
+pub const ASH : i32 = 13;
+pub const CHK : i32 = 14;
+
+pub const ADDI : i32 = 16;
+pub const SUBI : i32 = 17;
+pub const MULI : i32 = 18;
+pub const DIVI : i32 = 19;
+pub const MODI : i32 = 20;
+pub const CMPI : i32 = 21;
+pub const ORI  : i32 = 24;
+pub const ANDI : i32 = 25;
+pub const BICI : i32 = 26;
+pub const XORI : i32 = 27;
+pub const LSHI : i32 = 28;
+pub const ASHI : i32 = 29;
+pub const CHKI : i32 = 30;
+
+pub const LDW : i32 = 32;
+pub const LDX : i32 = 33;
+pub const POP : i32 = 34;
+pub const STW : i32 = 36;
+pub const STX : i32 = 37;
+pub const PSH : i32 = 38;
+
+pub const BEQ : i32 = 40;
+pub const BNE : i32 = 41;
+pub const BLT : i32 = 42;
+pub const BGE : i32 = 43;
+pub const BLE : i32 = 44;
+pub const BGT : i32 = 45;
+pub const BSR : i32 = 46;
+pub const JSR : i32 = 48;
+pub const RET : i32 = 49;
+
+pub const RDI : i32 = 50;
+pub const WRD : i32 = 51;
+pub const WRH : i32 = 52;
+pub const WRL : i32 = 53;
+
+pub const ERR : i32 = 63;   // error opcode which is insertered by loader
 // after end of program code
@@ -0,0 +1,413 @@
+//! Random PL241 programs for fuzzing the compiler. The same seed always
+//! gives the same program, and every program passes the semantic check and
+//! terminates: loops count a variable nothing else assigns up to a small
+//! constant, functions only call the ones declared before them, divisors
+//! are nonzero constants and arrays are only indexed by constants or by
+//! loop counters that stay inside the array.
+
+use std::fmt::Write;
+
+const MAX_FUNCTIONS: usize = 3;
+const MAX_PARAMS: usize = 3;
+// Nesting of ifs and whiles inside one body.
+const MAX_BLOCK_DEPTH: usize = 3;
+const MAX_LOOP_DEPTH: usize = 2;
+const MAX_LOOP_COUNT: i32 = 4;
+const MAX_EXPR_DEPTH: usize = 1;
+
+/// A generated program and the input its `InputNum` calls read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedProgram {
+    pub seed: u64,
+    pub source: String,
+    pub input: Vec<i32>,
+}
+
+/// Generates the program for `seed`.
+pub fn generate(seed: u64) -> GeneratedProgram {
+    Generator::new(seed).generate()
+}
+
+// SplitMix64, small and good enough to pick grammar rules with.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Uniform in `low..=high`.
+    fn between(&mut self, low: i32, high: i32) -> i32 {
+        low + self.below((high - low + 1) as usize) as i32
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    name: String,
+    params: usize,
+    returns: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Array {
+    name: String,
+    dims: Vec<i32>,
+}
+
+/// What the statements of one body can see.
+#[derive(Debug, Clone)]
+struct Scope {
+    // Variables statements may assign, loop counters are not among them.
+    scalars: Vec<String>,
+    arrays: Vec<Array>,
+    // Counters of the enclosing loops, each with the count it stops at.
+    counters: Vec<(String, i32)>,
+    // Counter names by loop depth.
+    counter_names: Vec<String>,
+    functions: Vec<Function>,
+    in_main: bool,
+    returns: bool,
+}
+
+pub struct Generator {
+    rng: Rng,
+    seed: u64,
+    reads: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            rng: Rng { state: seed },
+            seed,
+            reads: 0,
+        }
+    }
+
+    pub fn generate(mut self) -> GeneratedProgram {
+        let mut source = String::new();
+        writeln!(source, "// Generated from seed {}.", self.seed);
+        writeln!(source, "main");
+
+        let globals = (0..self.rng.between(2, 4))
+            .map(|index| format!("g{}", index))
+            .collect::<Vec<String>>();
+        let main_counters = (0..MAX_LOOP_DEPTH)
+            .map(|depth| format!("i{}", depth))
+            .collect::<Vec<String>>();
+        writeln!(source, "var {}, {};", globals.join(", "), main_counters.join(", "));
+
+        let arrays = (0..self.rng.below(3))
+            .map(|index| self.array(format!("a{}", index)))
+            .collect::<Vec<Array>>();
+        for array in &arrays {
+            writeln!(source, "{};", array_decl(array));
+        }
+
+        let mut functions = Vec::new();
+        for index in 0..self.rng.below(MAX_FUNCTIONS + 1) {
+            let function = Function {
+                name: format!("f{}", index),
+                params: self.rng.below(MAX_PARAMS + 1),
+                returns: self.rng.chance(60),
+            };
+            let decl = self.function(&function, &globals, &arrays, &functions);
+            source.push_str(&decl);
+            functions.push(function);
+        }
+
+        let scope = Scope {
+            scalars: globals.clone(),
+            arrays: arrays.clone(),
+            counters: Vec::new(),
+            counter_names: main_counters,
+            functions,
+            in_main: true,
+            returns: false,
+        };
+
+        // Globals start out set so the program does not lean on them
+        // being 0, the output at the end shows what the body did to them.
+        let mut stmts = Vec::new();
+        for global in &globals {
+            let value = self.rng.between(0, 20);
+            stmts.push(format!("let {} <- {}", global, value));
+        }
+        stmts.extend(self.statements(&scope, 0, 1));
+        for global in &globals {
+            stmts.push(format!("call OutputNum({})", global));
+        }
+        for array in &arrays {
+            let last = array
+                .dims
+                .iter()
+                .map(|dim| format!("[{}]", dim - 1))
+                .collect::<String>();
+            stmts.push(format!("call OutputNum({}{})", array.name, last));
+        }
+        stmts.push(String::from("call OutputNewLine()"));
+
+        writeln!(source, "{{");
+        source.push_str(&block(&stmts, 1));
+        writeln!(source, "}}.");
+
+        let input = (0..self.reads).map(|_| self.rng.between(-20, 20)).collect();
+        GeneratedProgram {
+            seed: self.seed,
+            source,
+            input,
+        }
+    }
+
+    fn array(&mut self, name: String) -> Array {
+        let dims = (0..self.rng.between(1, 2)).map(|_| self.rng.between(2, 5)).collect();
+        Array { name, dims }
+    }
+
+    fn function(&mut self, function: &Function, globals: &[String], arrays: &[Array], callable: &[Function]) -> String {
+        let params = (0..function.params)
+            .map(|index| format!("p{}", index))
+            .collect::<Vec<String>>();
+        let locals = (0..self.rng.below(3))
+            .map(|index| format!("l{}", index))
+            .collect::<Vec<String>>();
+        let counters = (0..MAX_LOOP_DEPTH)
+            .map(|depth| format!("j{}", depth))
+            .collect::<Vec<String>>();
+
+        let mut decl = String::new();
+        let kind = if function.returns { "function" } else { "procedure" };
+        writeln!(decl, "{} {}({});", kind, function.name, params.join(", "));
+        let mut vars = locals.clone();
+        vars.extend(counters.iter().cloned());
+        writeln!(decl, "var {};", vars.join(", "));
+
+        let mut scalars = globals.to_vec();
+        scalars.extend(params.iter().cloned());
+        let mut scope = Scope {
+            scalars,
+            arrays: arrays.to_vec(),
+            counters: Vec::new(),
+            counter_names: counters,
+            functions: callable.to_vec(),
+            in_main: false,
+            returns: function.returns,
+        };
+
+        // Locals are set before anything reads them.
+        let mut stmts = Vec::new();
+        for local in &locals {
+            let value = self.expression(&scope, 0);
+            stmts.push(format!("let {} <- {}", local, value));
+            scope.scalars.push(local.clone());
+        }
+        stmts.extend(self.statements(&scope, 0, 1));
+        if function.returns {
+            stmts.push(format!("return {}", self.expression(&scope, 0)));
+        }
+
+        writeln!(decl, "{{");
+        decl.push_str(&block(&stmts, 1));
+        writeln!(decl, "}};");
+        decl
+    }
+
+    fn statements(&mut self, scope: &Scope, depth: usize, indent: usize) -> Vec<String> {
+        let mut stmts = Vec::new();
+        for _ in 0..self.rng.between(1, 3) {
+            stmts.extend(self.statement(scope, depth, indent));
+        }
+        stmts
+    }
+
+    fn statement(&mut self, scope: &Scope, depth: usize, indent: usize) -> Vec<String> {
+        let procedures = scope
+            .functions
+            .iter()
+            .filter(|function| !function.returns)
+            .cloned()
+            .collect::<Vec<Function>>();
+        let loop_depth = scope.counters.len();
+
+        loop {
+            match self.rng.below(100) {
+                0..=29 => {
+                    let target = self.rng.pick(&scope.scalars).clone();
+                    return vec![format!("let {} <- {}", target, self.expression(scope, 0))];
+                }
+                30..=41 if !scope.arrays.is_empty() => {
+                    let target = self.element(scope);
+                    return vec![format!("let {} <- {}", target, self.expression(scope, 0))];
+                }
+                42..=55 if depth < MAX_BLOCK_DEPTH => {
+                    let relation = self.relation(scope);
+                    let then_stmts = self.statements(scope, depth + 1, indent + 1);
+                    let mut stmt = format!("if {} then\n{}", relation, block(&then_stmts, indent + 1));
+                    if self.rng.chance(50) {
+                        let else_stmts = self.statements(scope, depth + 1, indent + 1);
+                        write!(stmt, "{}else\n{}", tabs(indent), block(&else_stmts, indent + 1));
+                    }
+                    write!(stmt, "{}fi", tabs(indent));
+                    return vec![stmt];
+                }
+                56..=67 if depth < MAX_BLOCK_DEPTH && loop_depth < MAX_LOOP_DEPTH => {
+                    let counter = scope.counter_names[loop_depth].clone();
+                    let count = self.rng.between(1, MAX_LOOP_COUNT);
+                    let relation = match self.rng.below(4) {
+                        0 => format!("{} < {}", counter, count),
+                        1 => format!("{} <= {}", counter, count - 1),
+                        2 => format!("{} != {}", counter, count),
+                        _ => format!("{} > {}", count, counter),
+                    };
+
+                    let mut body_scope = scope.clone();
+                    body_scope.counters.push((counter.clone(), count));
+                    let mut body = self.statements(&body_scope, depth + 1, indent + 1);
+                    body.push(format!("let {} <- {} + 1", counter, counter));
+
+                    return vec![
+                        format!("let {} <- 0", counter),
+                        format!("while {} do\n{}{}od", relation, block(&body, indent + 1), tabs(indent)),
+                    ];
+                }
+                68..=77 if !procedures.is_empty() => {
+                    let procedure = self.rng.pick(&procedures).clone();
+                    return vec![self.call(scope, &procedure, 0)];
+                }
+                78..=87 => {
+                    return vec![format!("call OutputNum({})", self.expression(scope, 0))];
+                }
+                88..=90 => return vec![String::from("call OutputNewLine()")],
+                // Reads stay out of loops so the input is as long as the
+                // number of places that read.
+                91..=96 if scope.in_main && loop_depth == 0 => {
+                    self.reads += 1;
+                    let target = self.rng.pick(&scope.scalars).clone();
+                    return vec![format!("let {} <- call InputNum()", target)];
+                }
+                97..=99 if scope.returns && depth > 0 => {
+                    return vec![format!("return {}", self.expression(scope, 0))];
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn relation(&mut self, scope: &Scope) -> String {
+        let rel_op = *self.rng.pick(&["==", "!=", "<", "<=", ">", ">="]);
+        format!("{} {} {}", self.expression(scope, 1), rel_op, self.expression(scope, 1))
+    }
+
+    fn expression(&mut self, scope: &Scope, depth: usize) -> String {
+        let mut expression = self.term(scope, depth);
+        for _ in 0..self.rng.below(2) {
+            let math_op = if self.rng.chance(50) { "+" } else { "-" };
+            write!(expression, " {} {}", math_op, self.term(scope, depth));
+        }
+        expression
+    }
+
+    fn term(&mut self, scope: &Scope, depth: usize) -> String {
+        let mut term = self.factor(scope, depth);
+        if self.rng.chance(30) {
+            write!(term, " * {}", self.factor(scope, depth));
+        }
+        if self.rng.chance(15) {
+            write!(term, " / {}", self.rng.between(1, 7));
+        }
+        term
+    }
+
+    fn factor(&mut self, scope: &Scope, depth: usize) -> String {
+        let functions = scope
+            .functions
+            .iter()
+            .filter(|function| function.returns)
+            .cloned()
+            .collect::<Vec<Function>>();
+
+        loop {
+            match self.rng.below(100) {
+                0..=29 => return self.rng.between(0, 20).to_string(),
+                30..=59 => return self.rng.pick(&scope.scalars).clone(),
+                60..=69 if !scope.counters.is_empty() => return self.rng.pick(&scope.counters).0.clone(),
+                70..=79 if !scope.arrays.is_empty() => return self.element(scope),
+                80..=89 if depth < MAX_EXPR_DEPTH && !functions.is_empty() => {
+                    let function = self.rng.pick(&functions).clone();
+                    return self.call(scope, &function, depth + 1);
+                }
+                90..=99 if depth < MAX_EXPR_DEPTH => {
+                    return format!("({})", self.expression(scope, depth + 1));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn call(&mut self, scope: &Scope, function: &Function, depth: usize) -> String {
+        let args = (0..function.params)
+            .map(|_| self.expression(scope, depth))
+            .collect::<Vec<String>>();
+        format!("call {}({})", function.name, args.join(", "))
+    }
+
+    /// An element of one of the arrays, indexed by constants or by counters
+    /// that never reach the size of the dimension.
+    fn element(&mut self, scope: &Scope) -> String {
+        let array = self.rng.pick(&scope.arrays).clone();
+        let mut element = array.name.clone();
+        for dim in &array.dims {
+            let counters = scope
+                .counters
+                .iter()
+                .filter(|(_, count)| count <= dim)
+                .map(|(counter, _)| counter.clone())
+                .collect::<Vec<String>>();
+            if !counters.is_empty() && self.rng.chance(60) {
+                write!(element, "[{}]", self.rng.pick(&counters));
+            } else {
+                write!(element, "[{}]", self.rng.between(0, dim - 1));
+            }
+        }
+        element
+    }
+}
+
+fn array_decl(array: &Array) -> String {
+    let dims = array.dims.iter().map(|dim| format!("[{}]", dim)).collect::<String>();
+    format!("array {} {}", dims, array.name)
+}
+
+fn tabs(indent: usize) -> String {
+    "\t".repeat(indent)
+}
+
+/// Statements separated by `;`, one per line.
+fn block(stmts: &[String], indent: usize) -> String {
+    let mut text = String::new();
+    for (index, stmt) in stmts.iter().enumerate() {
+        let separator = if index + 1 < stmts.len() { ";" } else { "" };
+        writeln!(text, "{}{}{}", tabs(indent), stmt, separator);
+    }
+    text
+}
@@ -1,2 +1,7 @@
-//pub mod dlx;
+pub mod dlx;
 pub mod display;
+pub mod generator;
+
+// Runaway programs are stopped after this many instructions unless told
+// otherwise, by the DLX emulator and the IR interpreter alike.
+pub const DEFAULT_INSTRUCTION_BUDGET: u64 = 10_000_000;
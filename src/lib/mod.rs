@@ -15,206 +15,1048 @@ extern crate petgraph;
 use petgraph::graph;
 use petgraph::{Directed, Incoming, Outgoing};
 
+use lib::Optimizer::pass_manager::PassPipeline;
 use lib::Optimizer::temp_value_manager::TempValManager;
 use lib::RegisterAllocator::analyze_live_range;
 use lib::RegisterAllocator::{Color, RegisterAllocation};
-use lib::IR::ir::{InstTy, ValTy, Value};
+use lib::IR::ir::{InstTy, Op, ValTy, Value};
 use lib::IR::ir_manager::IRGraphManager;
+use lib::IR::verify::is_branch;
 use petgraph::algo::dominators::simple_fast;
 use petgraph::algo::dominators::Dominators;
 use petgraph::prelude::NodeIndex;
 
+pub mod CodeGen;
+pub mod Driver;
 pub mod Graph;
 pub mod IR;
 pub mod Lexer;
 pub mod Optimizer;
 pub mod Parser;
 pub mod RegisterAllocator;
+pub mod Semantic;
 pub mod Utility;
 
 #[cfg(test)]
 pub mod tests {
-    use lib::run_file;
+    use lib::compile_file;
+    use lib::Driver::pipeline::{
+        check_stages, compile_ir_stages, compile_source, compile_stages, fuzz, interpret_source,
+        interpret_stages, run_program, run_source, Run,
+    };
+    use lib::Driver::{parse_args, run, Destination, Emit, EXIT_USAGE_ERROR};
+    use lib::Optimizer::pass_manager::{pass_names, PassPipeline, DEFAULT_FIXPOINT_ROUNDS};
+    use lib::Lexer::source::SourceIter;
+    use lib::Lexer::token::{Span, TokenCollection};
+    use lib::Parser::interpreter::Evaluator;
+    use lib::Parser::parse;
+    use lib::IR::interpreter::{interpret, BufferedIo, Interpreter, StopKind};
+    use lib::IR::ir_text::{from_text, to_text};
+    use lib::IR::verify::verify;
+    use lib::Utility::dlx::{DlxErrorKind, ADD, ADDI, BEQ, BNE, DLX, JSR};
+    use lib::Utility::generator::generate;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
-    #[test]
-    fn test_big() {
-        run_file(String::from("big"));
+    // Each program in src/Testing comes with golden files next to it:
+    // `name.in` holds the integers InputNum reads (there is no input when it
+    // is missing), `name.out` what the program writes on the AST interpreter
+    // and `name.err` the kind of error it stopped with when it did not run to
+    // the end, or `CompileError` when the compiler rejects it. The program
+    // compiled at -O2 has to match them on the DLX emulator. Set BLESS when
+    // running the tests to write the files from what the AST interpreter
+    // does now instead of checking against them.
+
+    // Programs that loop until a budget runs out, which takes a long
+    // while on every engine and says nothing about their output. They have
+    // no golden files.
+    const ENDLESS: [&str; 8] = [
+        "test008", "test010", "test011", "test016", "test016_a", "test022", "test024", "test027",
+    ];
+
+    fn testing_programs() -> Vec<PathBuf> {
+        let mut paths = fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/Testing"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "txt"))
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+        paths
     }
 
-    #[test]
-    fn test_cell() {
-        run_file(String::from("cell"));
+    fn golden_input(path: &Path) -> Vec<i32> {
+        match fs::read_to_string(path.with_extension("in")) {
+            Ok(text) => text
+                .split_whitespace()
+                .map(|value| value.parse().expect("Input files hold integers."))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
     }
 
-    #[test]
-    fn test_conditional_call() {
-        run_file(String::from("conditional_call"));
+    // Runs `program` on the DLX emulator with `input` queued up for
+    // InputNum and returns what it wrote.
+    fn execute(program: &[i32], input: &[i32]) -> String {
+        let mut dlx = DLX::new();
+        dlx.set_input(input);
+        dlx.load(program).unwrap();
+        dlx.execute().unwrap().output
     }
 
-    #[test]
-    fn test_factorial() {
-        run_file(String::from("factorial"));
+    // Compiles src/Testing/`name`.txt at -O2 and runs it.
+    fn run_dlx(name: &str, input: &[i32]) -> String {
+        execute(&compile_file(String::from(name)).unwrap(), input)
     }
 
     #[test]
-    fn test_op_dom_test() {
-        run_file(String::from("op_dom_test"));
+    fn test_golden() {
+        let bless = env::var_os("BLESS").is_some();
+        let passes = PassPipeline::for_level(2).unwrap();
+        let kinds = [
+            StopKind::DivisionByZero,
+            StopKind::InputExhausted,
+            StopKind::IndexOutOfBounds,
+            StopKind::BudgetExhausted,
+            StopKind::CompileError,
+            StopKind::Other,
+        ];
+        let mut failures = Vec::new();
+
+        for path in testing_programs() {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            if ENDLESS.contains(&name) {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let out_path = path.with_extension("out");
+            let err_path = path.with_extension("err");
+            let found = run_program(&source, &passes, &golden_input(&path));
+
+            if bless {
+                let run = match found.stop {
+                    Some((StopKind::CompileError, _)) => found,
+                    _ => run_source(&source, &golden_input(&path)),
+                };
+                fs::write(&out_path, &run.output).unwrap();
+                match &run.stop {
+                    Some((kind, _)) => fs::write(&err_path, format!("{:?}\n", kind)).unwrap(),
+                    None if err_path.exists() => fs::remove_file(&err_path).unwrap(),
+                    None => {}
+                }
+                continue;
+            }
+
+            let output = match fs::read_to_string(&out_path) {
+                Ok(output) => output,
+                Err(_) => {
+                    failures.push(format!("{}: no {:?}, run with BLESS set to write it", name, out_path));
+                    continue;
+                }
+            };
+            let stop = fs::read_to_string(&err_path).ok().map(|error| {
+                let kind = kinds.iter().find(|kind| format!("{:?}", kind) == error.trim_end());
+                (*kind.expect("Error files hold the kind of error."), String::new())
+            });
+            let expected = Run { output, stop };
+            if !expected.matches(&found) {
+                failures.push(format!("{}: expected {} but found {}", name, expected, found));
+            }
+        }
+
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
     }
 
     #[test]
-    fn test_simple_reassignment() {
-        run_file(String::from("simple_reassignment"));
+    fn test_compile_factorial() {
+        let program = compile_file(String::from("factorial")).unwrap();
+        assert!(!program.is_empty());
     }
-
     #[test]
-    fn test_001() {
-        run_file(String::from("test001"));
+    fn test_compile_cell() {
+        let program = compile_file(String::from("cell")).unwrap();
+        assert!(!program.is_empty());
     }
-
     #[test]
-    fn test_001_a() {
-        run_file(String::from("test001_a"));
+    fn test_compile_004() {
+        let error = compile_file(String::from("test004")).unwrap_err();
+        assert_eq!(error, "37:38: function 'max' takes 5 argument(s) but 4 were given");
     }
-
     #[test]
-    fn test_002() {
-        run_file(String::from("test002"));
+    fn test_execute() {
+        let cases: [(&str, &[i32], &str); 4] = [
+            ("test001", &[3, 7], "102  4  4  "),
+            ("test002", &[], "22  "),
+            ("test007", &[], "0  3  "),
+            ("test013", &[], "9  2  9  4  6  6  8  "),
+        ];
+        for (name, input, expected) in cases.iter() {
+            assert_eq!(run_dlx(name, input), *expected, "{}", name);
+        }
     }
     #[test]
-    fn test_003() {
-        run_file(String::from("test003"));
+    fn test_execute_missing_input() {
+        let program = compile_file(String::from("test001")).unwrap();
+        let mut dlx = DLX::new();
+        dlx.set_input(&[]);
+        dlx.load(&program).unwrap();
+        assert_eq!(dlx.execute().unwrap_err().kind, DlxErrorKind::InputExhausted);
     }
     #[test]
-    fn test_004() {
-        run_file(String::from("test004"));
+    fn test_execute_budget() {
+        // test008 loops forever.
+        let program = compile_file(String::from("test008")).unwrap();
+        let mut dlx = DLX::new();
+        dlx.set_instruction_budget(Some(10_000));
+        dlx.load(&program).unwrap();
+        let err = dlx.execute().unwrap_err();
+        assert_eq!(err.kind, DlxErrorKind::BudgetExhausted(10_000));
     }
     #[test]
-    fn test_004_a() {
-        run_file(String::from("test004_a"));
+    fn test_execute_bad_branch() {
+        let program = vec![DLX::F1(BNE, 0, 0, 0).unwrap(), DLX::F1(BEQ, 0, 0, -5).unwrap()];
+        let mut dlx = DLX::new();
+        dlx.load(&program).unwrap();
+        let err = dlx.execute().unwrap_err();
+        assert_eq!(err.pc, 1);
+        assert_eq!(err.kind.bug_number(), Some(40));
     }
     #[test]
-    fn test_005() {
-        run_file(String::from("test005"));
+    fn test_dlx_bad_operands() {
+        assert_eq!(DLX::F1(ADDI, 32, 0, 0).unwrap_err().kind, DlxErrorKind::IllegalOperands { format: 1 });
+        assert_eq!(DLX::F1(ADDI, 1, 0, 1 << 16).unwrap_err().kind, DlxErrorKind::IllegalOperands { format: 1 });
+        assert_eq!(DLX::F2(ADD, 1, 2, 40).unwrap_err().kind, DlxErrorKind::IllegalOperands { format: 2 });
+        assert_eq!(DLX::F3(JSR, -4).unwrap_err().kind, DlxErrorKind::IllegalOperands { format: 3 });
+        assert_eq!(DLX::assemble(ADD, 0, 0, 0).unwrap_err().kind, DlxErrorKind::MissingOperands);
+
+        let program = vec![DLX::F1(ADDI, 1, 0, 1).unwrap(); 2500];
+        let mut dlx = DLX::new();
+        let err = dlx.load(&program).unwrap_err();
+        assert_eq!(err.kind, DlxErrorKind::ProgramTooLarge(2500));
+        assert_eq!(err.to_string(), "DLX.load: 2500 words do not fit into memory (10000 bytes).");
     }
     #[test]
-    fn test_006() {
-        run_file(String::from("test006"));
+    fn test_compile_020() {
+        // Its global arrays do not fit in the emulated memory.
+        assert!(compile_file(String::from("test020")).is_err());
     }
     #[test]
-    fn test_007() {
-        run_file(String::from("test007"));
+    fn test_execute_013_unoptimized() {
+        let source = include_str!("../Testing/test013.txt");
+        for opt_level in 0..2 {
+            let program = compile_source(source, opt_level).unwrap();
+            assert_eq!(execute(&program, &[]), "9  2  9  4  6  6  8  ");
+        }
     }
     #[test]
-    fn test_008() {
-        run_file(String::from("test008"));
+    fn test_execute_liveness_unoptimized() {
+        // Without constant propagation every variable stays live through
+        // both sides of the if and around the loop nested in it.
+        let source = "main var a, b, c, d, e, f, g, h; {
+            let a <- 1; let b <- 2; let c <- 3; let d <- 4;
+            let e <- 5; let f <- 6; let g <- 7; let h <- 8;
+            if a < b then
+                let a <- a + 1;
+                let e <- e + 1;
+                if b < c then
+                    let c <- c * 3;
+                    while a < c do let a <- a + 1 od
+                fi;
+                let g <- 9
+            else
+                let b <- b - 1;
+                let f <- f + 1;
+                let h <- 425
+            fi;
+            call OutputNum(a); call OutputNum(b); call OutputNum(c); call OutputNum(d);
+            call OutputNum(e); call OutputNum(f); call OutputNum(h)
+        }.";
+        for opt_level in 0..3 {
+            let program = compile_source(source, opt_level).unwrap();
+            assert_eq!(execute(&program, &[]), "9  2  9  4  6  6  8  ");
+        }
     }
     #[test]
-    fn test_009() {
-        run_file(String::from("test009"));
+    fn test_driver_args() {
+        let args = ["prog.txt", "-O1", "-o", "out/prog.dlx", "--emit", "ast=-,after:const-eval", "--run"];
+        let options = parse_args(args.iter().map(|arg| arg.to_string()).collect()).unwrap();
+        assert_eq!(options.passes, PassPipeline::for_level(1).unwrap());
+        assert_eq!(options.stage_path(&Emit::Program, None), Some(PathBuf::from("out/prog.dlx")));
+        assert_eq!(options.destination(&Emit::Ast), Some(&Destination::Stdout));
+        assert_eq!(options.stage_path(&Emit::After("const-eval"), None), Some(PathBuf::from("./prog.const-eval.ir")));
+        assert!(options.run);
+        assert_eq!(
+            Emit::from_str("after:gvn").err().unwrap(),
+            "Unknown pass 'gvn' given to --emit, the passes are prepare, const-eval, sccp, cse, dce, licm, \
+             strength-reduce, map-blocks."
+        );
+
+        let options = parse_args(vec![String::from("dir/prog.txt")]).unwrap();
+        assert_eq!(options.passes, PassPipeline::for_level(2).unwrap());
+        assert_eq!(options.stage_path(&Emit::Program, None), Some(PathBuf::from("./prog.dlx")));
+        assert_eq!(options.destination(&Emit::Cfg), None);
     }
     #[test]
-    fn test_010() {
-        run_file(String::from("test010"));
+    fn test_emit_stages() {
+        let source = include_str!("../Testing/test002.txt");
+        let stages = vec![Emit::Tokens, Emit::Ir, Emit::After("cse"), Emit::Interference, Emit::Asm];
+        let mut outputs = Vec::new();
+        compile_stages(source, &PassPipeline::for_level(2).unwrap(), &stages, &mut outputs).unwrap();
+
+        let emitted = outputs.iter().map(|output| output.stage.clone()).collect::<Vec<Emit>>();
+        assert_eq!(
+            emitted,
+            vec![Emit::Tokens, Emit::Ir, Emit::After("cse"), Emit::Interference, Emit::Interference,
+                 Emit::Interference, Emit::Asm]
+        );
+        assert!(outputs[0].text.starts_with("1:1\tComputation\tmain"));
+        assert!(outputs[1].text.starts_with("global array[4] a\n"));
+        assert!(outputs[1].text.contains("\nmain:\n"));
+        assert_eq!(outputs[3].func_name, Some(String::from("main")));
+        assert!(outputs[6].text.contains("RET"));
     }
     #[test]
-    fn test_011() {
-        run_file(String::from("test011"));
+    fn test_token_spans() {
+        let mut source = SourceIter::new("main\n  var ab;\n{ ab <- 10 }.");
+        let tokens = TokenCollection::collect(&mut source)
+            .get_vector()
+            .map(|token| token.get_span())
+            .collect::<Vec<Span>>();
+
+        assert_eq!(tokens[0], Span::new(1, 1, 0, 4));
+        assert_eq!(tokens[1], Span::new(2, 3, 7, 10));
+        assert_eq!(tokens[2], Span::new(2, 7, 11, 13));
+        assert_eq!(tokens[7], Span::new(3, 9, 23, 25));
+        assert_eq!(tokens.len(), 10);
     }
+
     #[test]
-    fn test_012() {
-        run_file(String::from("test012"));
+    fn test_parse_error_position() {
+        let error = compile_source("main\nvar a;\n{\n  let a <- ;\n}.", 2).unwrap_err();
+        assert!(error.starts_with("4:12: "), "{}", error);
     }
+
     #[test]
-    fn test_013() {
-        run_file(String::from("test013"));
+    fn test_lex_errors() {
+        let mut source = SourceIter::new("main\tvar a;\n{ let a <- 1 $ 2'; a = 3 }. /");
+        let mut tc = TokenCollection::collect(&mut source);
+        let errors = tc
+            .take_lex_errors()
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            errors,
+            vec![
+                "2:14: unexpected character '$'",
+                "2:17: unexpected character '\\''",
+                "2:22: unknown operator '='",
+            ]
+        );
+        assert_eq!(tc.get_vector().count(), 16);
+
+        let error = compile_source("main { let a <- 0x10 }.", 2).unwrap_err();
+        assert_eq!(error, "1:17: malformed number '0x10'");
     }
+
     #[test]
-    fn test_014() {
-        run_file(String::from("test014"));
+    fn test_parse_error_recovery() {
+        let source = "main\nvar a b;\n{\n  let a <- ;\n  while a < do let a <- 1 od;\n  \
+                      if a > 1 then let a <- 2 else let a <- * fi;\n  let a <- 3\n}.";
+        let mut tc = TokenCollection::collect(&mut SourceIter::new(source));
+        let errors = parse(&mut tc).unwrap_err();
+
+        let positions = errors
+            .iter()
+            .map(|error| error.get_span().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(positions, vec!["2:7", "4:12", "5:13", "6:42"]);
+        assert_eq!(
+            errors[0].to_string(),
+            "2:7: expected ',' or ';' in variable declaration, found 'b'"
+        );
+
+        let error = compile_source("main\n{\n  let a <- 1\n}", 2).unwrap_err();
+        assert_eq!(error, "4:2: expected '.' at end of program, found end of file");
     }
+
     #[test]
-    fn test_015() {
-        run_file(String::from("test015"));
+    fn test_forward_calls() {
+        // f calls g before g is declared and g sets globals behind main's back.
+        let source = "main\n\
+                      var a, b;\n\
+                      function f(x); { return call g(x) + a };\n\
+                      function g(y); { let b <- y * 2; let a <- a + 1; return b };\n\
+                      procedure even(n); { if n > 0 then call odd(n - 1) fi };\n\
+                      procedure odd(n); { if n > 0 then call even(n - 1) fi };\n\
+                      {\n\
+                      let a <- 1;\n\
+                      call OutputNum(call f(5));\n\
+                      call OutputNum(a);\n\
+                      call OutputNum(b)\n\
+                      }.";
+        let program = compile_source(source, 2).unwrap();
+        assert_eq!(execute(&program, &[]), "12  2  10  ");
+
+        let mut tc = TokenCollection::collect(&mut SourceIter::new(source));
+        let mut irgm = parse(&mut tc).unwrap().to_ir();
+        let f = irgm.function_manager().get_function(&String::from("f"));
+        let mut assigned = f.load_assigned_globals();
+        assigned.sort();
+        assert_eq!(assigned, vec!["a", "b"]);
+        assert!(f.has_return());
     }
+
     #[test]
-    fn test_016() {
-        run_file(String::from("test016"));
+    fn test_recursion() {
+        // Every call gets its own parameters and return address, and the
+        // arguments of twice are stored before add3's are.
+        let source = "main\n\
+                      procedure countdown(n); { if n > 0 then call OutputNum(n); call countdown(n - 1) fi };\n\
+                      function depth(n); { if n == 0 then return 0 fi; return call depth(n - 1) + 1 };\n\
+                      function even(n); { if n == 0 then return 1 fi; return call odd(n - 1) };\n\
+                      function odd(n); { if n == 0 then return 0 fi; return call even(n - 1) };\n\
+                      function add3(x, y, z); { return x * 100 + y * 10 + z };\n\
+                      function twice(x); { return x * 2 };\n\
+                      {\n\
+                      call countdown(3);\n\
+                      call OutputNum(call depth(100));\n\
+                      call OutputNum(call even(7));\n\
+                      call OutputNum(call add3(1, call twice(2), 5))\n\
+                      }.";
+        let program = compile_source(source, 2).unwrap();
+        assert_eq!(execute(&program, &[]), "3  2  1  100  0  145  ");
+
+        // Recursing into the globals is caught by the stack check.
+        let source = "main\n\
+                      array[1000] a;\n\
+                      function forever(n); { return call forever(n + 1) };\n\
+                      {\n\
+                      call OutputNum(call forever(0))\n\
+                      }.";
+        let program = compile_source(source, 2).unwrap();
+        let mut dlx = DLX::new();
+        dlx.load(&program).unwrap();
+        let error = dlx.execute().unwrap_err();
+        assert!(matches!(error.kind, DlxErrorKind::CheckFailed { reg: 29, .. }));
     }
+
     #[test]
-    fn test_016_a() {
-        run_file(String::from("test016_a"));
+    fn test_values_across_calls() {
+        // n, the first call's result and the loop variables all stay in
+        // registers that the callee also uses.
+        let source = "main\n\
+                      var i, sum;\n\
+                      function fact(n); { if n <= 1 then return 1 fi; return n * call fact(n - 1) };\n\
+                      function fib(n); { if n < 2 then return n fi; return call fib(n - 1) + call fib(n - 2) };\n\
+                      function square(x); var y; { let y <- x * x; return y };\n\
+                      {\n\
+                      let i <- 0;\n\
+                      let sum <- 0;\n\
+                      while i < 4 do\n\
+                      let sum <- sum + call square(i) * call square(i + 1);\n\
+                      let i <- i + 1\n\
+                      od;\n\
+                      call OutputNum(call fact(6));\n\
+                      call OutputNum(call fib(10));\n\
+                      call OutputNum(sum)\n\
+                      }.";
+        for opt_level in 0..3 {
+            let program = compile_source(source, opt_level).unwrap();
+            assert_eq!(execute(&program, &[]), "720  55  184  ");
+        }
     }
+
     #[test]
-    fn test_017() {
-        run_file(String::from("test017"));
+    fn test_semantic_errors() {
+        let source = "main\n\
+                      var a;\n\
+                      array[2][3] m;\n\
+                      procedure p(x);\n\
+                      var a;\n\
+                      { let a <- x + b };\n\
+                      function f(); { return call g() };\n\
+                      function g(); { return 1 };\n\
+                      {\n\
+                      let a <- m[1] + a[2];\n\
+                      let m[0][1] <- call p(a);\n\
+                      call p(1, 2);\n\
+                      call q;\n\
+                      return a\n\
+                      }.";
+        let errors = compile_source(source, 2).unwrap_err();
+        let errors = errors.lines().collect::<Vec<&str>>();
+
+        assert_eq!(
+            errors,
+            vec![
+                "6:16: 'b' is not declared",
+                "10:10: array 'm' has 2 dimension(s) but 1 index(es) were given",
+                "10:17: 'a' is not an array",
+                "11:21: 'p' does not return a value",
+                "12:6: function 'p' takes 1 argument(s) but 2 were given",
+                "13:6: function 'q' is not declared",
+                "14:1: 'return' can only be used in a function",
+            ]
+        );
     }
+
     #[test]
-    fn test_018() {
-        run_file(String::from("test018"));
+    fn test_ir_text_round_trip() {
+        for source in &[include_str!("../Testing/test007.txt"), include_str!("../Testing/test013.txt")] {
+            let mut outputs = Vec::new();
+            let passes = PassPipeline::for_level(2).unwrap();
+            let program = compile_stages(source, &passes, &[Emit::Ir, Emit::After("prepare"), Emit::After("map-blocks")], &mut outputs).unwrap();
+            let expected = execute(&program, &[]);
+
+            for output in outputs {
+                let mut irgm = from_text(&output.text).unwrap();
+                assert_eq!(to_text(&mut irgm), output.text);
+
+                let program = compile_ir_stages(&output.text, &passes, &[], &mut Vec::new()).unwrap();
+                assert_eq!(execute(&program, &[]), expected);
+            }
+        }
+
+        // Every block a dump names is in it, after any of the passes.
+        let mut stages = vec![Emit::Ir];
+        stages.extend(pass_names().iter().map(|pass| Emit::After(pass)));
+        let passes = PassPipeline::for_level(2).unwrap();
+        for path in testing_programs() {
+            let mut outputs = Vec::new();
+            let _ = compile_stages(&fs::read_to_string(&path).unwrap(), &passes, &stages, &mut outputs);
+            for output in outputs {
+                let name = format!("{:?} {}", path.file_stem().unwrap(), output.stage.name());
+                match from_text(&output.text) {
+                    Ok(mut irgm) => assert_eq!(to_text(&mut irgm), output.text, "{}", name),
+                    Err(error) => panic!("{}: {}", name, error),
+                }
+            }
+        }
     }
+
     #[test]
-    fn test_019() {
-        run_file(String::from("test019"));
+    fn test_ir_text_by_hand() {
+        // Sums 0..3 in a loop and squares the result in a function.
+        let text = "main:\n\
+                    \x20 b0 entrance -> b1\n\
+                    \x20 b1 main_node -> b2\n\
+                    \x20 b2 while_loop_header -> b3 b4\n\
+                    \x20   (1): phi (5) #0\n\
+                    \x20   (2): phi (4) #0\n\
+                    \x20   (3): cmp (1) #4\n\
+                    \x20   (6): bge (3) [b4]\n\
+                    \x20 b3 while_node -> b2\n\
+                    \x20   (4): add (2) (1)\n\
+                    \x20   (5): add (1) #1\n\
+                    \x20   (7): bra [b2]\n\
+                    \x20 b4 bra_node -> b5\n\
+                    \x20   (8): add &SP &arg0\n\
+                    \x20   (9): store (2) (8)   ; argument of square\n\
+                    \x20   (10): call square\n\
+                    \x20   (11): write (10)\n\
+                    \x20   (12): ret #0\n\
+                    \x20 b5 exit\n\
+                    \n\
+                    function square(x):\n\
+                    \x20 b6 entrance -> b7\n\
+                    \x20 b7 function_head -> b8\n\
+                    \x20   (13): add &FP &x\n\
+                    \x20   (14): load (13)\n\
+                    \x20   (15): mul (14) (14)\n\
+                    \x20   (16): ret (15)\n\
+                    \x20 b8 exit\n";
+        for opt_level in 0..3 {
+            let passes = PassPipeline::for_level(opt_level).unwrap();
+            let program = compile_ir_stages(text, &passes, &[], &mut Vec::new()).unwrap();
+            assert_eq!(execute(&program, &[]), "36  ");
+        }
+
+        let error = from_text(&text.replace("mul (14) (14)", "mul (14) (99)")).err().unwrap();
+        assert_eq!(error.to_string(), "line 26: unknown instruction (99)");
+        let error = from_text(&text.replace("(5): add (1) #1", "(5): add (1)")).err().unwrap();
+        assert_eq!(error.to_string(), "line 11: add takes 2 operand(s), found 1");
+        let error = from_text(&text.replace("b3 while_node", "b3 loop_body")).err().unwrap();
+        assert_eq!(error.to_string(), "line 9: unknown block type 'loop_body'");
+        let error = from_text(&text.replace("b1 main_node -> b2", "b1 main_node -> b1")).err().unwrap();
+        assert_eq!(error.to_string(), "line 2: b0: no exit can be reached from here");
+        let error = from_text(&text.replace("b3 while_node -> b2", "b3 while_node -> b2 b4")).err().unwrap();
+        assert_eq!(error.to_string(), "line 9: b3: while_node block has 2 successor(s) instead of 1");
+        let error = from_text(&text.replace("(6): bge (3) [b4]", "(6): bge (3) [b9]")).err().unwrap();
+        assert_eq!(error.to_string(), "line 8: unknown block b9");
+        let error = from_text(&text.replace("b2 while_loop_header", "b2 while_loop_header <- b1 b9")).err().unwrap();
+        assert_eq!(error.to_string(), "line 4: unknown block b9");
     }
+
     #[test]
-    fn test_020() {
-        run_file(String::from("test020"));
+    fn test_interpret_stages() {
+        let cases: [(&str, &[i32]); 3] = [
+            (include_str!("../Testing/factorial.txt"), &[5]),
+            (include_str!("../Testing/test002.txt"), &[]),
+            (include_str!("../Testing/test013.txt"), &[]),
+        ];
+        for (source, input) in cases.iter() {
+            let program = compile_source(source, 2).unwrap();
+            let expected = execute(&program, input);
+
+            let runs = interpret_stages(source, &PassPipeline::for_level(2).unwrap(), input);
+            assert_eq!(runs.len(), 9);
+            for (_, run) in runs {
+                assert_eq!((run.output, run.stop), (expected.clone(), None));
+            }
+        }
     }
+
     #[test]
-    fn test_021() {
-        run_file(String::from("test021"));
+    fn test_interpret_by_hand() {
+        // Reads n and writes n / (n - 3) for each n until it reads 0.
+        let text = "main:\n\
+                    \x20 b0 entrance -> b1\n\
+                    \x20 b1 main_node -> b2\n\
+                    \x20 b2 while_loop_header -> b3 b4\n\
+                    \x20   (1): read\n\
+                    \x20   (2): cmp (1) #0\n\
+                    \x20   (3): beq (2) [b4]\n\
+                    \x20 b3 while_node -> b2\n\
+                    \x20   (4): sub (1) #3\n\
+                    \x20   (5): div (1) (4)\n\
+                    \x20   (6): write (5)\n\
+                    \x20   (7): bra [b2]\n\
+                    \x20 b4 bra_node -> b5\n\
+                    \x20   (8): writeNL\n\
+                    \x20   (9): end\n\
+                    \x20 b5 exit\n";
+        let mut irgm = from_text(text).unwrap();
+
+        let mut io = BufferedIo::new(&[6, -3, 0]);
+        assert_eq!(interpret(&mut irgm, &mut io), Ok(()));
+        assert_eq!(io.output(), "2  0  \n");
+
+        let mut io = BufferedIo::new(&[6, 3, 0]);
+        let error = interpret(&mut irgm, &mut io).err().unwrap();
+        assert_eq!(error.to_string(), "b3 (5): division by zero");
+        assert_eq!(io.output(), "2  ");
+
+        let mut io = BufferedIo::new(&[6]);
+        let error = interpret(&mut irgm, &mut io).err().unwrap();
+        assert_eq!(error.to_string(), "b2 (1): read found no more input");
+
+        let mut interpreter = Interpreter::new(&mut irgm);
+        interpreter.set_instruction_budget(Some(10));
+        let error = interpreter.run(&mut BufferedIo::new(&[6, 6, 6, 6, 0])).err().unwrap();
+        assert_eq!(error.to_string(), "b3 (4): stopped after 10 instructions");
     }
+
     #[test]
-    fn test_022() {
-        run_file(String::from("test022"));
+    fn test_interpret_source() {
+        let cases: [(&str, &[i32]); 4] = [
+            (include_str!("../Testing/factorial.txt"), &[5]),
+            (include_str!("../Testing/test002.txt"), &[]),
+            (include_str!("../Testing/test013.txt"), &[]),
+            (include_str!("../Testing/cell.txt"), &[]),
+        ];
+        for (source, input) in cases.iter() {
+            let program = compile_source(source, 2).unwrap();
+            let expected = execute(&program, input);
+
+            assert_eq!(interpret_source(source, input), Ok(expected));
+        }
     }
+
     #[test]
-    fn test_023() {
-        run_file(String::from("test023"));
+    fn test_interpret_source_by_hand() {
+        let source = "main\n\
+                      var n; array[3][2] a;\n\
+                      function sum(x, y); { return x + y };\n\
+                      function fill(k);\n\
+                      var i;\n\
+                      { let i <- 0; while i < 3 do let a[i][k] <- i * 10 - k; let i <- i + 1 od };\n\
+                      {\n\
+                      \x20 call fill(0); call fill(1);\n\
+                      \x20 let n <- call InputNum();\n\
+                      \x20 call OutputNum(call sum(a[2][1], a[1][0]) / n);\n\
+                      \x20 call OutputNum(a[n][0]);\n\
+                      \x20 call OutputNewLine()\n\
+                      }.\n";
+        assert_eq!(interpret_source(source, &[2]), Ok(String::from("14  20  \n")));
+        assert_eq!(interpret_source(source, &[0]), Err(String::from("10:47: division by zero")));
+        assert_eq!(
+            interpret_source(source, &[5]),
+            Err(String::from("11:20: index 5 is out of bounds for 'a' (size 3)"))
+        );
+        assert_eq!(interpret_source(source, &[]), Err(String::from("9:12: read found no more input")));
+
+        let source = "main var i; { let i <- 1; while i > 0 do let i <- i + 1 od }.";
+        let comp = parse(&mut TokenCollection::collect(&mut SourceIter::new(source))).unwrap();
+        let mut evaluator = Evaluator::new(&comp);
+        evaluator.set_step_budget(Some(100));
+        let error = evaluator.run(&mut BufferedIo::new(&[])).err().unwrap();
+        assert_eq!(error.to_string(), "1:42: stopped after 100 steps");
+
+        // Recursion as deep as the DLX stack allows runs like it does
+        // there, only recursion the DLX runs out of memory for is cut off.
+        let depth = |n: i32| {
+            format!(
+                "main function depth(n); {{ if n == 0 then return 0 fi; return call depth(n - 1) + 1 }};\n\
+                 {{ call OutputNum(call depth({})) }}.",
+                n
+            )
+        };
+        let program = compile_source(&depth(300), 2).unwrap();
+        assert_eq!(interpret_source(&depth(300), &[]), Ok(execute(&program, &[])));
+        assert_eq!(interpret_source(&depth(499), &[]), Ok(String::from("499  ")));
+        assert_eq!(
+            interpret_source(&depth(500), &[]),
+            Err(String::from("1:62: calls nested more than 500 deep"))
+        );
+        let program = compile_source(&depth(500), 2).unwrap();
+        let mut dlx = DLX::new();
+        dlx.load(&program).unwrap();
+        assert!(dlx.execute().is_err());
     }
+
     #[test]
-    fn test_024() {
-        run_file(String::from("test024"));
+    fn test_check_stages() {
+        // Programs whose globals do not fit the memory of the DLX, so only
+        // the stages before code generation run them.
+        let too_big = ["test020"];
+
+        let passes = PassPipeline::for_level(2).unwrap();
+        let mut divergences = Vec::new();
+        for path in testing_programs() {
+            let name = path.file_stem().unwrap().to_str().unwrap().to_owned();
+            if ENDLESS.contains(&name.as_str()) {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            match check_stages(&source, &passes, &golden_input(&path)) {
+                Err(ref divergence)
+                    if too_big.contains(&name.as_str())
+                        && divergence.stage == Emit::Program
+                        && divergence.found.stop.as_ref().map(|(kind, _)| *kind) == Some(StopKind::CompileError) => {}
+                Err(divergence) => divergences.push(format!("{}: {}", name, divergence)),
+                Ok(()) if too_big.contains(&name.as_str()) => divergences.push(format!("{}: compiled", name)),
+                Ok(()) => {}
+            }
+        }
+        assert!(divergences.is_empty(), "\n{}", divergences.join("\n"));
+
+        // What was written before a program stops counts as well.
+        let source = "main var n; { call OutputNum(7); let n <- call InputNum(); call OutputNum(1 / n) }.";
+        assert_eq!(check_stages(source, &passes, &[0]), Ok(()));
+        assert_eq!(check_stages(source, &passes, &[]), Ok(()));
+
+        // Only runs that both ran out of budget are compared by prefix, a
+        // hang or a failed compile does not pass for the start of the output.
+        let finished = Run {
+            output: String::from("1  2  "),
+            stop: None,
+        };
+        let stopped = |output: &str, kind: StopKind| Run {
+            output: output.to_owned(),
+            stop: Some((kind, String::new())),
+        };
+        assert!(!finished.matches(&stopped("", StopKind::BudgetExhausted)));
+        assert!(!stopped("1  2  ", StopKind::BudgetExhausted).matches(&finished));
+        assert!(stopped("1  2  ", StopKind::BudgetExhausted).matches(&stopped("1  ", StopKind::BudgetExhausted)));
+        assert!(stopped("1  ", StopKind::BudgetExhausted).matches(&stopped("1  2  ", StopKind::BudgetExhausted)));
+        assert!(!stopped("", StopKind::IndexOutOfBounds).matches(&stopped("", StopKind::Other)));
+        assert!(!stopped("", StopKind::IndexOutOfBounds).matches(&stopped("", StopKind::CompileError)));
+        assert!(stopped("1  ", StopKind::IndexOutOfBounds).matches(&finished));
     }
+
     #[test]
-    fn test_024_a() {
-        run_file(String::from("test024_a"));
+    fn test_pass_pipeline() {
+        assert!(PassPipeline::for_level(3).is_err());
+        assert!(!PassPipeline::for_level(0).unwrap().is_enabled("cse"));
+        assert_eq!(
+            PassPipeline::for_level(2).unwrap().disable("cse").unwrap().enable("cse").unwrap(),
+            PassPipeline::for_level(2).unwrap()
+        );
+        assert_eq!(
+            PassPipeline::new().enable("gvn").err().unwrap(),
+            "Unknown pass 'gvn', the passes are prepare, const-eval, sccp, cse, dce, licm, strength-reduce, map-blocks."
+        );
+        assert!(PassPipeline::new().disable("prepare").is_err());
+
+        let args = ["prog.txt", "--disable", "dce,cse", "-O2", "--enable", "dce", "--fixpoint"];
+        let options = parse_args(args.iter().map(|arg| arg.to_string()).collect()).unwrap();
+        let expected = PassPipeline::for_level(1)
+            .unwrap()
+            .enable("dce")
+            .unwrap()
+            .enable("licm")
+            .unwrap()
+            .enable("strength-reduce")
+            .unwrap()
+            .repeat_until_fixpoint(DEFAULT_FIXPOINT_ROUNDS);
+        assert_eq!(options.passes, expected);
+        assert!(parse_args(vec![String::from("a.txt"), String::from("--disable"), String::from("map-blocks")]).is_err());
+
+        // Repeating the passes does not change what the programs do.
+        let fixpoint = PassPipeline::for_level(2).unwrap().repeat_until_fixpoint(DEFAULT_FIXPOINT_ROUNDS);
+        for name in ["conditional_call", "factorial", "test002", "test007", "test013"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/Testing").join(format!("{}.txt", name));
+            let source = fs::read_to_string(&path).unwrap();
+            assert_eq!(check_stages(&source, &fixpoint, &golden_input(&path)), Ok(()), "{}", name);
+        }
     }
+
     #[test]
-    fn test_024_b() {
-        run_file(String::from("test024_b"));
+    fn test_sccp() {
+        // Only 0 reaches the loop phi of i, so the loop is never entered and
+        // the branch on i is decided once the loop is gone.
+        let source = "main var i, j; {
+            let i <- 0;
+            while i < 0 do let i <- i + 1 od;
+            if i == 0 then let j <- 4 else let j <- 5 fi;
+            call OutputNum(j)
+        }.";
+        let passes = PassPipeline::for_level(1).unwrap();
+        let mut outputs = Vec::new();
+        let program = compile_stages(source, &passes, &[Emit::After("const-eval"), Emit::After("sccp")], &mut outputs).unwrap();
+
+        assert!(outputs[0].text.contains(": phi "));
+        assert!(!outputs[1].text.contains(": phi "));
+        assert!(!outputs[1].text.contains(": cmp "));
+
+        assert_eq!(execute(&program, &[]), "4  ");
+        assert!(check_stages(source, &passes, &[]).is_ok());
+
+        // A decided branch takes its comparison and what only the
+        // comparison read along.
+        let source = "main var i, j; { let i <- call InputNum(); let j <- 3; if j < 4 then let i <- i + 1 fi; call OutputNum(i) }.";
+        let passes = passes.disable("const-eval").unwrap();
+        let mut outputs = Vec::new();
+        compile_stages(source, &passes, &[Emit::After("prepare"), Emit::After("sccp")], &mut outputs).unwrap();
+        assert!(outputs[0].text.contains(": cmp (8) #4"));
+        assert!(!outputs[1].text.contains(": cmp "));
+        assert!(!outputs[1].text.contains("(8): "));
     }
+
     #[test]
-    fn test_025() {
-        run_file(String::from("test025"));
+    fn test_licm() {
+        // The bound and the array base move in front of the loop, the
+        // division by n stays since it could trap when the body never runs.
+        let source = "main var i, n, a; array[10] x; {
+            let n <- call InputNum();
+            let i <- 0;
+            while i < n * 2 + 1 do
+                let a <- 100 / n;
+                let x[i] <- i * n + a;
+                let i <- i + 1
+            od;
+            call OutputNum(x[3])
+        }.";
+        let passes = PassPipeline::for_level(2).unwrap();
+        let mut outputs = Vec::new();
+        let program = compile_stages(source, &passes, &[Emit::After("licm")], &mut outputs).unwrap();
+
+        let preheader = outputs[0]
+            .text
+            .split("\n  b")
+            .find(|block| block.contains("loop_preheader"))
+            .unwrap();
+        assert!(preheader.contains(": mul "));
+        assert!(preheader.contains(": add &"));
+        assert!(!preheader.contains(": div "));
+
+        assert_eq!(execute(&program, &[3]), "42  ");
+        assert!(check_stages(source, &passes, &[3]).is_ok());
     }
+
     #[test]
-    fn test_025_a() {
-        run_file(String::from("test025_a"));
+    fn test_spill_phi_operand() {
+        // Once licm has moved code in front of their loops these programs
+        // spill values a loop header phi takes over the back edge, which
+        // were reloaded on the way into the loop instead of at the end of
+        // its body.
+        let passes = PassPipeline::for_level(2).unwrap();
+        for seed in [1, 45] {
+            let program = generate(seed);
+            assert!(check_stages(&program.source, &passes, &program.input).is_ok(), "seed {}", seed);
+        }
     }
+
     #[test]
-    fn test_026() {
-        run_file(String::from("test026"));
+    fn test_strength_reduction() {
+        // Every multiplication by i becomes a phi stepped along with it,
+        // and i itself goes once the exit test compares one of those.
+        let source = "main var i, s; array[10] x; {
+            let i <- 0;
+            while i < 10 do
+                let x[i] <- i * 3 + 1;
+                let i <- i + 1
+            od;
+            let s <- 0;
+            let i <- 0;
+            while i < 10 do
+                let s <- s + x[i];
+                let i <- i + 1
+            od;
+            call OutputNum(s)
+        }.";
+        let passes = PassPipeline::for_level(2).unwrap();
+        let mut outputs = Vec::new();
+        let program = compile_stages(source, &passes, &[Emit::After("licm"), Emit::After("strength-reduce")], &mut outputs).unwrap();
+
+        assert!(outputs[0].text.contains(": mul "));
+        assert!(!outputs[1].text.contains(": mul "));
+        let compares = outputs[1]
+            .text
+            .lines()
+            .filter(|line| line.contains(": cmp "))
+            .map(|line| line.rsplit(' ').next().unwrap())
+            .collect::<Vec<&str>>();
+        assert_eq!(compares, ["#30", "#40"]);
+
+        assert_eq!(execute(&program, &[]), "145  ");
+        assert!(check_stages(source, &passes, &[]).is_ok());
     }
+
     #[test]
-    fn test_027() {
-        run_file(String::from("test027"));
+    fn test_stats() {
+        let passes = PassPipeline::for_level(2).unwrap();
+        let mut outputs = Vec::new();
+        let source = include_str!("../Testing/test013.txt");
+        compile_stages(source, &passes, &[Emit::Stats, Emit::StatsJson], &mut outputs).unwrap();
+
+        let table = outputs[0].text.lines().collect::<Vec<&str>>();
+        assert!(table[0].starts_with("pass             function  runs  folded  replaced  moved  deactivated  removed"));
+        let row = |pass: &str, func_name: &str| {
+            let line = table
+                .iter()
+                .find(|line| line.split_whitespace().take(2).eq([pass, func_name].iter().cloned()))
+                .unwrap();
+            line.split_whitespace().skip(2).map(|cell| cell.parse().unwrap()).collect::<Vec<u128>>()
+        };
+        // runs, folded, replaced, moved, deactivated, removed, blocks, spills
+        assert_eq!(row("const-eval", "foo")[..8], [1, 3, 7, 0, 46, 49, 8, 0]);
+        assert_eq!(row("cse", "foo")[..8], [1, 0, 2, 0, 2, 2, 0, 0]);
+        assert_eq!(row("regalloc", "main")[7], 0);
+
+        let json = &outputs[1].text;
+        assert_eq!(json.lines().count(), table.len() + 1);
+        assert!(json.contains(
+            "{\"pass\": \"const-eval\", \"function\": \"foo\", \"runs\": 1, \"folded\": 3, \"replaced\": 7, \"moved\": 0, \
+             \"deactivated\": 46, \"removed\": 49, \"blocks\": 8, \"spills\": 0, \"time_us\": "
+        ));
+
+        // With sccp the else side is gone and nothing is left to spill.
+        let passes = passes.disable("sccp").unwrap();
+        let mut outputs = Vec::new();
+        let source = include_str!("../Testing/test024_b.txt");
+        compile_stages(source, &passes, &[Emit::Stats], &mut outputs).unwrap();
+        let spills = outputs[0].text.lines().find(|line| line.starts_with("regalloc         main")).unwrap();
+        assert!(spills.split_whitespace().nth(9).unwrap().parse::<usize>().unwrap() > 0);
+
+        // The invariants of the loops in bar end up in their preheaders.
+        let passes = PassPipeline::for_level(2).unwrap();
+        let mut outputs = Vec::new();
+        compile_stages(include_str!("../Testing/test002.txt"), &passes, &[Emit::Stats], &mut outputs).unwrap();
+        let licm = outputs[0].text.lines().find(|line| line.starts_with("licm             bar")).unwrap();
+        assert_eq!(licm.split_whitespace().nth(5), Some("3"));
     }
+
     #[test]
-    fn test_028() {
-        run_file(String::from("test028"));
+    fn test_generator() {
+        assert_eq!(generate(7), generate(7));
+        assert_ne!(generate(7).source, generate(8).source);
+
+        let mut sources = String::new();
+        for seed in 0..40 {
+            let program = generate(seed);
+            assert!(
+                interpret_source(&program.source, &program.input).is_ok(),
+                "seed {} does not run:\n{}",
+                seed,
+                program.source
+            );
+            sources.push_str(&program.source);
+        }
+        for feature in ["while", "if", "else", "array", "function", "procedure", "return", "InputNum", "OutputNum", "/"] {
+            assert!(sources.contains(feature), "no program uses {}", feature);
+        }
     }
+
     #[test]
-    fn test_029() {
-        run_file(String::from("test029"));
+    fn test_fuzz() {
+        let divergences = fuzz(0..500, &PassPipeline::for_level(2).unwrap())
+            .iter()
+            .map(|(program, divergence)| format!("seed {}: {}", program.seed, divergence))
+            .collect::<Vec<String>>();
+        assert!(divergences.is_empty(), "\n{}", divergences.join("\n"));
     }
+
     #[test]
-    fn test_030() {
-        run_file(String::from("test030"));
+    fn test_verify() {
+        // Every program holds up after each stage, whether or not the
+        // compiler gets through code generation with it.
+        let mut violations = Vec::new();
+        for path in testing_programs() {
+            let name = path.file_stem().unwrap().to_str().unwrap().to_owned();
+            let source = fs::read_to_string(&path).unwrap();
+            for opt_level in 0..3 {
+                let mut outputs = Vec::new();
+                let passes = PassPipeline::for_level(opt_level).unwrap();
+                let _ = compile_stages(&source, &passes, &[Emit::Verify], &mut outputs);
+                for output in outputs {
+                    for line in output.text.lines().filter(|line| !line.ends_with(": ok")) {
+                        violations.push(format!("{} -O{}: {}", name, opt_level, line));
+                    }
+                }
+            }
+        }
+        assert!(violations.is_empty(), "\n{}", violations.join("\n"));
+
+        let text = "main:\n\
+                    \x20 b0 entrance -> b1\n\
+                    \x20 b1 main_node -> b2\n\
+                    \x20 b2 while_loop_header -> b3 b4\n\
+                    \x20   (1): phi (5) #0\n\
+                    \x20   (2): cmp (1) #4\n\
+                    \x20   (3): bge (2) [b4]\n\
+                    \x20 b3 while_node -> b2\n\
+                    \x20   (4): add (1) #1\n\
+                    \x20   (5): add (4) #1\n\
+                    \x20   (6): bra [b2]\n\
+                    \x20 b4 bra_node -> b5\n\
+                    \x20   (7): write (1)\n\
+                    \x20   (8): end\n\
+                    \x20 b5 exit\n";
+        assert_eq!(verify(&mut from_text(text).unwrap()), Ok(()));
+
+        let broken = text
+            .replace("(4): add (1) #1", "(4): add (1) (7)")
+            .replace("(5): add (4) #1", "(5): add (9) #1")
+            .replace("(6): bra [b2]", "(6): bra [b4]")
+            .replace("(8): end", "(9): phi (1) #0\n\x20   (8): end");
+        let errors = verify(&mut from_text(&broken).unwrap())
+            .err()
+            .unwrap()
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            errors,
+            vec![
+                "b4 (9): phi has 2 operand(s) but the block has 1 predecessor(s)",
+                "b3 (4): uses (7) which does not dominate it",
+                "b3 (5): uses (9) which does not dominate it",
+                "b3 (6): branches to b4 which does not follow this block",
+            ]
+        );
     }
+
     #[test]
-    fn test_031() {
-        run_file(String::from("test031"));
+    fn test_driver_bad_args() {
+        assert!(parse_args(vec![]).is_err());
+        assert!(parse_args(vec![String::from("a.txt"), String::from("b.txt")]).is_err());
+        assert!(parse_args(vec![String::from("a.txt"), String::from("-o")]).is_err());
+        assert!(parse_args(vec![String::from("a.txt"), String::from("--emit"), String::from("bogus")]).is_err());
+        assert!(parse_args(vec![String::from("a.txt"), String::from("--emit"), String::from("ir=")]).is_err());
+        assert_eq!(run(vec![String::from("--bogus")]), EXIT_USAGE_ERROR);
     }
 }
 
@@ -236,20 +1078,18 @@ pub fn run_file(file_name: String) {
     let mut buffer = String::new();
     let result = BufReader::new(file).read_to_string(&mut buffer);
 
-    let mut char_iter = buffer.chars().peekable();
-    let mut read_iter = char_iter.clone();
+    let mut char_iter = Lexer::source::SourceIter::new(&buffer);
+    let mut read_iter = Lexer::source::SourceIter::new(&buffer);
 
     let mut tokens = Lexer::token::TokenCollection::collect(&mut read_iter);
     let mut tc = Lexer::token::TokenCollection::collect(&mut char_iter);
 
-    let comp = Parser::AST::computation::Comp::new(&mut tc);
+    let comp = Parser::parse(&mut tc).expect("Syntax error in test program.");
+    Semantic::check(&comp).expect("Semantic error in test program.");
     let mut irgmanager = comp.to_ir();
 
     let mut optimizer = Optimizer::Optimizer::new(irgmanager);
-    optimizer.pass_0();
-    optimizer.pass_1();
-    optimizer.pass_2();
-    optimizer.pass_3();
+    PassPipeline::for_level(2).unwrap().run(&mut optimizer, |_, _| {});
 
     //clean_graph(&mut irgmanager);
     //Optimizer::constant_evaluation::eval_program_constants(&mut irgmanager);
@@ -302,6 +1142,26 @@ pub fn run_file(file_name: String) {
     println!();
 }
 
+/// Runs the full pipeline (including register allocation) on a file from
+/// src/Testing and returns the generated DLX program.
+pub fn compile_file(file_name: String) -> std::result::Result<Vec<i32>, String> {
+    let mut path = PathBuf::new();
+    path.push(env::current_exe().unwrap());
+    path.pop();
+    path.pop();
+    path.pop();
+    path.pop(); //this is needed because current .exe is 4 folders too deep.
+    path.push("src/Testing/");
+    path.push(file_name + ".txt");
+
+    let file = fs::File::open(path.as_path()).expect("Error Opening File.");
+
+    let mut buffer = String::new();
+    BufReader::new(file).read_to_string(&mut buffer);
+
+    Driver::pipeline::compile_source(&buffer, 2)
+}
+
 pub fn clean_base_values(irgm: &mut IRGraphManager) {
     for node in irgm.graph_manager().get_mut_ref_graph().node_weights_mut() {
         for inst in node.get_mut_data_ref().get_mut_inst_list_ref() {
@@ -554,7 +1414,7 @@ pub fn extract_constants(irgm: &mut IRGraphManager, root_id: NodeIndex) {
                 InstTy::add,
                 parent_block_id
             );
-            let inst_val = irgm.graph_manager().add_instruction_in_node(add_inst, &parent_node_id);
+            let inst_val = add_before_branch(irgm, add_inst, parent_node_id);
 
             inst_clone.borrow_mut().update_x_val(inst_val);
         }
@@ -577,7 +1437,7 @@ pub fn extract_constants(irgm: &mut IRGraphManager, root_id: NodeIndex) {
                 parent_block_id
             );
 
-            let inst_val = irgm.graph_manager().add_instruction_in_node(add_inst, &parent_node_id);
+            let inst_val = add_before_branch(irgm, add_inst, parent_node_id);
 
             inst_clone.borrow_mut().update_y_val(inst_val);
         }
@@ -585,3 +1445,25 @@ pub fn extract_constants(irgm: &mut IRGraphManager, root_id: NodeIndex) {
 
 
 }
+
+/// Adds `inst` at the end of a phi predecessor, ahead of the branch that
+/// leaves it so the block still ends on its branch.
+fn add_before_branch(irgm: &mut IRGraphManager, inst: Op, node_id: NodeIndex) -> Value {
+    let branch_position = {
+        let inst_list = irgm.graph_manager_ref()
+            .get_ref_graph()
+            .node_weight(node_id)
+            .unwrap()
+            .get_data_ref()
+            .get_inst_list_ref();
+        match inst_list.last() {
+            Some(last) if is_branch(last.borrow().inst_type()) => Some(inst_list.len() - 1),
+            _ => None,
+        }
+    };
+
+    match branch_position {
+        Some(position) => irgm.graph_manager().insert_instruction_in_node(position, inst, &node_id),
+        None => irgm.graph_manager().add_instruction_in_node(inst, &node_id),
+    }
+}